@@ -9,10 +9,9 @@ use ABC_Game_Engine::{EntitiesAndComponents, System};
 use ABC_Game_Engine::{KeyCode, KeyState};
 use ABC_lumenpyx::lights;
 use ABC_lumenpyx::lights::PointLight;
-use ABC_lumenpyx::primitives::BlendComponent;
 use ABC_lumenpyx::primitives::Circle;
+use ABC_lumenpyx::primitives::MaskComponent;
 use ABC_lumenpyx::primitives::Rectangle;
-use ABC_lumenpyx::BlendMode;
 use ABC_lumenpyx::LumenpyxEventLoop;
 use ABC_lumenpyx::LumenpyxProgram;
 use ABC_lumenpyx::RenderSettings;
@@ -127,11 +126,6 @@ fn main() {
             background_transform,
         ));
 
-        let blend_parent = entities_and_components.add_entity_with((
-            BlendComponent::new(BlendMode::Subtractive),
-            ABC_Game_Engine::Transform::default(),
-        ));
-
         let circle_transform = Transform {
             x: 0.0,
             y: 0.0,
@@ -141,12 +135,19 @@ fn main() {
 
         let circle_child = entities_and_components
             .add_entity_with((Circle::new([1.0, 1.0, 1.0, 1.0], 5.0), circle_transform));
-        entities_and_components.set_parent(circle_child, blend_parent);
+
+        // the circle is the mask, inverted so it cuts a hole instead of being the only part shown
+        let mask_parent = entities_and_components.add_entity_with((
+            MaskComponent::from_entity(circle_child).with_invert(true),
+            ABC_Game_Engine::Transform::default(),
+        ));
+
+        entities_and_components.set_parent(circle_child, mask_parent);
         let rect_child = entities_and_components.add_entity_with((
             Rectangle::new([1.0, 1.0, 1.0, 1.0], 128.0, 128.0),
             Transform::default(),
         ));
-        entities_and_components.set_parent(rect_child, blend_parent);
+        entities_and_components.set_parent(rect_child, mask_parent);
 
         // make a camera, to specify the position we would like to view everything from
         entities_and_components
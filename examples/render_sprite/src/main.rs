@@ -1,9 +1,9 @@
 use ABC_Game_Engine::DeltaTime;
-use ABC_Game_Engine::Input;
+use ABC_Game_Engine::KeyCode;
 use ABC_Game_Engine::Scene;
 use ABC_Game_Engine::Transform;
 use ABC_Game_Engine::{EntitiesAndComponents, System};
-use ABC_Game_Engine::{KeyCode, KeyState};
+use ABC_lumenpyx::input::actions::{ActionMap, Binding};
 use ABC_lumenpyx::lights;
 use ABC_lumenpyx::primitives::Circle;
 use ABC_lumenpyx::primitives::TextBox;
@@ -33,24 +33,14 @@ impl System for CircleMovementSystem {
         let mut movement_dir: [f64; 2] = [0.0, 0.0];
         let delta_time;
         {
-            let input = entities_and_components.get_resource::<Input>().unwrap();
+            let actions = entities_and_components.get_resource::<ActionMap>().unwrap();
             delta_time = entities_and_components
                 .get_resource::<DeltaTime>()
                 .unwrap()
                 .get_delta_time();
 
-            if input.get_key_state(KeyCode::W) == KeyState::Held {
-                movement_dir[1] += 1.0;
-            }
-            if input.get_key_state(KeyCode::S) == KeyState::Held {
-                movement_dir[1] += -1.0;
-            }
-            if input.get_key_state(KeyCode::A) == KeyState::Held {
-                movement_dir[0] += -1.0;
-            }
-            if input.get_key_state(KeyCode::D) == KeyState::Held {
-                movement_dir[0] += 1.0;
-            }
+            movement_dir[0] = actions.get_axis("move_x") as f64;
+            movement_dir[1] = actions.get_axis("move_y") as f64;
 
             let magnitude = (movement_dir[0].powi(2) + movement_dir[1].powi(2)).sqrt();
 
@@ -98,6 +88,13 @@ fn main() {
         let lumenpyx_program = entities_and_components
             .get_resource_mut::<LumenpyxProgram>()
             .unwrap();
+
+        // the keys could also be loaded from a config file with ActionMap::load
+        let actions = entities_and_components
+            .get_resource_mut::<ActionMap>()
+            .unwrap();
+        actions.bind_axis("move_x", Binding::Key(KeyCode::A), Binding::Key(KeyCode::D));
+        actions.bind_axis("move_y", Binding::Key(KeyCode::S), Binding::Key(KeyCode::W));
     }
 
     //scene.world.add_system(CameraMovementSystem);
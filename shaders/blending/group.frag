@@ -0,0 +1,20 @@
+#version 140
+
+in vec2 v_tex_coords;
+out vec4 color;
+
+uniform sampler2D layer;
+uniform float opacity;
+uniform bool multiply;
+
+void main() {
+    vec4 layer_color = texture(layer, v_tex_coords);
+    float alpha = layer_color.a * opacity;
+
+    if (multiply) {
+        // the blending multiplies what's below by this, so it fades to white instead of to transparent
+        color = vec4(mix(vec3(1.0), layer_color.rgb, alpha), alpha);
+    } else {
+        color = vec4(layer_color.rgb, alpha);
+    }
+}
@@ -0,0 +1,51 @@
+#version 140
+
+in vec2 v_tex_coords;
+out vec4 color;
+
+uniform sampler2D content;
+uniform sampler2D mask;
+uniform vec2 resolution;
+uniform bool use_clip_rect;
+// left, bottom, right, top in texture coordinates
+uniform vec4 clip_rect;
+// how far the edge of the mask fades out, in pixels
+uniform float softness;
+uniform bool invert;
+
+const int SAMPLES = 16;
+const float GOLDEN_ANGLE = 2.39996323;
+
+void main() {
+    vec4 content_color = texture(content, v_tex_coords);
+
+    float amount;
+    if (use_clip_rect) {
+        // how far inside the rect this pixel is, negative outside of it
+        vec2 pixel = v_tex_coords * resolution;
+        vec2 low = clip_rect.xy * resolution;
+        vec2 high = clip_rect.zw * resolution;
+        float inside = min(min(pixel.x - low.x, high.x - pixel.x), min(pixel.y - low.y, high.y - pixel.y));
+
+        amount = softness > 0.0 ? clamp(inside / softness, 0.0, 1.0) : step(0.0, inside);
+    } else if (softness > 0.0) {
+        // average the mask over a disk so its edge fades out
+        amount = 0.0;
+        for (int i = 0; i < SAMPLES; i++) {
+            float dist = sqrt((float(i) + 0.5) / float(SAMPLES));
+            float angle = float(i) * GOLDEN_ANGLE;
+            vec2 offset = vec2(cos(angle), sin(angle)) * dist * softness / resolution;
+
+            amount += texture(mask, v_tex_coords + offset).a;
+        }
+        amount /= float(SAMPLES);
+    } else {
+        amount = texture(mask, v_tex_coords).a;
+    }
+
+    if (invert) {
+        amount = 1.0 - amount;
+    }
+
+    color = vec4(content_color.rgb, content_color.a * amount);
+}
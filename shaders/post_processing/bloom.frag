@@ -0,0 +1,38 @@
+#version 140
+
+in vec2 v_tex_coords;
+out vec4 color;
+
+uniform sampler2D image;
+uniform vec2 resolution;
+uniform float threshold;
+uniform float intensity;
+uniform float radius;
+
+const int SAMPLES = 32;
+const float GOLDEN_ANGLE = 2.39996323;
+
+void main() {
+    vec4 base = texture(image, v_tex_coords);
+
+    vec3 glow = vec3(0.0);
+    float total_weight = 0.0;
+
+    // sample a spiral around the pixel, only the light above the threshold spreads
+    for (int i = 0; i < SAMPLES; i++) {
+        float dist = sqrt((float(i) + 0.5) / float(SAMPLES));
+        float angle = float(i) * GOLDEN_ANGLE;
+        vec2 offset = vec2(cos(angle), sin(angle)) * dist * radius / resolution;
+
+        vec3 sample_color = texture(image, v_tex_coords + offset).rgb;
+        float weight = 1.0 - dist;
+
+        glow += max(sample_color - vec3(threshold), vec3(0.0)) * weight;
+        total_weight += weight;
+    }
+
+    glow = glow / total_weight * intensity;
+
+    // the glow can spread over pixels that had nothing drawn on them
+    color = vec4(base.rgb + glow, min(base.a + length(glow), 1.0));
+}
@@ -0,0 +1,19 @@
+#version 140
+
+in vec2 v_tex_coords;
+out vec4 color;
+
+uniform sampler2D image;
+uniform vec2 resolution;
+uniform float offset;
+
+void main() {
+    // the channels split more towards the edges, offset is in pixels at the edge
+    vec2 direction = (v_tex_coords - vec2(0.5)) * 2.0 * offset / resolution;
+
+    vec4 base = texture(image, v_tex_coords);
+    float red = texture(image, v_tex_coords + direction).r;
+    float blue = texture(image, v_tex_coords - direction).b;
+
+    color = vec4(red, base.g, blue, base.a);
+}
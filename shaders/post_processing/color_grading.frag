@@ -0,0 +1,32 @@
+#version 140
+
+in vec2 v_tex_coords;
+out vec4 color;
+
+uniform sampler2D image;
+uniform sampler2D lut;
+uniform float lut_size;
+uniform float strength;
+
+void main() {
+    vec4 base = texture(image, v_tex_coords);
+    vec3 clamped = clamp(base.rgb, 0.0, 1.0);
+
+    // the lut is a row of squares, one for each blue value, with red going right and green going down
+    float max_index = lut_size - 1.0;
+    float blue = clamped.b * max_index;
+    float low_slice = floor(blue);
+    float high_slice = min(low_slice + 1.0, max_index);
+
+    // sample the middle of the texels so the squares don't bleed into each other
+    vec2 slice_uv = vec2(
+        (clamped.r * max_index + 0.5) / (lut_size * lut_size),
+        1.0 - (clamped.g * max_index + 0.5) / lut_size
+    );
+
+    vec3 low = texture(lut, slice_uv + vec2(low_slice / lut_size, 0.0)).rgb;
+    vec3 high = texture(lut, slice_uv + vec2(high_slice / lut_size, 0.0)).rgb;
+    vec3 graded = mix(low, high, blue - low_slice);
+
+    color = vec4(mix(base.rgb, graded, strength), base.a);
+}
@@ -0,0 +1,29 @@
+#version 140
+
+in vec2 v_tex_coords;
+out vec4 color;
+
+uniform sampler2D image;
+uniform vec2 resolution;
+uniform float curvature;
+uniform float scanline_strength;
+
+void main() {
+    // bend the image like the glass of a crt
+    vec2 centered = v_tex_coords * 2.0 - 1.0;
+    centered *= 1.0 + curvature * dot(centered, centered);
+    vec2 uv = centered * 0.5 + 0.5;
+
+    if (uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+        color = vec4(0.0, 0.0, 0.0, 1.0);
+        return;
+    }
+
+    vec4 base = texture(image, uv);
+
+    // every other row of pixels is darker
+    float row = floor(uv.y * resolution.y);
+    float scanline = mod(row, 2.0) < 1.0 ? 1.0 : 1.0 - scanline_strength;
+
+    color = vec4(base.rgb * scanline, base.a);
+}
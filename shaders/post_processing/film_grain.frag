@@ -0,0 +1,19 @@
+#version 140
+
+in vec2 v_tex_coords;
+out vec4 color;
+
+uniform sampler2D image;
+uniform vec2 resolution;
+uniform float time;
+uniform float strength;
+
+void main() {
+    vec4 base = texture(image, v_tex_coords);
+
+    // different noise for every pixel and every frame
+    vec2 seed = floor(v_tex_coords * resolution) + fract(time) * 100.0;
+    float noise = fract(sin(dot(seed, vec2(12.9898, 78.233))) * 43758.5453);
+
+    color = vec4(base.rgb + (noise - 0.5) * strength, base.a);
+}
@@ -0,0 +1,42 @@
+#version 140
+
+in vec2 v_tex_coords;
+out vec4 color;
+
+uniform sampler2D image;
+uniform vec2 resolution;
+uniform sampler2D palette;
+uniform int palette_size;
+uniform float dither_strength;
+
+// a 4x4 Bayer matrix, the order the pixels of a pattern turn on in
+const float BAYER[16] = float[16](
+    0.0, 8.0, 2.0, 10.0,
+    12.0, 4.0, 14.0, 6.0,
+    3.0, 11.0, 1.0, 9.0,
+    15.0, 7.0, 13.0, 5.0
+);
+
+void main() {
+    vec4 base = texture(image, v_tex_coords);
+
+    // push the color towards one of its neighbours in the palette, by the pixel's place in the pattern
+    ivec2 pixel = ivec2(mod(floor(v_tex_coords * resolution), 4.0));
+    float threshold = (BAYER[pixel.y * 4 + pixel.x] + 0.5) / 16.0 - 0.5;
+    vec3 wanted = base.rgb + threshold * dither_strength;
+
+    vec3 nearest = base.rgb;
+    float nearest_distance = 1e10;
+    for (int i = 0; i < palette_size; i++) {
+        vec3 palette_color = texelFetch(palette, ivec2(i, 0), 0).rgb;
+        vec3 difference = wanted - palette_color;
+        float distance_squared = dot(difference, difference);
+
+        if (distance_squared < nearest_distance) {
+            nearest_distance = distance_squared;
+            nearest = palette_color;
+        }
+    }
+
+    color = vec4(nearest, base.a);
+}
@@ -0,0 +1,19 @@
+#version 140
+
+in vec2 v_tex_coords;
+out vec4 color;
+
+uniform sampler2D image;
+uniform float strength;
+uniform float radius;
+uniform float softness;
+
+void main() {
+    vec4 base = texture(image, v_tex_coords);
+
+    // 0 in the middle, 1 in the corners
+    float dist = length(v_tex_coords - vec2(0.5)) * 1.41421356;
+    float amount = smoothstep(radius, radius + softness, dist) * strength;
+
+    color = vec4(base.rgb * (1.0 - amount), base.a);
+}
@@ -0,0 +1,11 @@
+#version 140
+
+in vec2 position;
+
+uniform mat4 matrix;
+// from pixels to the -1 to 1 square the matrix places
+uniform float pixel_scale;
+
+void main() {
+    gl_Position = matrix * vec4(position * pixel_scale, 0.0, 1.0);
+}
@@ -0,0 +1,23 @@
+#version 140
+
+in vec2 v_tex_coords;
+flat in vec4 v_normal_transform;
+out vec4 color;
+
+uniform sampler2D image;
+// turn and flip the normals the same way as the tile
+uniform bool is_normal_map;
+
+void main() {
+    vec4 new_color = texture(image, v_tex_coords);
+    if (new_color.a == 0.0) {
+        discard;
+    }
+
+    if (is_normal_map) {
+        // lumenpyx reads the normal buffer as it is, so the normal is turned as it is too
+        new_color.xy = mat2(v_normal_transform) * new_color.xy;
+    }
+
+    color = new_color;
+}
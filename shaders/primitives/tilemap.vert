@@ -0,0 +1,18 @@
+#version 140
+
+in vec2 position;
+in vec2 tex_coords;
+in vec4 normal_transform;
+out vec2 v_tex_coords;
+flat out vec4 v_normal_transform;
+
+uniform mat4 matrix;
+// from pixels to the -1 to 1 square the matrix places
+uniform float pixel_scale;
+
+void main() {
+    v_tex_coords = tex_coords;
+    v_normal_transform = normal_transform;
+
+    gl_Position = matrix * vec4(position * pixel_scale, 0.0, 1.0);
+}
@@ -0,0 +1,760 @@
+//! The asset server, loads the textures for sprites and animations once and shares them
+//! loading the same files again gives back the textures that are already loaded,
+//! and textures are unloaded at the end of the frame after the last `AssetHandle` using them is dropped
+//! sprites and animations from the server hold a handle themselves, so their textures last as long as they do
+//!
+//! missing or broken images are returned as an `AssetError` instead of panicking
+//!
+//! ```ignore
+//! let sprite = Assets::load_sprite(entities_and_components, SpriteSource::new("crate.png"))?;
+//! // the textures are unloaded after the entity and every clone of the sprite are gone
+//! entities_and_components.add_entity_with((sprite, Transform::default()));
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use image::DynamicImage;
+use lumenpyx::primitives::{Normal, NormalInput, Texture, TextureInput};
+use lumenpyx::{TextureHandle, Transform};
+use ABC_Game_Engine::{EntitiesAndComponents, Resource};
+
+use crate::primitives::{Animation, Sprite};
+use crate::LumenpyxProgram;
+
+#[derive(Debug)]
+pub enum AssetError {
+    NotFound(PathBuf),
+    /// the file couldn't be read or decoded as an image
+    Image(PathBuf, image::ImageError),
+    /// the image was read but the texture couldn't be made from it
+    Texture(PathBuf, String),
+    /// like an animation with no frames, or a spritesheet thinner than its frame count
+    Invalid(String),
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::NotFound(path) => write!(f, "{} doesn't exist", path.display()),
+            AssetError::Image(path, error) => {
+                write!(f, "failed to load image {}: {}", path.display(), error)
+            }
+            AssetError::Texture(path, error) => {
+                write!(
+                    f,
+                    "failed to create texture from {}: {}",
+                    path.display(),
+                    error
+                )
+            }
+            AssetError::Invalid(message) => write!(f, "invalid asset: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for AssetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AssetError::Image(_, error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Where the height or roughness texture of a sprite comes from
+#[derive(Clone, Debug)]
+pub enum TextureSource {
+    Path(PathBuf),
+    /// the color everywhere the albedo isn't transparent
+    Solid([f32; 4]),
+}
+
+/// Where the normal map of a sprite comes from
+#[derive(Clone, Debug)]
+pub enum NormalSource {
+    Path(PathBuf),
+    Solid([f32; 4]),
+    /// made from the height texture
+    AutoGenerated,
+}
+
+// colors are compared by their bits so that equal sources always hash the same
+impl PartialEq for TextureSource {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (TextureSource::Path(a), TextureSource::Path(b)) => a == b,
+            (TextureSource::Solid(a), TextureSource::Solid(b)) => {
+                a.map(f32::to_bits) == b.map(f32::to_bits)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for TextureSource {}
+
+impl Hash for TextureSource {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            TextureSource::Path(path) => path.hash(state),
+            TextureSource::Solid(color) => color.map(f32::to_bits).hash(state),
+        }
+    }
+}
+
+impl PartialEq for NormalSource {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (NormalSource::Path(a), NormalSource::Path(b)) => a == b,
+            (NormalSource::Solid(a), NormalSource::Solid(b)) => {
+                a.map(f32::to_bits) == b.map(f32::to_bits)
+            }
+            (NormalSource::AutoGenerated, NormalSource::AutoGenerated) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for NormalSource {}
+
+impl Hash for NormalSource {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            NormalSource::Path(path) => path.hash(state),
+            NormalSource::Solid(color) => color.map(f32::to_bits).hash(state),
+            NormalSource::AutoGenerated => (),
+        }
+    }
+}
+
+/// The files a sprite or animation is loaded from, two sources that are equal share their textures
+/// for animations from images, `walk.png` means `walk1.png`, `walk2.png` and so on, for every path
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SpriteSource {
+    pub albedo: PathBuf,
+    pub height: TextureSource,
+    pub roughness: TextureSource,
+    pub normal: NormalSource,
+}
+
+impl SpriteSource {
+    /// a flat, fully rough sprite with generated normals
+    pub fn new(albedo: impl Into<PathBuf>) -> Self {
+        Self {
+            albedo: albedo.into(),
+            height: TextureSource::Solid([0.0, 0.0, 0.0, 1.0]),
+            roughness: TextureSource::Solid([1.0, 1.0, 1.0, 1.0]),
+            normal: NormalSource::AutoGenerated,
+        }
+    }
+
+    pub fn with_height(mut self, height: TextureSource) -> Self {
+        self.height = height;
+        self
+    }
+
+    pub fn with_roughness(mut self, roughness: TextureSource) -> Self {
+        self.roughness = roughness;
+        self
+    }
+
+    pub fn with_normal(mut self, normal: NormalSource) -> Self {
+        self.normal = normal;
+        self
+    }
+
+    /// the same source with every path written the same way, so `./a.png` and `a.png` share their textures
+    fn normalized(self) -> Self {
+        let texture = |source| match source {
+            TextureSource::Path(path) => TextureSource::Path(normalize_path(&path)),
+            source => source,
+        };
+
+        Self {
+            albedo: normalize_path(&self.albedo),
+            height: texture(self.height),
+            roughness: texture(self.roughness),
+            normal: match self.normal {
+                NormalSource::Path(path) => NormalSource::Path(normalize_path(&path)),
+                normal => normal,
+            },
+        }
+    }
+}
+
+/// an absolute path without `.` or `..` in it
+/// done by hand instead of with `canonicalize`, because animation paths like `walk.png` don't exist themselves
+fn normalize_path(path: &Path) -> PathBuf {
+    let path = match std::env::current_dir() {
+        Ok(directory) if path.is_relative() => directory.join(path),
+        _ => path.to_path_buf(),
+    };
+
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir if normalized.file_name().is_some() => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// how the frames are laid out in the files
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Layout {
+    Single,
+    /// a file per frame
+    Images(usize),
+    /// frames side by side in one file
+    Spritesheet(usize),
+}
+
+impl Layout {
+    fn get_frame_count(&self) -> usize {
+        match self {
+            Layout::Single => 1,
+            Layout::Images(frames) | Layout::Spritesheet(frames) => *frames,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct AssetKey {
+    source: SpriteSource,
+    layout: Layout,
+}
+
+#[derive(Debug)]
+struct LoadedAsset {
+    /// the albedo, height, roughness and normal textures of every frame
+    frames: Vec<[TextureHandle; 4]>,
+}
+
+/// A reference to textures loaded by `Assets`, they stay loaded while any clone of it is around
+/// sprites and animations keep their own, for textures from `load_textures` add it to the entity using them
+#[derive(Clone, Debug)]
+pub struct AssetHandle {
+    asset: Arc<LoadedAsset>,
+}
+
+impl AssetHandle {
+    /// the albedo, height, roughness and normal textures in that order, of the first frame for animations
+    pub fn get_textures(&self) -> [TextureHandle; 4] {
+        self.asset.frames[0]
+    }
+
+    /// the textures of every frame, in the same order as `get_textures`
+    pub fn get_frames(&self) -> &[[TextureHandle; 4]] {
+        &self.asset.frames
+    }
+}
+
+struct AssetEntry {
+    frames: Vec<[TextureHandle; 4]>,
+    /// dead when every handle has been dropped
+    asset: Weak<LoadedAsset>,
+}
+
+/// The asset server resource, keeps track of what's loaded so nothing is loaded twice
+#[derive(Default)]
+pub struct Assets {
+    entries: HashMap<AssetKey, AssetEntry>,
+}
+
+impl Assets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// how many sprites and animations have their textures loaded, including unused ones that haven't been unloaded yet
+    pub fn get_loaded_count(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// whether a sprite or animation from this source is loaded and still used
+    pub fn is_loaded(&self, source: &SpriteSource) -> bool {
+        let source = &source.clone().normalized();
+        self.entries
+            .iter()
+            .any(|(key, entry)| key.source == *source && entry.asset.strong_count() > 0)
+    }
+
+    /// the textures for a sprite, for things like tilesets that take texture handles
+    pub fn load_textures(
+        entities_and_components: &mut EntitiesAndComponents,
+        source: SpriteSource,
+    ) -> Result<AssetHandle, AssetError> {
+        Self::load(entities_and_components, source, Layout::Single)
+    }
+
+    pub fn load_sprite(
+        entities_and_components: &mut EntitiesAndComponents,
+        source: SpriteSource,
+    ) -> Result<Sprite, AssetError> {
+        let handle = Self::load(entities_and_components, source, Layout::Single)?;
+        let [albedo, height, roughness, normal] = handle.get_textures();
+
+        let program = entities_and_components
+            .get_resource_mut::<LumenpyxProgram>()
+            .expect("failed to get lumen program");
+        let (sprite, _, _, _, _) = Sprite::new(
+            albedo.into(),
+            height.into(),
+            roughness.into(),
+            normal.into(),
+            program,
+        );
+
+        Ok(sprite.with_asset_handle(handle))
+    }
+
+    /// an animation with a file per frame, see `SpriteSource` for how they're named
+    pub fn load_animation_from_images(
+        entities_and_components: &mut EntitiesAndComponents,
+        source: SpriteSource,
+        num_frames: usize,
+        time_between_frames: Duration,
+        loop_animation: bool,
+    ) -> Result<Animation, AssetError> {
+        let handle = Self::load(entities_and_components, source, Layout::Images(num_frames))?;
+        let animation = create_animation(
+            entities_and_components,
+            &handle,
+            time_between_frames,
+            loop_animation,
+        );
+
+        Ok(animation.with_asset_handle(handle))
+    }
+
+    /// an animation with its frames side by side in one file
+    pub fn load_animation_from_spritesheet(
+        entities_and_components: &mut EntitiesAndComponents,
+        source: SpriteSource,
+        num_frames: usize,
+        time_between_frames: Duration,
+        loop_animation: bool,
+    ) -> Result<Animation, AssetError> {
+        let handle = Self::load(
+            entities_and_components,
+            source,
+            Layout::Spritesheet(num_frames),
+        )?;
+        let animation = create_animation(
+            entities_and_components,
+            &handle,
+            time_between_frames,
+            loop_animation,
+        );
+
+        Ok(animation.with_asset_handle(handle))
+    }
+
+    /// removes the textures nobody has a handle to anymore and returns them so they can be freed
+    fn take_unused(&mut self) -> Vec<TextureHandle> {
+        let mut unused = vec![];
+        self.entries.retain(|_, entry| {
+            let is_used = entry.asset.strong_count() > 0;
+            if !is_used {
+                unused.extend(entry.frames.iter().flatten());
+            }
+            is_used
+        });
+
+        unused
+    }
+
+    /// the handle for already loaded textures, or loads them if they aren't
+    fn load(
+        entities_and_components: &mut EntitiesAndComponents,
+        source: SpriteSource,
+        layout: Layout,
+    ) -> Result<AssetHandle, AssetError> {
+        let key = AssetKey {
+            source: source.normalized(),
+            layout,
+        };
+
+        let assets = entities_and_components
+            .get_resource_mut::<Assets>()
+            .expect("failed to get assets");
+
+        if let Some(entry) = assets.entries.get_mut(&key) {
+            // textures that aren't used but haven't been unloaded yet can be used again
+            let asset = entry.asset.upgrade().unwrap_or_else(|| {
+                let asset = Arc::new(LoadedAsset {
+                    frames: entry.frames.clone(),
+                });
+                entry.asset = Arc::downgrade(&asset);
+                asset
+            });
+            return Ok(AssetHandle { asset });
+        }
+
+        // everything is read before any textures are made, so a bad file doesn't leave textures behind
+        let images = read_images(&key.source, layout)?;
+
+        let program = entities_and_components
+            .get_resource_mut::<LumenpyxProgram>()
+            .expect("failed to get lumen program");
+
+        let mut frames = vec![];
+        for frame in images {
+            match create_frame_textures(program, &key.source, frame) {
+                Ok(textures) => frames.push(textures),
+                Err(error) => {
+                    for texture in frames.iter().flatten() {
+                        program.remove_texture(texture);
+                    }
+                    return Err(error);
+                }
+            }
+        }
+
+        let asset = Arc::new(LoadedAsset {
+            frames: frames.clone(),
+        });
+        entities_and_components
+            .get_resource_mut::<Assets>()
+            .expect("failed to get assets")
+            .entries
+            .insert(
+                key,
+                AssetEntry {
+                    frames,
+                    asset: Arc::downgrade(&asset),
+                },
+            );
+
+        Ok(AssetHandle { asset })
+    }
+}
+
+impl Resource for Assets {
+    fn update(&mut self) {}
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+/// frees the textures of assets that stopped being used, run at the end of every frame
+pub(crate) fn unload_unused_assets(entities_and_components: &mut EntitiesAndComponents) {
+    // nothing can be loaded without the assets resource, so there is nothing to unload
+    let Some(assets) = entities_and_components.get_resource_mut::<Assets>() else {
+        return;
+    };
+    let unused = assets.take_unused();
+
+    if unused.is_empty() {
+        return;
+    }
+
+    let program = entities_and_components
+        .get_resource_mut::<LumenpyxProgram>()
+        .expect("failed to get lumen program");
+
+    for texture in unused {
+        program.remove_texture(&texture);
+    }
+}
+
+fn create_animation(
+    entities_and_components: &mut EntitiesAndComponents,
+    handle: &AssetHandle,
+    time_between_frames: Duration,
+    loop_animation: bool,
+) -> Animation {
+    let frames = handle.get_frames();
+    let channel = |i: usize| frames.iter().map(|frame| frame[i]).collect::<Vec<_>>();
+
+    Animation::new_from_handles(
+        channel(0),
+        channel(1),
+        channel(2),
+        channel(3),
+        time_between_frames,
+        loop_animation,
+        entities_and_components,
+    )
+}
+
+/// an image read from a file, with the path for errors
+struct SourceImage {
+    path: PathBuf,
+    image: DynamicImage,
+}
+
+/// the images a frame is made from, None where the texture isn't from a file
+struct FrameImages {
+    albedo: SourceImage,
+    height: Option<SourceImage>,
+    roughness: Option<SourceImage>,
+    normal: Option<SourceImage>,
+}
+
+/// `walk.png` is `walk1.png` for the first frame
+fn get_frame_path(path: &Path, frame: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{}{}.{}", stem, frame + 1, extension.to_string_lossy()),
+        None => format!("{}{}", stem, frame + 1),
+    };
+
+    path.with_file_name(name)
+}
+
+fn read_image(path: &Path) -> Result<DynamicImage, AssetError> {
+    if !path.exists() {
+        return Err(AssetError::NotFound(path.to_path_buf()));
+    }
+
+    image::open(path).map_err(|error| AssetError::Image(path.to_path_buf(), error))
+}
+
+/// every frame of one file, or None for every frame if the path is None
+fn read_frames(
+    path: Option<&Path>,
+    layout: Layout,
+) -> Result<Vec<Option<SourceImage>>, AssetError> {
+    let frame_count = layout.get_frame_count();
+    let Some(path) = path else {
+        return Ok((0..frame_count).map(|_| None).collect());
+    };
+
+    let frames = match layout {
+        Layout::Single => vec![SourceImage {
+            path: path.to_path_buf(),
+            image: read_image(path)?,
+        }],
+        Layout::Images(frames) => (0..frames)
+            .map(|frame| {
+                let path = get_frame_path(path, frame);
+                Ok(SourceImage {
+                    image: read_image(&path)?,
+                    path,
+                })
+            })
+            .collect::<Result<_, AssetError>>()?,
+        Layout::Spritesheet(frames) => {
+            let sheet = read_image(path)?;
+            let frame_width = sheet.width() / frames as u32;
+            if frame_width == 0 {
+                return Err(AssetError::Invalid(format!(
+                    "{} is {} pixels wide, too thin for {} frames",
+                    path.display(),
+                    sheet.width(),
+                    frames
+                )));
+            }
+
+            (0..frames as u32)
+                .map(|frame| SourceImage {
+                    path: path.to_path_buf(),
+                    image: sheet.crop_imm(frame * frame_width, 0, frame_width, sheet.height()),
+                })
+                .collect()
+        }
+    };
+
+    Ok(frames.into_iter().map(Some).collect())
+}
+
+fn read_images(source: &SpriteSource, layout: Layout) -> Result<Vec<FrameImages>, AssetError> {
+    if layout.get_frame_count() == 0 {
+        return Err(AssetError::Invalid(format!(
+            "{} has no frames",
+            source.albedo.display()
+        )));
+    }
+
+    let get_path = |texture: &TextureSource| match texture {
+        TextureSource::Path(path) => Some(path.clone()),
+        TextureSource::Solid(_) => None,
+    };
+    let normal_path = match &source.normal {
+        NormalSource::Path(path) => Some(path.as_path()),
+        _ => None,
+    };
+
+    let albedo = read_frames(Some(&source.albedo), layout)?;
+    let height = read_frames(get_path(&source.height).as_deref(), layout)?;
+    let roughness = read_frames(get_path(&source.roughness).as_deref(), layout)?;
+    let normal = read_frames(normal_path, layout)?;
+
+    Ok(albedo
+        .into_iter()
+        .zip(height)
+        .zip(roughness)
+        .zip(normal)
+        .map(|(((albedo, height), roughness), normal)| FrameImages {
+            albedo: albedo.expect("the albedo is always read from a file"),
+            height,
+            roughness,
+            normal,
+        })
+        .collect())
+}
+
+fn create_texture(
+    program: &LumenpyxProgram,
+    image: &SourceImage,
+) -> Result<glium::texture::Texture2d, AssetError> {
+    let pixels = image.image.to_rgba32f();
+    let dimensions = pixels.dimensions();
+    // textures are stored bottom row first
+    let raw = glium::texture::RawImage2d::from_raw_rgba_reversed(&pixels, dimensions);
+
+    glium::texture::Texture2d::new(&program.display, raw)
+        .map_err(|error| AssetError::Texture(image.path.clone(), format!("{:?}", error)))
+}
+
+/// the textures for one frame, loaded into the program like a `Sprite`'s
+fn create_frame_textures(
+    program: &mut LumenpyxProgram,
+    source: &SpriteSource,
+    frame: FrameImages,
+) -> Result<[TextureHandle; 4], AssetError> {
+    let texture = |image: Option<&SourceImage>, source: &TextureSource| match (image, source) {
+        (Some(image), _) => Ok(Texture::Texture(create_texture(program, image)?)),
+        (None, TextureSource::Solid(color)) => Ok(Texture::Solid(*color)),
+        (None, TextureSource::Path(path)) => Err(AssetError::NotFound(path.clone())),
+    };
+
+    let albedo = Texture::Texture(create_texture(program, &frame.albedo)?);
+    let height = texture(frame.height.as_ref(), &source.height)?;
+    let roughness = texture(frame.roughness.as_ref(), &source.roughness)?;
+    let normal = match (&frame.normal, &source.normal) {
+        (Some(image), _) => Normal::Texture(create_texture(program, image)?),
+        (None, NormalSource::Solid(color)) => Normal::Solid(*color),
+        (None, _) => Normal::AutoGenerated,
+    };
+
+    let (_, albedo, height, roughness, normal) = lumenpyx::primitives::Sprite::new(
+        TextureInput::from(albedo),
+        TextureInput::from(height),
+        TextureInput::from(roughness),
+        NormalInput::from(normal),
+        program,
+        Transform::default(),
+    );
+
+    Ok([albedo, height, roughness, normal])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_paths() {
+        assert_eq!(
+            get_frame_path(Path::new("sprites/walk.png"), 0),
+            Path::new("sprites/walk1.png")
+        );
+        assert_eq!(get_frame_path(Path::new("walk"), 2), Path::new("walk3"));
+    }
+
+    #[test]
+    fn test_read_images() {
+        let directory = std::env::temp_dir().join(format!("assets_test_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let sheet_path = directory.join("sheet.png");
+        image::RgbaImage::from_pixel(12, 4, image::Rgba([255, 0, 0, 255]))
+            .save(&sheet_path)
+            .unwrap();
+
+        let source = SpriteSource::new(&sheet_path);
+        let frames = read_images(&source, Layout::Spritesheet(3)).unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[2].albedo.image.width(), 4);
+        assert!(frames[0].height.is_none());
+
+        assert!(matches!(
+            read_images(&source, Layout::Spritesheet(13)),
+            Err(AssetError::Invalid(_))
+        ));
+        assert!(matches!(
+            read_images(&source, Layout::Images(2)),
+            Err(AssetError::NotFound(_))
+        ));
+
+        std::fs::write(directory.join("broken.png"), b"not a png").unwrap();
+        assert!(matches!(
+            read_images(
+                &SpriteSource::new(directory.join("broken.png")),
+                Layout::Single
+            ),
+            Err(AssetError::Image(_, _))
+        ));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_is_loaded() {
+        let source = SpriteSource::new("walk.png");
+        let asset = Arc::new(LoadedAsset { frames: vec![] });
+        let mut assets = Assets::new();
+        assets.entries.insert(
+            AssetKey {
+                source: source.clone().normalized(),
+                layout: Layout::Spritesheet(4),
+            },
+            AssetEntry {
+                frames: vec![],
+                asset: Arc::downgrade(&asset),
+            },
+        );
+
+        // animations count too, not only sprites
+        assert!(assets.is_loaded(&source));
+        assert!(assets.is_loaded(&SpriteSource::new("./sprites/../walk.png")));
+        assert!(!assets.is_loaded(&SpriteSource::new("run.png")));
+
+        drop(asset);
+        assert!(!assets.is_loaded(&source));
+    }
+
+    #[test]
+    fn test_handle_is_send_and_sync() {
+        fn is_send_and_sync<T: Send + Sync>() {}
+        is_send_and_sync::<AssetHandle>();
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(
+            normalize_path(Path::new("./a.png")),
+            normalize_path(Path::new("a.png"))
+        );
+        assert_eq!(
+            normalize_path(Path::new("sprites/./player/../a.png")),
+            normalize_path(Path::new("sprites/a.png"))
+        );
+        assert!(normalize_path(Path::new("a.png")).is_absolute());
+    }
+
+    #[test]
+    fn test_equal_sources() {
+        let solid = SpriteSource::new("a.png").with_height(TextureSource::Solid([0.5; 4]));
+        assert_eq!(
+            solid,
+            SpriteSource::new("a.png").with_height(TextureSource::Solid([0.5; 4]))
+        );
+        assert_ne!(solid, SpriteSource::new("a.png"));
+    }
+}
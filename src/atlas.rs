@@ -0,0 +1,563 @@
+//! Texture atlases, one set of textures with many named sprites packed into it
+//! the textures are loaded once and every `AtlasSprite` just draws its own part of them,
+//! so hundreds of props don't each load four textures
+//!
+//! regions come from a json manifest, either the hash or array layout that TexturePacker and Aseprite export,
+//! or a plain object of names to `{"x", "y", "w", "h"}`
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use glium::framebuffer::SimpleFrameBuffer;
+use glium::uniforms::Sampler;
+use glium::Surface;
+use lumenpyx::drawable_object::Drawable;
+use lumenpyx::primitives::{NormalInput, TextureInput};
+use lumenpyx::{LumenpyxProgram, TextureHandle, Transform};
+use serde_json::Value;
+
+use crate::blending::ALPHA_BLEND;
+use crate::pipeline::BUFFER_BEHAVIOR;
+use crate::tilemap::{load_tilemap_shader, TileVertex};
+
+#[derive(Debug)]
+pub enum AtlasError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// the manifest isn't laid out like any of the formats that can be loaded
+    Invalid(String),
+    /// a region that was packed rotated, turn rotation off when packing
+    RotatedRegion(String),
+}
+
+impl fmt::Display for AtlasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AtlasError::Io(error) => write!(f, "failed to read atlas manifest: {}", error),
+            AtlasError::Json(error) => write!(f, "failed to parse atlas manifest: {}", error),
+            AtlasError::Invalid(message) => write!(f, "invalid atlas manifest: {}", message),
+            AtlasError::RotatedRegion(name) => write!(
+                f,
+                "region {:?} is rotated, rotated regions aren't supported",
+                name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AtlasError {}
+
+impl From<std::io::Error> for AtlasError {
+    fn from(error: std::io::Error) -> Self {
+        AtlasError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for AtlasError {
+    fn from(error: serde_json::Error) -> Self {
+        AtlasError::Json(error)
+    }
+}
+
+/// Where a sprite is in the atlas, in pixels from the top left of the texture
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AtlasRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// where the region goes in the untrimmed sprite, packers cut off empty pixels around the edges
+    pub trim_offset: [u32; 2],
+    /// the size of the sprite before it was trimmed, the sprite is centered on this
+    pub source_size: [u32; 2],
+}
+
+impl AtlasRegion {
+    /// an untrimmed region
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+            trim_offset: [0, 0],
+            source_size: [width, height],
+        }
+    }
+}
+
+/// The named regions of an atlas
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AtlasManifest {
+    pub regions: HashMap<String, AtlasRegion>,
+}
+
+impl AtlasManifest {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, AtlasError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, AtlasError> {
+        let value: Value = serde_json::from_str(text)?;
+
+        let regions = match value.get("frames") {
+            // TexturePacker and Aseprite's hash layout
+            Some(Value::Object(frames)) => frames
+                .iter()
+                .map(|(name, frame)| Ok((name.clone(), parse_frame(name, frame)?)))
+                .collect::<Result<_, AtlasError>>()?,
+            // and their array layout
+            Some(Value::Array(frames)) => frames
+                .iter()
+                .map(|frame| {
+                    let name = frame
+                        .get("filename")
+                        .and_then(Value::as_str)
+                        .ok_or_else(|| AtlasError::Invalid("frame has no filename".to_string()))?;
+                    Ok((name.to_string(), parse_frame(name, frame)?))
+                })
+                .collect::<Result<_, AtlasError>>()?,
+            Some(_) => {
+                return Err(AtlasError::Invalid(
+                    "frames isn't an object or an array".to_string(),
+                ))
+            }
+            None => match &value {
+                Value::Object(regions) => regions
+                    .iter()
+                    .map(|(name, rect)| {
+                        Ok((name.clone(), AtlasRegion::from(parse_rect(name, rect)?)))
+                    })
+                    .collect::<Result<_, AtlasError>>()?,
+                _ => return Err(AtlasError::Invalid("expected an object".to_string())),
+            },
+        };
+
+        Ok(Self { regions })
+    }
+}
+
+impl From<[u32; 4]> for AtlasRegion {
+    fn from([x, y, width, height]: [u32; 4]) -> Self {
+        Self::new(x, y, width, height)
+    }
+}
+
+/// x, y, width and height from an object with x, y, w and h
+fn parse_rect(name: &str, value: &Value) -> Result<[u32; 4], AtlasError> {
+    let field = |field: &str| {
+        value
+            .get(field)
+            .and_then(Value::as_u64)
+            .and_then(|number| u32::try_from(number).ok())
+            .ok_or_else(|| {
+                AtlasError::Invalid(format!("region {:?} has no whole number {}", name, field))
+            })
+    };
+
+    Ok([field("x")?, field("y")?, field("w")?, field("h")?])
+}
+
+fn parse_frame(name: &str, frame: &Value) -> Result<AtlasRegion, AtlasError> {
+    if frame.get("rotated").and_then(Value::as_bool) == Some(true) {
+        return Err(AtlasError::RotatedRegion(name.to_string()));
+    }
+
+    let rect = frame
+        .get("frame")
+        .ok_or_else(|| AtlasError::Invalid(format!("frame {:?} has no rect", name)))?;
+    let mut region = AtlasRegion::from(parse_rect(name, rect)?);
+
+    if frame.get("trimmed").and_then(Value::as_bool) == Some(true) {
+        if let (Some(sprite_source), Some(source_size)) =
+            (frame.get("spriteSourceSize"), frame.get("sourceSize"))
+        {
+            let [x, y, _, _] = parse_rect(name, sprite_source)?;
+            // without a size the sprite can't have been trimmed from anything bigger than the frame
+            let size = |field: &str, frame_size: u32| {
+                source_size
+                    .get(field)
+                    .and_then(Value::as_u64)
+                    .and_then(|number| u32::try_from(number).ok())
+                    .unwrap_or(frame_size)
+            };
+            region.trim_offset = [x, y];
+            region.source_size = [size("w", region.width), size("h", region.height)];
+        }
+    }
+
+    Ok(region)
+}
+
+/// A set of textures with named regions, make sprites from it with `create_sprite`
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextureAtlas {
+    albedo: TextureHandle,
+    height: TextureHandle,
+    roughness: TextureHandle,
+    normal: TextureHandle,
+    texture_size: [u32; 2],
+    regions: HashMap<String, AtlasRegion>,
+}
+
+impl TextureAtlas {
+    /// loads the textures like a `Sprite`'s, all of them should use the same layout as the manifest
+    pub fn new(
+        albedo: TextureInput,
+        height: TextureInput,
+        roughness: TextureInput,
+        normal: NormalInput,
+        manifest: AtlasManifest,
+        program: &mut LumenpyxProgram,
+    ) -> Self {
+        let (_, albedo, height, roughness, normal) = lumenpyx::primitives::Sprite::new(
+            albedo,
+            height,
+            roughness,
+            normal,
+            program,
+            Transform::default(),
+        );
+
+        let texture = program
+            .get_texture_from_handle(&albedo)
+            .expect("failed to get atlas texture");
+        let texture_size = [
+            texture.get_width(),
+            texture
+                .get_height()
+                .expect("failed to get height of atlas texture"),
+        ];
+
+        Self {
+            albedo,
+            height,
+            roughness,
+            normal,
+            texture_size,
+            regions: manifest.regions,
+        }
+    }
+
+    /// reads the manifest from a file before loading the textures
+    pub fn load(
+        albedo: TextureInput,
+        height: TextureInput,
+        roughness: TextureInput,
+        normal: NormalInput,
+        manifest_path: impl AsRef<Path>,
+        program: &mut LumenpyxProgram,
+    ) -> Result<Self, AtlasError> {
+        let manifest = AtlasManifest::load(manifest_path)?;
+        Ok(Self::new(
+            albedo, height, roughness, normal, manifest, program,
+        ))
+    }
+
+    /// a sprite drawing the named region, None if the atlas doesn't have it
+    pub fn create_sprite(&self, name: &str) -> Option<AtlasSprite> {
+        let region = *self.regions.get(name)?;
+
+        Some(AtlasSprite {
+            textures: self.get_textures(),
+            texture_size: self.texture_size,
+            name: name.to_string(),
+            region,
+            vertex_buffer: RefCell::new(None),
+            shadow_strength: 0.5,
+            transform: Transform::default(),
+        })
+    }
+
+    pub fn get_region(&self, name: &str) -> Option<&AtlasRegion> {
+        self.regions.get(name)
+    }
+
+    pub fn get_region_names(&self) -> impl Iterator<Item = &str> {
+        self.regions.keys().map(String::as_str)
+    }
+
+    /// add or replace a region after loading
+    pub fn set_region(&mut self, name: &str, region: AtlasRegion) {
+        self.regions.insert(name.to_string(), region);
+    }
+
+    pub fn get_texture_size(&self) -> [u32; 2] {
+        self.texture_size
+    }
+
+    /// the albedo, height, roughness and normal textures in that order
+    pub fn get_textures(&self) -> [TextureHandle; 4] {
+        [self.albedo, self.height, self.roughness, self.normal]
+    }
+
+    /// every sprite made from the atlas stops drawing after this
+    pub fn free_all_textures(&self, program: &mut LumenpyxProgram) {
+        for texture in self.get_textures() {
+            program.remove_texture(&texture);
+        }
+    }
+}
+
+/// A sprite drawing one region of a `TextureAtlas`, centered on the transform like a `Sprite`
+/// it shares the atlas's textures, so it's cheap to make lots of them
+pub struct AtlasSprite {
+    textures: [TextureHandle; 4],
+    texture_size: [u32; 2],
+    name: String,
+    region: AtlasRegion,
+    /// None when the region changed since it was last drawn
+    vertex_buffer: RefCell<Option<glium::VertexBuffer<TileVertex>>>,
+    shadow_strength: f32,
+    transform: Transform,
+}
+
+impl AtlasSprite {
+    /// the name of the region this sprite draws
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_region(&self) -> AtlasRegion {
+        self.region
+    }
+
+    /// switch to another region of the same atlas, returns false if it doesn't have one with that name
+    pub fn set_region(&mut self, atlas: &TextureAtlas, name: &str) -> bool {
+        let Some(region) = atlas.get_region(name) else {
+            return false;
+        };
+
+        self.textures = atlas.get_textures();
+        self.texture_size = atlas.texture_size;
+        self.name = name.to_string();
+        self.region = *region;
+        *self.vertex_buffer.get_mut() = None;
+        true
+    }
+
+    pub fn set_shadow_strength(&mut self, shadow_strength: f32) {
+        self.shadow_strength = shadow_strength;
+    }
+
+    fn draw(
+        &self,
+        program: &LumenpyxProgram,
+        transform: &Transform,
+        framebuffer: &mut SimpleFrameBuffer,
+        texture: TextureHandle,
+    ) {
+        // the atlas's textures were freed, so there's nothing to draw
+        let Some(texture) = program.get_texture_from_handle(&texture) else {
+            return;
+        };
+        let shader = program
+            .get_shader("tilemap_shader")
+            .expect("failed to get tilemap shader");
+
+        let mut vertex_buffer = self.vertex_buffer.borrow_mut();
+        if vertex_buffer.is_none() {
+            let vertices = get_region_vertices(self.texture_size, &self.region);
+            *vertex_buffer = Some(
+                glium::VertexBuffer::new(&program.display, &vertices)
+                    .expect("failed to create vertex buffer for atlas sprite"),
+            );
+        }
+        let vertex_buffer = vertex_buffer
+            .as_ref()
+            .expect("the vertices were just built");
+
+        let (width, height) = framebuffer.get_dimensions();
+        let uniforms = glium::uniform! {
+            matrix: transform.get_matrix(),
+            pixel_scale: 2.0 / width.min(height) as f32,
+            image: Sampler(texture, BUFFER_BEHAVIOR),
+            is_normal_map: false,
+        };
+
+        framebuffer
+            .draw(
+                vertex_buffer,
+                glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList),
+                shader,
+                &uniforms,
+                &glium::DrawParameters {
+                    blend: ALPHA_BLEND,
+                    ..Default::default()
+                },
+            )
+            .expect("failed to draw atlas sprite");
+    }
+}
+
+/// the two triangles of a region, placed where it was before trimming with the whole sprite centered on 0, 0
+fn get_region_vertices(texture_size: [u32; 2], region: &AtlasRegion) -> Vec<TileVertex> {
+    let [texture_width, texture_height] = texture_size.map(|size| size as f32);
+    let [source_width, source_height] = region.source_size.map(|size| size as f32);
+    let [offset_x, offset_y] = region.trim_offset.map(|offset| offset as f32);
+    let [width, height] = [region.width as f32, region.height as f32];
+
+    // y goes up on screen
+    let left = offset_x - source_width / 2.0;
+    let top = source_height / 2.0 - offset_y;
+    let [right, bottom] = [left + width, top - height];
+
+    // textures are stored bottom row first
+    let u_left = region.x as f32 / texture_width;
+    let u_right = (region.x + region.width) as f32 / texture_width;
+    let v_top = 1.0 - region.y as f32 / texture_height;
+    let v_bottom = 1.0 - (region.y + region.height) as f32 / texture_height;
+
+    let corners = [
+        ([left, top], [u_left, v_top]),
+        ([right, top], [u_right, v_top]),
+        ([right, bottom], [u_right, v_bottom]),
+        ([left, bottom], [u_left, v_bottom]),
+    ];
+
+    [0, 1, 2, 0, 2, 3]
+        .map(|corner| TileVertex::new(corners[corner].0, corners[corner].1))
+        .to_vec()
+}
+
+impl Drawable for AtlasSprite {
+    fn draw_albedo(
+        &self,
+        program: &LumenpyxProgram,
+        transform: &Transform,
+        albedo_framebuffer: &mut SimpleFrameBuffer,
+    ) {
+        self.draw(program, transform, albedo_framebuffer, self.textures[0]);
+    }
+
+    fn draw_height(
+        &self,
+        program: &LumenpyxProgram,
+        transform: &Transform,
+        height_framebuffer: &mut SimpleFrameBuffer,
+    ) {
+        self.draw(program, transform, height_framebuffer, self.textures[1]);
+    }
+
+    fn draw_roughness(
+        &self,
+        program: &LumenpyxProgram,
+        transform: &Transform,
+        roughness_framebuffer: &mut SimpleFrameBuffer,
+    ) {
+        self.draw(program, transform, roughness_framebuffer, self.textures[2]);
+    }
+
+    fn draw_normal(
+        &self,
+        program: &LumenpyxProgram,
+        transform: &Transform,
+        normal_framebuffer: &mut SimpleFrameBuffer,
+    ) {
+        self.draw(program, transform, normal_framebuffer, self.textures[3]);
+    }
+
+    fn try_load_shaders(&self, program: &mut LumenpyxProgram) {
+        load_tilemap_shader(program);
+    }
+
+    fn get_transform(&self) -> Transform {
+        self.transform
+    }
+
+    fn get_recieve_shadows_strength(&self) -> f32 {
+        self.shadow_strength
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifests() {
+        let texture_packer = r#"{
+            "frames": {
+                "barrel.png": {
+                    "frame": {"x": 2, "y": 4, "w": 10, "h": 12},
+                    "rotated": false, "trimmed": true,
+                    "spriteSourceSize": {"x": 3, "y": 1, "w": 10, "h": 12},
+                    "sourceSize": {"w": 16, "h": 16}
+                }
+            },
+            "meta": {"app": "https://www.codeandweb.com/texturepacker", "size": {"w": 64, "h": 64}}
+        }"#;
+        let manifest = AtlasManifest::from_json(texture_packer).unwrap();
+        assert_eq!(
+            manifest.regions["barrel.png"],
+            AtlasRegion {
+                x: 2,
+                y: 4,
+                width: 10,
+                height: 12,
+                trim_offset: [3, 1],
+                source_size: [16, 16],
+            }
+        );
+
+        let aseprite = r#"{
+            "frames": [
+                {"filename": "crate 0.aseprite", "frame": {"x": 0, "y": 0, "w": 8, "h": 8},
+                 "rotated": false, "trimmed": false, "duration": 100},
+                {"filename": "crate 1.aseprite", "frame": {"x": 8, "y": 0, "w": 8, "h": 8},
+                 "rotated": false, "trimmed": false, "duration": 100}
+            ],
+            "meta": {"app": "https://www.aseprite.org/", "frameTags": []}
+        }"#;
+        let manifest = AtlasManifest::from_json(aseprite).unwrap();
+        assert_eq!(manifest.regions.len(), 2);
+        assert_eq!(
+            manifest.regions["crate 1.aseprite"],
+            AtlasRegion::new(8, 0, 8, 8)
+        );
+
+        let plain = r#"{"lamp": {"x": 1, "y": 2, "w": 3, "h": 4}}"#;
+        let manifest = AtlasManifest::from_json(plain).unwrap();
+        assert_eq!(manifest.regions["lamp"], AtlasRegion::new(1, 2, 3, 4));
+
+        // a trimmed frame without a source size is as big as the frame
+        let no_size = r#"{"frames": {"rock": {"frame": {"x": 0, "y": 0, "w": 6, "h": 5},
+            "trimmed": true, "spriteSourceSize": {"x": 0, "y": 0, "w": 6, "h": 5},
+            "sourceSize": {}}}}"#;
+        let manifest = AtlasManifest::from_json(no_size).unwrap();
+        assert_eq!(manifest.regions["rock"].source_size, [6, 5]);
+
+        let rotated =
+            r#"{"frames": {"tree": {"frame": {"x": 0, "y": 0, "w": 1, "h": 1}, "rotated": true}}}"#;
+        assert!(matches!(
+            AtlasManifest::from_json(rotated),
+            Err(AtlasError::RotatedRegion(_))
+        ));
+    }
+
+    #[test]
+    fn test_region_vertices() {
+        let region = AtlasRegion {
+            x: 16,
+            y: 0,
+            width: 8,
+            height: 4,
+            trim_offset: [2, 4],
+            source_size: [16, 16],
+        };
+        let vertices = get_region_vertices([32, 32], &region);
+
+        // the top left corner, 2 right and 4 down from the top left of the untrimmed sprite
+        assert_eq!(vertices[0].position, [-6.0, 4.0]);
+        assert_eq!(vertices[0].tex_coords, [0.5, 1.0]);
+        // the bottom right corner
+        assert_eq!(vertices[2].position, [2.0, 0.0]);
+        assert_eq!(vertices[2].tex_coords, [0.75, 1.0 - 4.0 / 32.0]);
+    }
+}
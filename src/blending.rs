@@ -0,0 +1,542 @@
+//! Blending any number of drawables together, the way lumenpyx's `BlendObject` blends two
+//! the layers are folded in draw order, so with subtractive blending every layer cuts into the ones below it
+//! every level of nesting has its own textures, so a blend can be one of the layers of another blend
+//! masks work the same way, the layers are drawn together and then clipped by the mask
+//! and so do opacity groups, the layers are drawn together and then faded as one image
+
+use glium::framebuffer::SimpleFrameBuffer;
+use glium::uniforms::Sampler;
+use glium::{Surface, Texture2d};
+use lumenpyx::blending::BlendMode;
+use lumenpyx::drawable_object::Drawable;
+use lumenpyx::{LumenpyxProgram, Transform};
+
+use crate::pipeline::{
+    adjust_transform_for_drawable, draw_full_screen, load_render_texture, BUFFER_BEHAVIOR,
+};
+use crate::primitives::GroupBlendMode;
+
+/// the same alpha blending lumenpyx draws with
+pub(crate) const ALPHA_BLEND: glium::Blend = glium::Blend {
+    color: glium::BlendingFunction::Addition {
+        source: glium::LinearBlendingFactor::SourceAlpha,
+        destination: glium::LinearBlendingFactor::OneMinusSourceAlpha,
+    },
+    alpha: glium::BlendingFunction::Addition {
+        source: glium::LinearBlendingFactor::SourceAlpha,
+        destination: glium::LinearBlendingFactor::OneMinusSourceAlpha,
+    },
+    constant_value: (0.0, 0.0, 0.0, 0.0),
+};
+
+const FULL_SCREEN_VERTEX_SHADER: &str = include_str!("../shaders/full_screen.vert");
+const MASK_FRAGMENT_SHADER: &str = include_str!("../shaders/blending/mask.frag");
+const GROUP_FRAGMENT_SHADER: &str = include_str!("../shaders/blending/group.frag");
+/// the same shader lumenpyx's `BlendObject` mixes with, so either can load it
+const MIX_FRAGMENT_SHADER: &str = include_str!("../shaders/blending/mix.frag");
+
+/// the layer being added, and the two textures the blend so far takes turns being drawn into
+const BLEND_SLOTS: [&str; 3] = ["layer", "accumulated_a", "accumulated_b"];
+
+/// One of the images every drawable draws
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum BlendBuffer {
+    Albedo,
+    Height,
+    Roughness,
+    Normal,
+}
+
+impl BlendBuffer {
+    const ALL: [BlendBuffer; 4] = [
+        BlendBuffer::Albedo,
+        BlendBuffer::Height,
+        BlendBuffer::Roughness,
+        BlendBuffer::Normal,
+    ];
+
+    fn get_name(self) -> &'static str {
+        match self {
+            BlendBuffer::Albedo => "albedo",
+            BlendBuffer::Height => "height",
+            BlendBuffer::Roughness => "roughness",
+            BlendBuffer::Normal => "normal",
+        }
+    }
+
+    fn draw(
+        self,
+        drawable: &dyn Drawable,
+        program: &LumenpyxProgram,
+        transform: &Transform,
+        framebuffer: &mut SimpleFrameBuffer,
+    ) {
+        match self {
+            BlendBuffer::Albedo => drawable.draw_albedo(program, transform, framebuffer),
+            BlendBuffer::Height => drawable.draw_height(program, transform, framebuffer),
+            BlendBuffer::Roughness => drawable.draw_roughness(program, transform, framebuffer),
+            BlendBuffer::Normal => drawable.draw_normal(program, transform, framebuffer),
+        }
+    }
+}
+
+/// What clips the layers of a mask
+pub(crate) enum MaskShape<'a> {
+    /// the alpha of these drawables
+    Layers(&'a [&'a dyn Drawable]),
+    /// a rectangle in pixels, around the mask's position
+    ClipRect { width: f32, height: f32 },
+}
+
+fn get_blend_texture_name(depth: usize, buffer: BlendBuffer, slot: &str) -> String {
+    format!("blend_{}_{}_{}_texture", depth, buffer.get_name(), slot)
+}
+
+fn get_mask_texture_name(depth: usize, buffer: &str) -> String {
+    format!("mask_{}_{}_texture", depth, buffer)
+}
+
+fn get_group_texture_name(depth: usize, buffer: BlendBuffer) -> String {
+    format!("group_{}_{}_texture", depth, buffer.get_name())
+}
+
+/// every texture blends, masks and opacity groups at this depth can use
+fn get_depth_texture_names(depth: usize) -> Vec<String> {
+    let mut names = vec![];
+    for buffer in BlendBuffer::ALL {
+        for slot in BLEND_SLOTS {
+            names.push(get_blend_texture_name(depth, buffer, slot));
+        }
+        names.push(get_mask_texture_name(depth, buffer.get_name()));
+        names.push(get_group_texture_name(depth, buffer));
+    }
+    names.push(get_mask_texture_name(depth, "mask"));
+
+    names
+}
+
+/// one of the copies of a texture, every resolution drawn at in the same frame needs its own copy
+/// so targets of different sizes don't remake them every frame
+fn get_texture_copy_name(name: &str, copy: usize) -> String {
+    format!("{}_{}", name, copy)
+}
+
+fn get_texture_resolution(program: &LumenpyxProgram, name: &str) -> Option<[u32; 2]> {
+    program.get_texture(name).map(|texture| {
+        let (width, height) = texture.dimensions();
+        [width, height]
+    })
+}
+
+/// marks a texture as needed, the drawables don't know the size of the target when their shaders are loaded
+/// so `load_blending_textures` makes the real ones once it does
+fn require_texture(program: &mut LumenpyxProgram, name: &str) {
+    if program.get_texture(name).is_none() {
+        load_render_texture(program, name, [1, 1]);
+    }
+}
+
+/// makes sure a copy of the texture has this resolution
+/// a copy with a resolution that isn't drawn at this frame is resized in place, so resizing the window doesn't leave old textures behind
+fn load_texture_copy(
+    program: &mut LumenpyxProgram,
+    name: &str,
+    resolution: [u32; 2],
+    frame_resolutions: &[[u32; 2]],
+) {
+    for copy in 0.. {
+        let copy_name = get_texture_copy_name(name, copy);
+        match get_texture_resolution(program, &copy_name) {
+            Some(copy_resolution) if copy_resolution == resolution => return,
+            Some(copy_resolution) if frame_resolutions.contains(&copy_resolution) => {}
+            _ => {
+                load_render_texture(program, &copy_name, resolution);
+                return;
+            }
+        }
+    }
+}
+
+/// makes the textures every blend, mask and opacity group needs for a target with this resolution
+/// called after the shaders of the drawables are loaded, so they have marked the textures they need
+/// `frame_resolutions` are the resolutions drawn at this frame, including this one
+pub(crate) fn load_blending_textures(
+    program: &mut LumenpyxProgram,
+    resolution: [u32; 2],
+    frame_resolutions: &[[u32; 2]],
+) {
+    // blends, masks and groups are nested one depth at a time, so the first depth without any is the last
+    for depth in 0.. {
+        let required = get_depth_texture_names(depth)
+            .into_iter()
+            .filter(|name| program.get_texture(name).is_some())
+            .collect::<Vec<_>>();
+
+        if required.is_empty() {
+            break;
+        }
+
+        for name in required {
+            load_texture_copy(program, &name, resolution, frame_resolutions);
+        }
+    }
+}
+
+/// gets the copy of a texture made by `load_blending_textures` for the framebuffer being drawn into
+fn get_blending_texture<'a>(
+    program: &'a LumenpyxProgram,
+    name: &str,
+    resolution: [u32; 2],
+) -> &'a Texture2d {
+    (0..)
+        .map_while(|copy| program.get_texture(&get_texture_copy_name(name, copy)))
+        .find(|texture| texture.dimensions() == (resolution[0], resolution[1]))
+        .expect("failed to get blending texture")
+}
+
+fn load_shader(program: &mut LumenpyxProgram, name: &str, fragment_shader: &str) {
+    if program.get_shader(name).is_none() {
+        let shader = glium::Program::from_source(
+            &program.display,
+            FULL_SCREEN_VERTEX_SHADER,
+            fragment_shader,
+            None,
+        )
+        .expect("failed to create blending shader");
+
+        program.add_shader(shader, name);
+    }
+}
+
+fn load_layer_shaders(program: &mut LumenpyxProgram, layers: &[&dyn Drawable]) {
+    for layer in layers {
+        layer.try_load_shaders(program);
+    }
+}
+
+/// loads the shaders of the layers and the mix shader, and marks the textures for blends at this depth
+pub(crate) fn load_blend_textures(
+    program: &mut LumenpyxProgram,
+    layers: &[&dyn Drawable],
+    depth: usize,
+) {
+    load_layer_shaders(program, layers);
+    load_shader(program, "mix", MIX_FRAGMENT_SHADER);
+
+    for buffer in BlendBuffer::ALL {
+        for slot in BLEND_SLOTS {
+            require_texture(program, &get_blend_texture_name(depth, buffer, slot));
+        }
+    }
+}
+
+/// loads the shaders of the layers and the mask, and marks the textures for masks at this depth
+pub(crate) fn load_mask_textures(
+    program: &mut LumenpyxProgram,
+    layers: &[&dyn Drawable],
+    mask: &MaskShape,
+    depth: usize,
+) {
+    load_layer_shaders(program, layers);
+
+    if let MaskShape::Layers(mask_layers) = mask {
+        load_layer_shaders(program, mask_layers);
+    }
+
+    load_shader(program, "mask", MASK_FRAGMENT_SHADER);
+
+    for buffer in BlendBuffer::ALL {
+        require_texture(program, &get_mask_texture_name(depth, buffer.get_name()));
+    }
+    require_texture(program, &get_mask_texture_name(depth, "mask"));
+}
+
+/// loads the shaders of the layers and marks the textures for opacity groups at this depth
+pub(crate) fn load_group_textures(
+    program: &mut LumenpyxProgram,
+    layers: &[&dyn Drawable],
+    depth: usize,
+) {
+    load_layer_shaders(program, layers);
+    load_shader(program, "group", GROUP_FRAGMENT_SHADER);
+
+    for buffer in BlendBuffer::ALL {
+        require_texture(program, &get_group_texture_name(depth, buffer));
+    }
+}
+
+/// draws one buffer of every layer and blends them together into the framebuffer
+pub(crate) fn draw_blended(
+    program: &LumenpyxProgram,
+    layers: &[&dyn Drawable],
+    blend_mode: BlendMode,
+    depth: usize,
+    transform: &Transform,
+    buffer: BlendBuffer,
+    framebuffer: &mut SimpleFrameBuffer,
+) {
+    let Some((first, rest)) = layers.split_first() else {
+        return;
+    };
+
+    let (width, height) = framebuffer.get_dimensions();
+    let get_texture = |slot: &str| {
+        get_blending_texture(
+            program,
+            &get_blend_texture_name(depth, buffer, slot),
+            [width, height],
+        )
+    };
+
+    let layer_texture = get_texture("layer");
+    let mut accumulated = [get_texture("accumulated_a"), get_texture("accumulated_b")];
+
+    draw_layers(
+        program,
+        &[*first],
+        accumulated[0],
+        transform,
+        buffer,
+        [width, height],
+    );
+
+    for (index, layer) in rest.iter().enumerate() {
+        draw_layers(
+            program,
+            &[*layer],
+            layer_texture,
+            transform,
+            buffer,
+            [width, height],
+        );
+
+        let uniforms = glium::uniform! {
+            bottom_image: Sampler(accumulated[0], BUFFER_BEHAVIOR),
+            top_image: Sampler(layer_texture, BUFFER_BEHAVIOR),
+            add: matches!(blend_mode, BlendMode::Additive),
+            subtract: matches!(blend_mode, BlendMode::Subtractive),
+            multiply: matches!(blend_mode, BlendMode::Multiplicative),
+            divide: matches!(blend_mode, BlendMode::Divisive),
+        };
+
+        if index == rest.len() - 1 {
+            draw_full_screen(
+                program,
+                framebuffer,
+                "mix",
+                &uniforms,
+                &glium::DrawParameters {
+                    blend: ALPHA_BLEND,
+                    ..Default::default()
+                },
+            );
+        } else {
+            let mut accumulated_framebuffer =
+                SimpleFrameBuffer::new(&program.display, accumulated[1])
+                    .expect("failed to create blending framebuffer");
+            accumulated_framebuffer.clear_color(0.0, 0.0, 0.0, 0.0);
+
+            draw_full_screen(
+                program,
+                &mut accumulated_framebuffer,
+                "mix",
+                &uniforms,
+                &Default::default(),
+            );
+
+            accumulated.swap(0, 1);
+        }
+    }
+}
+
+/// draws one buffer of every layer over each other, then clips them with the mask into the framebuffer
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn draw_masked(
+    program: &LumenpyxProgram,
+    layers: &[&dyn Drawable],
+    mask: &MaskShape,
+    invert: bool,
+    softness: f32,
+    depth: usize,
+    transform: &Transform,
+    buffer: BlendBuffer,
+    framebuffer: &mut SimpleFrameBuffer,
+) {
+    let (width, height) = framebuffer.get_dimensions();
+    let get_texture = |name: &str| {
+        get_blending_texture(
+            program,
+            &get_mask_texture_name(depth, name),
+            [width, height],
+        )
+    };
+
+    let content_texture = get_texture(buffer.get_name());
+    let mask_texture = get_texture("mask");
+
+    draw_layers(
+        program,
+        layers,
+        content_texture,
+        transform,
+        buffer,
+        [width, height],
+    );
+
+    // the mask is the same for every buffer, it's always cut out of the albedo
+    let clip_rect = match mask {
+        MaskShape::Layers(mask_layers) => {
+            draw_layers(
+                program,
+                mask_layers,
+                mask_texture,
+                transform,
+                BlendBuffer::Albedo,
+                [width, height],
+            );
+            [0.0; 4]
+        }
+        MaskShape::ClipRect {
+            width: rect_width,
+            height: rect_height,
+        } => {
+            // the transform is already in screen space, from -1 to 1
+            let matrix = transform.get_matrix();
+            let center = [matrix[3][0] * 0.5 + 0.5, matrix[3][1] * 0.5 + 0.5];
+            let half_size = [
+                rect_width / 2.0 / width as f32,
+                rect_height / 2.0 / height as f32,
+            ];
+
+            [
+                center[0] - half_size[0],
+                center[1] - half_size[1],
+                center[0] + half_size[0],
+                center[1] + half_size[1],
+            ]
+        }
+    };
+
+    let uniforms = glium::uniform! {
+        content: Sampler(content_texture, BUFFER_BEHAVIOR),
+        mask: Sampler(mask_texture, BUFFER_BEHAVIOR),
+        resolution: [width as f32, height as f32],
+        use_clip_rect: matches!(mask, MaskShape::ClipRect { .. }),
+        clip_rect: clip_rect,
+        softness: softness,
+        invert: invert,
+    };
+
+    draw_full_screen(
+        program,
+        framebuffer,
+        "mask",
+        &uniforms,
+        &glium::DrawParameters {
+            blend: ALPHA_BLEND,
+            ..Default::default()
+        },
+    );
+}
+
+/// draws one buffer of every layer over each other, then draws them into the framebuffer as one faded image
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn draw_group(
+    program: &LumenpyxProgram,
+    layers: &[&dyn Drawable],
+    opacity: f32,
+    blend_mode: GroupBlendMode,
+    depth: usize,
+    transform: &Transform,
+    buffer: BlendBuffer,
+    framebuffer: &mut SimpleFrameBuffer,
+) {
+    let (width, height) = framebuffer.get_dimensions();
+    let texture = get_blending_texture(
+        program,
+        &get_group_texture_name(depth, buffer),
+        [width, height],
+    );
+
+    draw_layers(program, layers, texture, transform, buffer, [width, height]);
+
+    // the blend mode is for the colors, the height and the rest are faded normally
+    let blend_mode = match buffer {
+        BlendBuffer::Albedo => blend_mode,
+        _ => GroupBlendMode::Normal,
+    };
+
+    let uniforms = glium::uniform! {
+        layer: Sampler(texture, BUFFER_BEHAVIOR),
+        opacity: opacity,
+        multiply: blend_mode == GroupBlendMode::Multiplicative,
+    };
+
+    draw_full_screen(
+        program,
+        framebuffer,
+        "group",
+        &uniforms,
+        &glium::DrawParameters {
+            blend: get_group_blend(blend_mode),
+            ..Default::default()
+        },
+    );
+}
+
+fn get_group_blend(blend_mode: GroupBlendMode) -> glium::Blend {
+    let color = match blend_mode {
+        GroupBlendMode::Normal => return ALPHA_BLEND,
+        GroupBlendMode::Additive => glium::BlendingFunction::Addition {
+            source: glium::LinearBlendingFactor::SourceAlpha,
+            destination: glium::LinearBlendingFactor::One,
+        },
+        GroupBlendMode::Subtractive => glium::BlendingFunction::ReverseSubtraction {
+            source: glium::LinearBlendingFactor::SourceAlpha,
+            destination: glium::LinearBlendingFactor::One,
+        },
+        // the shader already faded the color towards white
+        GroupBlendMode::Multiplicative => glium::BlendingFunction::Addition {
+            source: glium::LinearBlendingFactor::Zero,
+            destination: glium::LinearBlendingFactor::SourceColor,
+        },
+    };
+
+    glium::Blend {
+        color,
+        ..ALPHA_BLEND
+    }
+}
+
+/// draws layers over each other into a texture, positioned relative to the blend
+fn draw_layers(
+    program: &LumenpyxProgram,
+    layers: &[&dyn Drawable],
+    texture: &Texture2d,
+    transform: &Transform,
+    buffer: BlendBuffer,
+    resolution: [u32; 2],
+) {
+    let mut framebuffer = SimpleFrameBuffer::new(&program.display, texture)
+        .expect("failed to create blending framebuffer");
+    framebuffer.clear_color(0.0, 0.0, 0.0, 0.0);
+
+    // the same as lumenpyx's blend object, the layer is placed with the camera at the origin
+    // then moved by the blend's transform, which already has the camera in it
+    let camera = lumenpyx::Camera::new([0.0, 0.0, 0.0]);
+    let parent_matrix = transform.get_matrix();
+
+    for layer in layers {
+        let mut matrix =
+            adjust_transform_for_drawable(&layer.get_transform(), &camera, resolution).get_matrix();
+        matrix[3][0] += parent_matrix[3][0];
+        matrix[3][1] += parent_matrix[3][1];
+
+        buffer.draw(
+            *layer,
+            program,
+            &Transform::from_matrix(matrix),
+            &mut framebuffer,
+        );
+    }
+}
@@ -0,0 +1,453 @@
+//! Saving what the game draws, at the render resolution rather than the size of the window
+//! screenshots are saved as PNG, recordings as a numbered PNG sequence or an animated GIF
+//! GIFs are slow to encode, so that happens on another thread while the game keeps running
+
+use std::fmt;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, RgbaImage};
+use ABC_Game_Engine::{EntitiesAndComponents, Input, KeyCode, KeyState, Resource};
+
+use crate::pipeline::{RenderBuffer, MAIN_TARGET};
+use crate::LumenpyxProgram;
+
+/// how fast the GIF encoder is, from 1 (best colors) to 30 (fastest)
+const GIF_SPEED: i32 = 10;
+
+/// how long the last frame of a GIF is shown if it is also the first, there is no frame before it to copy
+const DEFAULT_GIF_FRAME_TIME: Duration = Duration::from_millis(1000 / 60);
+
+/// How a recording is saved
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordingFormat {
+    /// every frame is a PNG in a directory, named frame_000000.png, frame_000001.png...
+    PngSequence,
+    /// one animated GIF that loops forever, each frame is shown for as long as it took
+    /// and the last one as long as the one before it
+    Gif,
+}
+
+#[derive(Debug)]
+pub enum CaptureError {
+    Io(std::io::Error),
+    Image(image::ImageError),
+    /// a recording was started while another one was running
+    AlreadyRecording,
+    NotRecording,
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureError::Io(error) => write!(f, "failed to write capture: {}", error),
+            CaptureError::Image(error) => write!(f, "failed to encode capture: {}", error),
+            CaptureError::AlreadyRecording => write!(f, "a recording is already running"),
+            CaptureError::NotRecording => write!(f, "there is no recording to stop"),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {}
+
+impl From<std::io::Error> for CaptureError {
+    fn from(error: std::io::Error) -> Self {
+        CaptureError::Io(error)
+    }
+}
+
+impl From<image::ImageError> for CaptureError {
+    fn from(error: image::ImageError) -> Self {
+        CaptureError::Image(error)
+    }
+}
+
+/// Encodes the frames of a GIF on its own thread
+struct GifWriter {
+    sender: Sender<Frame>,
+    thread: JoinHandle<Result<(), CaptureError>>,
+    /// the thread stopped because of an error, `finish` returns it
+    stopped: bool,
+}
+
+impl GifWriter {
+    fn new(mut encoder: GifEncoder<BufWriter<File>>) -> Self {
+        let (sender, receiver) = mpsc::channel::<Frame>();
+
+        let thread = std::thread::spawn(move || {
+            for frame in receiver {
+                encoder.encode_frame(frame)?;
+            }
+            Ok(())
+        });
+
+        Self {
+            sender,
+            thread,
+            stopped: false,
+        }
+    }
+
+    fn write_frame(&mut self, frame: Frame) {
+        if self.sender.send(frame).is_err() {
+            self.stopped = true;
+        }
+    }
+
+    /// waits for every frame to be written
+    fn finish(self) -> Result<(), CaptureError> {
+        drop(self.sender);
+
+        self.thread.join().unwrap_or_else(|_| {
+            Err(CaptureError::Io(std::io::Error::other(
+                "the gif encoder panicked",
+            )))
+        })
+    }
+}
+
+enum RecordingOutput {
+    PngSequence(PathBuf),
+    Gif {
+        writer: GifWriter,
+        /// a frame is only written once the next one arrives, so we know how long it was shown
+        pending_frame: Option<(RgbaImage, Instant)>,
+        /// how long the last written frame was shown
+        last_frame_time: Option<Duration>,
+    },
+}
+
+struct Recording {
+    output: RecordingOutput,
+    frames_recorded: u32,
+    frame_count: Option<u32>,
+}
+
+impl Recording {
+    fn add_frame(&mut self, image: RgbaImage) -> Result<(), CaptureError> {
+        match &mut self.output {
+            RecordingOutput::PngSequence(directory) => {
+                image.save(directory.join(format!("frame_{:06}.png", self.frames_recorded)))?;
+            }
+            RecordingOutput::Gif {
+                writer,
+                pending_frame,
+                last_frame_time,
+            } => {
+                let now = Instant::now();
+                if let Some((last_image, last_time)) = pending_frame.replace((image, now)) {
+                    let frame_time = now.duration_since(last_time);
+                    let delay = Delay::from_saturating_duration(frame_time);
+                    writer.write_frame(Frame::from_parts(last_image, 0, 0, delay));
+                    *last_frame_time = Some(frame_time);
+                }
+            }
+        }
+
+        self.frames_recorded += 1;
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), CaptureError> {
+        if let RecordingOutput::Gif {
+            mut writer,
+            pending_frame,
+            last_frame_time,
+        } = self.output
+        {
+            // the time until the recording stopped isn't a real frame time, so the last frame copies the one before it
+            if let Some((last_image, _)) = pending_frame {
+                let frame_time = last_frame_time.unwrap_or(DEFAULT_GIF_FRAME_TIME);
+                let delay = Delay::from_saturating_duration(frame_time);
+                writer.write_frame(Frame::from_parts(last_image, 0, 0, delay));
+            }
+
+            writer.finish()?;
+        }
+
+        Ok(())
+    }
+
+    /// returns true once the recording has all its frames, or can't write any more
+    fn is_done(&self) -> bool {
+        let writer_stopped =
+            matches!(&self.output, RecordingOutput::Gif { writer, .. } if writer.stopped);
+
+        writer_stopped
+            || self
+                .frame_count
+                .is_some_and(|frame_count| self.frames_recorded >= frame_count)
+    }
+}
+
+/// A resource for taking screenshots and recording frames
+/// frames are captured at the end of the frame, after the update function
+pub struct Capture {
+    screenshots: Vec<PathBuf>,
+    /// the key that takes a screenshot, and the directory it's saved to
+    screenshot_key: Option<(KeyCode, PathBuf)>,
+    recording: Option<Recording>,
+    errors: Vec<CaptureError>,
+}
+
+impl Capture {
+    pub fn new() -> Self {
+        Self {
+            screenshots: vec![],
+            screenshot_key: None,
+            recording: None,
+            errors: vec![],
+        }
+    }
+
+    /// save this frame as a PNG at the end of the frame
+    pub fn take_screenshot(&mut self, path: impl Into<PathBuf>) {
+        self.screenshots.push(path.into());
+    }
+
+    /// take a screenshot whenever a key is pressed, they are named by the time they were taken
+    /// None to stop
+    pub fn set_screenshot_key(&mut self, key: Option<KeyCode>, directory: impl Into<PathBuf>) {
+        self.screenshot_key = key.map(|key| (key, directory.into()));
+    }
+
+    /// start recording frames, starting with this one
+    /// for a PNG sequence the path is a directory, it is made if it doesn't exist
+    /// with a frame count the recording stops by itself after that many frames
+    pub fn start_recording(
+        &mut self,
+        format: RecordingFormat,
+        path: impl AsRef<Path>,
+        frame_count: Option<u32>,
+    ) -> Result<(), CaptureError> {
+        if self.recording.is_some() {
+            return Err(CaptureError::AlreadyRecording);
+        }
+
+        let path = path.as_ref();
+        let output = match format {
+            RecordingFormat::PngSequence => {
+                std::fs::create_dir_all(path)?;
+                RecordingOutput::PngSequence(path.to_path_buf())
+            }
+            RecordingFormat::Gif => {
+                let file = BufWriter::new(File::create(path)?);
+                let mut encoder = GifEncoder::new_with_speed(file, GIF_SPEED);
+                encoder.set_repeat(Repeat::Infinite)?;
+
+                RecordingOutput::Gif {
+                    writer: GifWriter::new(encoder),
+                    pending_frame: None,
+                    last_frame_time: None,
+                }
+            }
+        };
+
+        self.recording = Some(Recording {
+            output,
+            frames_recorded: 0,
+            frame_count,
+        });
+
+        Ok(())
+    }
+
+    /// stop recording and finish writing the file
+    pub fn stop_recording(&mut self) -> Result<(), CaptureError> {
+        self.recording
+            .take()
+            .ok_or(CaptureError::NotRecording)?
+            .finish()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// the errors from saving screenshots and recorded frames since this was last called
+    /// a recording stops when one of its frames fails
+    pub fn take_errors(&mut self) -> Vec<CaptureError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// stops the recording if there is one, the shutdown callback can still get the error
+    pub(crate) fn finish_recording(&mut self) {
+        if let Some(recording) = self.recording.take() {
+            if let Err(error) = recording.finish() {
+                self.errors.push(error);
+            }
+        }
+    }
+
+    fn is_capture_needed(&self) -> bool {
+        !self.screenshots.is_empty() || self.recording.is_some()
+    }
+
+    fn add_frame(&mut self, image: RgbaImage) {
+        for path in std::mem::take(&mut self.screenshots) {
+            if let Err(error) = image.save(path) {
+                self.errors.push(error.into());
+            }
+        }
+
+        let Some(recording) = &mut self.recording else {
+            return;
+        };
+
+        let result = recording.add_frame(image);
+
+        if result.is_err() || recording.is_done() {
+            let recording = self.recording.take().expect("the recording was just used");
+            if let Err(error) = result.and(recording.finish()) {
+                self.errors.push(error);
+            }
+        }
+    }
+}
+
+impl Default for Capture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Resource for Capture {
+    fn update(&mut self) {}
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+/// turns pixels read from a texture into an image, keeping only the middle `visible_size` pixels
+/// textures are read bottom row first, images are top row first
+/// the alpha is made opaque because the window doesn't show it either
+fn texture_pixels_to_image(pixels: &[u8], size: [u32; 2], visible_size: [u32; 2]) -> RgbaImage {
+    let visible_width = visible_size[0].min(size[0]);
+    let visible_height = visible_size[1].min(size[1]);
+    let left = (size[0] - visible_width) / 2;
+    let bottom = (size[1] - visible_height) / 2;
+
+    RgbaImage::from_fn(visible_width, visible_height, |x, y| {
+        let row = bottom + visible_height - 1 - y;
+        let index = ((row * size[0] + left + x) * 4) as usize;
+
+        image::Rgba([pixels[index], pixels[index + 1], pixels[index + 2], 255])
+    })
+}
+
+/// saves the frame for the screenshots and recording that need it, called by the event loop after the update
+pub(crate) fn capture_frame(scene: &mut EntitiesAndComponents) {
+    let screenshot_pressed = scene
+        .get_resource::<Capture>()
+        .and_then(|capture| capture.screenshot_key.as_ref())
+        .is_some_and(|(key, _)| {
+            scene
+                .get_resource::<Input>()
+                .is_some_and(|input| input.get_key_state(*key) == KeyState::Pressed)
+        });
+
+    let Some(capture) = scene.get_resource_mut::<Capture>() else {
+        return;
+    };
+
+    if screenshot_pressed {
+        if let Some((_, directory)) = &capture.screenshot_key {
+            let time = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis();
+            let path = directory.join(format!("screenshot_{}.png", time));
+
+            match std::fs::create_dir_all(directory) {
+                Ok(()) => capture.take_screenshot(path),
+                Err(error) => capture.errors.push(error.into()),
+            }
+        }
+    }
+
+    if !capture.is_capture_needed() {
+        return;
+    }
+
+    let lumen_program = scene
+        .get_resource::<LumenpyxProgram>()
+        .expect("failed to get lumen program");
+
+    // what the main window shows, before it's scaled up
+    let buffer = RenderBuffer::from_debug_option(&lumen_program.debug);
+    let Some(texture) = lumen_program.get_target_texture(MAIN_TARGET, buffer) else {
+        return;
+    };
+
+    let pixels: glium::texture::RawImage2d<u8> = texture.read();
+    let image = texture_pixels_to_image(
+        &pixels.data,
+        [pixels.width, pixels.height],
+        lumen_program.get_resolution(),
+    );
+
+    scene
+        .get_resource_mut::<Capture>()
+        .expect("failed to get capture")
+        .add_frame(image);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_texture_pixels_to_image() {
+        // a 4x2 texture, the bottom row is red and the top row is blue
+        let mut pixels = vec![];
+        for color in [[255, 0, 0, 0], [0, 0, 255, 0]] {
+            for _ in 0..4 {
+                pixels.extend(color);
+            }
+        }
+
+        let image = texture_pixels_to_image(&pixels, [4, 2], [2, 2]);
+
+        assert_eq!(image.dimensions(), (2, 2));
+        assert_eq!(image.get_pixel(0, 0), &image::Rgba([0, 0, 255, 255]));
+        assert_eq!(image.get_pixel(1, 1), &image::Rgba([255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_gif_last_frame_delay() {
+        use image::AnimationDecoder;
+
+        // the process id keeps test runs at the same time from writing the same file
+        let path =
+            std::env::temp_dir().join(format!("lumenpyx_capture_test_{}.gif", std::process::id()));
+
+        let mut capture = Capture::new();
+        capture
+            .start_recording(RecordingFormat::Gif, &path, None)
+            .expect("failed to start recording");
+        for _ in 0..3 {
+            capture.add_frame(RgbaImage::new(2, 2));
+            std::thread::sleep(Duration::from_millis(30));
+        }
+        capture.stop_recording().expect("failed to finish the gif");
+
+        let file = std::io::BufReader::new(File::open(&path).expect("failed to open the gif"));
+        let frames = image::codecs::gif::GifDecoder::new(file)
+            .expect("failed to read the gif")
+            .into_frames()
+            .collect_frames()
+            .expect("failed to decode the gif");
+        std::fs::remove_file(&path).expect("failed to remove the gif");
+
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[2].delay(), frames[1].delay());
+    }
+}
@@ -2,6 +2,7 @@
 // All this only does one thing: exclude the transform from the drawable objects so that they can be used in the ECS without confusion
 
 pub mod primitives {
+    use crate::time::GameTime;
     use lumenpyx::animation::AnimationTimeElapsed;
     use lumenpyx::drawable_object::Drawable;
     use lumenpyx::primitives::Normal;
@@ -13,9 +14,17 @@ pub mod primitives {
     use lumenpyx::Transform;
     use std::ops::Deref;
     use std::ops::DerefMut;
-    use ABC_Game_Engine::DeltaTime;
     use ABC_Game_Engine::EntitiesAndComponents;
-
+    use ABC_Game_Engine::Entity;
+
+    use crate::assets::AssetHandle;
+    use crate::blending::{
+        draw_blended, draw_group, draw_masked, load_blend_textures, load_group_textures,
+        load_mask_textures, BlendBuffer, MaskShape,
+    };
+    use crate::shapes::{
+        fill_arc, fill_ellipse, fill_polygon, fill_rounded_rectangle, stroke_polyline, Shape,
+    };
     use crate::LumenpyxProgram as ABCLumenpyxProgram;
     use crate::OwnedOrMutableDrawable;
 
@@ -113,26 +122,50 @@ pub mod primitives {
         }
     }
 
+    /// the drawables of a `BlendComponent`'s children, blended together in draw order
     pub(crate) struct LumenBlendObject<'a> {
-        blend_1: OwnedOrMutableDrawable<'a>,
-        blend_2: OwnedOrMutableDrawable<'a>,
+        layers: Vec<OwnedOrMutableDrawable<'a>>,
         blend_mode: lumenpyx::blending::BlendMode,
+        /// how many blends this one is inside of, so nested blends don't share textures
+        depth: usize,
         transform: Transform,
     }
 
     impl<'a> LumenBlendObject<'a> {
         pub(crate) fn new(
-            blend_1: OwnedOrMutableDrawable<'a>,
-            blend_2: OwnedOrMutableDrawable<'a>,
+            layers: Vec<OwnedOrMutableDrawable<'a>>,
             blend_mode: lumenpyx::blending::BlendMode,
+            depth: usize,
         ) -> Self {
             Self {
-                blend_1,
-                blend_2,
+                layers,
                 blend_mode,
+                depth,
                 transform: Transform::default(),
             }
         }
+
+        fn get_layers(&self) -> Vec<&dyn Drawable> {
+            self.layers.iter().map(|layer| &**layer).collect()
+        }
+
+        fn draw_buffer(
+            &self,
+            program: &LumenpyxProgram,
+            transform: &Transform,
+            framebuffer: &mut glium::framebuffer::SimpleFrameBuffer,
+            buffer: BlendBuffer,
+        ) {
+            draw_blended(
+                program,
+                &self.get_layers(),
+                self.blend_mode,
+                self.depth,
+                transform,
+                buffer,
+                framebuffer,
+            );
+        }
     }
 
     impl<'a> Drawable for LumenBlendObject<'a> {
@@ -142,14 +175,7 @@ pub mod primitives {
             transform: &Transform,
             albedo_framebuffer: &mut glium::framebuffer::SimpleFrameBuffer,
         ) {
-            // construct the blend object and draw it
-            let blend_object = lumenpyx::blending::BlendObject::new(
-                &*self.blend_1,
-                &*self.blend_2,
-                self.blend_mode,
-            );
-
-            blend_object.draw_albedo(program, transform, albedo_framebuffer);
+            self.draw_buffer(program, transform, albedo_framebuffer, BlendBuffer::Albedo);
         }
 
         fn draw_height(
@@ -158,14 +184,7 @@ pub mod primitives {
             transform: &Transform,
             height_framebuffer: &mut glium::framebuffer::SimpleFrameBuffer,
         ) {
-            // construct the blend object and draw it
-            let blend_object = lumenpyx::blending::BlendObject::new(
-                &*self.blend_1,
-                &*self.blend_2,
-                self.blend_mode,
-            );
-
-            blend_object.draw_height(program, transform, height_framebuffer);
+            self.draw_buffer(program, transform, height_framebuffer, BlendBuffer::Height);
         }
 
         fn draw_normal(
@@ -174,14 +193,7 @@ pub mod primitives {
             transform: &Transform,
             normal_framebuffer: &mut glium::framebuffer::SimpleFrameBuffer,
         ) {
-            // construct the blend object and draw it
-            let blend_object = lumenpyx::blending::BlendObject::new(
-                &*self.blend_1,
-                &*self.blend_2,
-                self.blend_mode,
-            );
-
-            blend_object.draw_normal(program, transform, normal_framebuffer);
+            self.draw_buffer(program, transform, normal_framebuffer, BlendBuffer::Normal);
         }
 
         fn draw_roughness(
@@ -190,14 +202,12 @@ pub mod primitives {
             transform: &Transform,
             roughness_framebuffer: &mut glium::framebuffer::SimpleFrameBuffer,
         ) {
-            // construct the blend object and draw it
-            let blend_object = lumenpyx::blending::BlendObject::new(
-                &*self.blend_1,
-                &*self.blend_2,
-                self.blend_mode,
+            self.draw_buffer(
+                program,
+                transform,
+                roughness_framebuffer,
+                BlendBuffer::Roughness,
             );
-
-            blend_object.draw_roughness(program, transform, roughness_framebuffer);
         }
 
         fn set_transform(&mut self, transform: Transform) {
@@ -205,8 +215,7 @@ pub mod primitives {
         }
 
         fn try_load_shaders(&self, program: &mut LumenpyxProgram) {
-            lumenpyx::blending::BlendObject::new(&*self.blend_1, &*self.blend_2, self.blend_mode)
-                .try_load_shaders(program);
+            load_blend_textures(program, &self.get_layers(), self.depth);
         }
 
         fn get_transform(&self) -> Transform {
@@ -214,24 +223,432 @@ pub mod primitives {
         }
 
         fn get_recieve_shadows_strength(&self) -> f32 {
-            if self.blend_1.get_recieve_shadows_strength() != 0.5 {
-                self.blend_1.get_recieve_shadows_strength()
-            } else if self.blend_2.get_recieve_shadows_strength() != 0.5 {
-                self.blend_2.get_recieve_shadows_strength()
-            } else {
-                0.5
-            }
+            // the first layer that isn't the default decides
+            self.layers
+                .iter()
+                .map(|layer| layer.get_recieve_shadows_strength())
+                .find(|strength| *strength != 0.5)
+                .unwrap_or(0.5)
         }
     }
 
+    /// Why a `BlendComponent` wasn't drawn as a blend in the last frame
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum BlendDiagnostic {
+        /// a blend needs at least two drawables in its children
+        /// with fewer than two children they are drawn normally, otherwise they aren't drawn until there are enough drawables
+        TooFewLayers { found: usize },
+    }
+
+    /// Blends the drawables of all of an entity's children together, ordered by their z
+    /// a child can have a `BlendComponent` itself, then its children are blended first and used as one layer
     #[derive(Clone, Copy, Debug)]
     pub struct BlendComponent {
         pub(crate) lumen_blend_mode: lumenpyx::blending::BlendMode,
+        pub(crate) diagnostic: Option<BlendDiagnostic>,
     }
 
     impl BlendComponent {
         pub fn new(lumen_blend_mode: lumenpyx::blending::BlendMode) -> Self {
-            Self { lumen_blend_mode }
+            Self {
+                lumen_blend_mode,
+                diagnostic: None,
+            }
+        }
+
+        /// the problem with this blend from the last frame it was rendered, None if it was drawn fine
+        pub fn get_diagnostic(&self) -> Option<BlendDiagnostic> {
+            self.diagnostic
+        }
+    }
+
+    /// the drawables of a `MaskComponent`'s children, clipped by the mask
+    pub(crate) struct LumenMaskObject<'a> {
+        layers: Vec<OwnedOrMutableDrawable<'a>>,
+        /// the drawables of the mask entity, empty for a clip rect
+        mask_layers: Vec<OwnedOrMutableDrawable<'a>>,
+        mask: MaskComponent,
+        /// how many blends and masks this one is inside of, so nested ones don't share textures
+        depth: usize,
+        transform: Transform,
+    }
+
+    impl<'a> LumenMaskObject<'a> {
+        pub(crate) fn new(
+            layers: Vec<OwnedOrMutableDrawable<'a>>,
+            mask_layers: Vec<OwnedOrMutableDrawable<'a>>,
+            mask: MaskComponent,
+            depth: usize,
+        ) -> Self {
+            Self {
+                layers,
+                mask_layers,
+                mask,
+                depth,
+                transform: Transform::default(),
+            }
+        }
+
+        fn draw_buffer(
+            &self,
+            program: &LumenpyxProgram,
+            transform: &Transform,
+            framebuffer: &mut glium::framebuffer::SimpleFrameBuffer,
+            buffer: BlendBuffer,
+        ) {
+            let layers = self.layers.iter().map(|layer| &**layer).collect::<Vec<_>>();
+            let mask_layers = self
+                .mask_layers
+                .iter()
+                .map(|layer| &**layer)
+                .collect::<Vec<_>>();
+
+            draw_masked(
+                program,
+                &layers,
+                &self.get_shape(&mask_layers),
+                self.mask.invert,
+                self.mask.softness,
+                self.depth,
+                transform,
+                buffer,
+                framebuffer,
+            );
+        }
+
+        fn get_shape<'b>(&self, mask_layers: &'b [&'b dyn Drawable]) -> MaskShape<'b> {
+            match self.mask.source {
+                MaskSource::Entity(_) => MaskShape::Layers(mask_layers),
+                MaskSource::ClipRect { width, height } => MaskShape::ClipRect { width, height },
+            }
+        }
+    }
+
+    impl<'a> Drawable for LumenMaskObject<'a> {
+        fn draw_albedo(
+            &self,
+            program: &LumenpyxProgram,
+            transform: &Transform,
+            albedo_framebuffer: &mut glium::framebuffer::SimpleFrameBuffer,
+        ) {
+            self.draw_buffer(program, transform, albedo_framebuffer, BlendBuffer::Albedo);
+        }
+
+        fn draw_height(
+            &self,
+            program: &LumenpyxProgram,
+            transform: &Transform,
+            height_framebuffer: &mut glium::framebuffer::SimpleFrameBuffer,
+        ) {
+            self.draw_buffer(program, transform, height_framebuffer, BlendBuffer::Height);
+        }
+
+        fn draw_normal(
+            &self,
+            program: &LumenpyxProgram,
+            transform: &Transform,
+            normal_framebuffer: &mut glium::framebuffer::SimpleFrameBuffer,
+        ) {
+            self.draw_buffer(program, transform, normal_framebuffer, BlendBuffer::Normal);
+        }
+
+        fn draw_roughness(
+            &self,
+            program: &LumenpyxProgram,
+            transform: &Transform,
+            roughness_framebuffer: &mut glium::framebuffer::SimpleFrameBuffer,
+        ) {
+            self.draw_buffer(
+                program,
+                transform,
+                roughness_framebuffer,
+                BlendBuffer::Roughness,
+            );
+        }
+
+        fn set_transform(&mut self, transform: Transform) {
+            self.transform = transform;
+        }
+
+        fn try_load_shaders(&self, program: &mut LumenpyxProgram) {
+            let layers = self.layers.iter().map(|layer| &**layer).collect::<Vec<_>>();
+            let mask_layers = self
+                .mask_layers
+                .iter()
+                .map(|layer| &**layer)
+                .collect::<Vec<_>>();
+
+            load_mask_textures(program, &layers, &self.get_shape(&mask_layers), self.depth);
+        }
+
+        fn get_transform(&self) -> Transform {
+            self.transform
+        }
+
+        fn get_recieve_shadows_strength(&self) -> f32 {
+            // the first layer that isn't the default decides
+            self.layers
+                .iter()
+                .map(|layer| layer.get_recieve_shadows_strength())
+                .find(|strength| *strength != 0.5)
+                .unwrap_or(0.5)
+        }
+    }
+
+    /// What a `MaskComponent` clips its children with
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum MaskSource {
+        /// the alpha of this entity's drawables, it can be one of the children or any other entity
+        /// either way it's only drawn as the mask, and it can only be the mask of one `MaskComponent`
+        Entity(Entity),
+        /// a rectangle in pixels around the mask's position, for scrolling panels and the like
+        ClipRect { width: f32, height: f32 },
+    }
+
+    /// Why a `MaskComponent` wasn't drawn as a mask in the last frame
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum MaskDiagnostic {
+        /// there are no drawables in the children other than the mask, so there is nothing to clip
+        NothingToClip,
+        /// the mask entity has no drawables, until it does the children aren't drawn
+        EmptyMask,
+        /// another `MaskComponent` already uses the same mask entity
+        SharedMask,
+        /// the mask entity is the entity with the `MaskComponent` or one of its parents
+        MaskIsParent,
+        /// the entity also has a `BlendComponent`, they can't both draw the same children so only the blend is drawn
+        HasBlend,
+    }
+
+    /// Clips the drawables of all of an entity's children to a mask
+    /// the children are drawn over each other in order of their z, then cut out as one image
+    #[derive(Clone, Copy, Debug)]
+    pub struct MaskComponent {
+        pub(crate) source: MaskSource,
+        pub(crate) invert: bool,
+        pub(crate) softness: f32,
+        pub(crate) diagnostic: Option<MaskDiagnostic>,
+    }
+
+    impl MaskComponent {
+        /// clip the children to the drawables of an entity, like a circle for a spotlight effect
+        pub fn from_entity(mask: Entity) -> Self {
+            Self::new(MaskSource::Entity(mask))
+        }
+
+        /// clip the children to a rectangle around this entity, in pixels
+        pub fn clip_rect(width: f32, height: f32) -> Self {
+            Self::new(MaskSource::ClipRect { width, height })
+        }
+
+        pub fn new(source: MaskSource) -> Self {
+            Self {
+                source,
+                invert: false,
+                softness: 0.0,
+                diagnostic: None,
+            }
+        }
+
+        /// show the children only outside of the mask instead of inside
+        pub fn with_invert(mut self, invert: bool) -> Self {
+            self.invert = invert;
+            self
+        }
+
+        /// fade the edge of the mask out over this many pixels, 0 is a hard edge
+        pub fn with_softness(mut self, softness: f32) -> Self {
+            self.softness = softness;
+            self
+        }
+
+        pub fn set_source(&mut self, source: MaskSource) {
+            self.source = source;
+        }
+
+        pub fn get_source(&self) -> MaskSource {
+            self.source
+        }
+
+        pub fn set_invert(&mut self, invert: bool) {
+            self.invert = invert;
+        }
+
+        pub fn get_invert(&self) -> bool {
+            self.invert
+        }
+
+        pub fn set_softness(&mut self, softness: f32) {
+            self.softness = softness;
+        }
+
+        pub fn get_softness(&self) -> f32 {
+            self.softness
+        }
+
+        /// the problem with this mask from the last frame it was rendered, None if it was drawn fine
+        pub fn get_diagnostic(&self) -> Option<MaskDiagnostic> {
+            self.diagnostic
+        }
+    }
+
+    /// an entity and all its children, drawn together and faded as one image
+    pub(crate) struct LumenGroupObject<'a> {
+        layers: Vec<OwnedOrMutableDrawable<'a>>,
+        group: OpacityGroup,
+        /// how many blends, masks and groups this one is inside of, so nested ones don't share textures
+        depth: usize,
+        transform: Transform,
+    }
+
+    impl<'a> LumenGroupObject<'a> {
+        pub(crate) fn new(
+            layers: Vec<OwnedOrMutableDrawable<'a>>,
+            group: OpacityGroup,
+            depth: usize,
+        ) -> Self {
+            Self {
+                layers,
+                group,
+                depth,
+                transform: Transform::default(),
+            }
+        }
+
+        fn get_layers(&self) -> Vec<&dyn Drawable> {
+            self.layers.iter().map(|layer| &**layer).collect()
+        }
+
+        fn draw_buffer(
+            &self,
+            program: &LumenpyxProgram,
+            transform: &Transform,
+            framebuffer: &mut glium::framebuffer::SimpleFrameBuffer,
+            buffer: BlendBuffer,
+        ) {
+            draw_group(
+                program,
+                &self.get_layers(),
+                self.group.opacity,
+                self.group.blend_mode,
+                self.depth,
+                transform,
+                buffer,
+                framebuffer,
+            );
+        }
+    }
+
+    impl<'a> Drawable for LumenGroupObject<'a> {
+        fn draw_albedo(
+            &self,
+            program: &LumenpyxProgram,
+            transform: &Transform,
+            albedo_framebuffer: &mut glium::framebuffer::SimpleFrameBuffer,
+        ) {
+            self.draw_buffer(program, transform, albedo_framebuffer, BlendBuffer::Albedo);
+        }
+
+        fn draw_height(
+            &self,
+            program: &LumenpyxProgram,
+            transform: &Transform,
+            height_framebuffer: &mut glium::framebuffer::SimpleFrameBuffer,
+        ) {
+            self.draw_buffer(program, transform, height_framebuffer, BlendBuffer::Height);
+        }
+
+        fn draw_normal(
+            &self,
+            program: &LumenpyxProgram,
+            transform: &Transform,
+            normal_framebuffer: &mut glium::framebuffer::SimpleFrameBuffer,
+        ) {
+            self.draw_buffer(program, transform, normal_framebuffer, BlendBuffer::Normal);
+        }
+
+        fn draw_roughness(
+            &self,
+            program: &LumenpyxProgram,
+            transform: &Transform,
+            roughness_framebuffer: &mut glium::framebuffer::SimpleFrameBuffer,
+        ) {
+            self.draw_buffer(
+                program,
+                transform,
+                roughness_framebuffer,
+                BlendBuffer::Roughness,
+            );
+        }
+
+        fn set_transform(&mut self, transform: Transform) {
+            self.transform = transform;
+        }
+
+        fn try_load_shaders(&self, program: &mut LumenpyxProgram) {
+            load_group_textures(program, &self.get_layers(), self.depth);
+        }
+
+        fn get_transform(&self) -> Transform {
+            self.transform
+        }
+
+        fn get_recieve_shadows_strength(&self) -> f32 {
+            // the first layer that isn't the default decides
+            self.layers
+                .iter()
+                .map(|layer| layer.get_recieve_shadows_strength())
+                .find(|strength| *strength != 0.5)
+                .unwrap_or(0.5)
+        }
+    }
+
+    /// How an `OpacityGroup` is drawn over what's below it
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub enum GroupBlendMode {
+        #[default]
+        Normal,
+        Additive,
+        Subtractive,
+        Multiplicative,
+    }
+
+    /// Draws an entity and all its children as one image, then fades it with one opacity
+    /// so the parts of a character don't show through each other when it fades out
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct OpacityGroup {
+        pub(crate) opacity: f32,
+        pub(crate) blend_mode: GroupBlendMode,
+    }
+
+    impl OpacityGroup {
+        /// opacity goes from 0 (invisible) to 1
+        pub fn new(opacity: f32) -> Self {
+            Self {
+                opacity,
+                blend_mode: GroupBlendMode::Normal,
+            }
+        }
+
+        pub fn with_blend_mode(mut self, blend_mode: GroupBlendMode) -> Self {
+            self.blend_mode = blend_mode;
+            self
+        }
+
+        pub fn set_opacity(&mut self, opacity: f32) {
+            self.opacity = opacity;
+        }
+
+        pub fn get_opacity(&self) -> f32 {
+            self.opacity
+        }
+
+        pub fn set_blend_mode(&mut self, blend_mode: GroupBlendMode) {
+            self.blend_mode = blend_mode;
+        }
+
+        pub fn get_blend_mode(&self) -> GroupBlendMode {
+            self.blend_mode
         }
     }
 
@@ -241,9 +658,12 @@ pub mod primitives {
     pub struct Animation {
         lumen_animation: lumenpyx::animation::Animation,
         start_time: f64,
+        /// keeps textures from `Assets` loaded for as long as the animation is around
+        asset: Option<AssetHandle>,
     }
 
     impl Animation {
+        /// loads the textures every time, `Assets::load_animation_from_images` shares them and returns errors instead of panicking
         pub fn new_from_images(
             albedo: Texture,
             height: Texture,
@@ -261,8 +681,8 @@ pub mod primitives {
             Vec<TextureHandle>,
         ) {
             let total_time = entities_and_components
-                .get_resource::<DeltaTime>()
-                .expect("Failed to get DeltaTime resource")
+                .get_resource::<GameTime>()
+                .expect("Failed to get GameTime resource")
                 .get_total_time();
 
             let program = entities_and_components
@@ -286,6 +706,7 @@ pub mod primitives {
                 Self {
                     lumen_animation: anim,
                     start_time: total_time,
+                    asset: None,
                 },
                 albedo,
                 height,
@@ -294,6 +715,7 @@ pub mod primitives {
             )
         }
 
+        /// loads the textures every time, `Assets::load_animation_from_spritesheet` shares them and returns errors instead of panicking
         pub fn new_from_spritesheet(
             albedo: Texture,
             height: Texture,
@@ -311,8 +733,8 @@ pub mod primitives {
             Vec<TextureHandle>,
         ) {
             let total_time = entities_and_components
-                .get_resource::<DeltaTime>()
-                .expect("Failed to get DeltaTime resource")
+                .get_resource::<GameTime>()
+                .expect("Failed to get GameTime resource")
                 .get_total_time();
 
             let program = entities_and_components
@@ -336,6 +758,7 @@ pub mod primitives {
                 Self {
                     lumen_animation: anim,
                     start_time: total_time,
+                    asset: None,
                 },
                 albedo,
                 height,
@@ -354,8 +777,8 @@ pub mod primitives {
             entities_and_components: &mut EntitiesAndComponents,
         ) -> Self {
             let total_time = entities_and_components
-                .get_resource::<DeltaTime>()
-                .expect("Failed to get DeltaTime resource")
+                .get_resource::<GameTime>()
+                .expect("Failed to get GameTime resource")
                 .get_total_time();
 
             let program = entities_and_components
@@ -374,15 +797,26 @@ pub mod primitives {
                     loop_animation,
                 ),
                 start_time: total_time,
+                asset: None,
             }
         }
 
+        pub(crate) fn with_asset_handle(mut self, handle: AssetHandle) -> Self {
+            self.asset = Some(handle);
+            self
+        }
+
+        /// the textures it uses if it was loaded with `Assets`
+        pub fn get_asset_handle(&self) -> Option<&AssetHandle> {
+            self.asset.as_ref()
+        }
+
         pub fn restart_animation(&mut self, entities_and_components: &EntitiesAndComponents) {
             self.lumen_animation.restart_animation();
 
             self.start_time = entities_and_components
-                .get_resource::<DeltaTime>()
-                .expect("Failed to get DeltaTime resource")
+                .get_resource::<GameTime>()
+                .expect("Failed to get GameTime resource")
                 .get_total_time();
         }
 
@@ -479,8 +913,8 @@ pub mod primitives {
             entities_and_components: &EntitiesAndComponents,
         ) -> Self {
             let total_time = entities_and_components
-                .get_resource::<DeltaTime>()
-                .expect("Failed to get DeltaTime resource")
+                .get_resource::<GameTime>()
+                .expect("Failed to get GameTime resource")
                 .get_total_time();
 
             let mut animations_to_use = Vec::new();
@@ -769,12 +1203,15 @@ pub mod primitives {
         }
     }
 
-    #[derive(Clone, Copy)]
+    #[derive(Clone)]
     pub struct Sprite {
         lumen_sprite: lumenpyx::primitives::Sprite,
+        /// keeps textures from `Assets` loaded for as long as the sprite is around
+        asset: Option<AssetHandle>,
     }
 
     impl Sprite {
+        /// loads the textures every time, `Assets::load_sprite` shares them and returns errors instead of panicking
         pub fn new(
             albedo: TextureInput,
             height: TextureInput,
@@ -800,6 +1237,7 @@ pub mod primitives {
             (
                 Self {
                     lumen_sprite: sprite,
+                    asset: None,
                 },
                 albedo,
                 height,
@@ -807,6 +1245,16 @@ pub mod primitives {
                 normal,
             )
         }
+
+        pub(crate) fn with_asset_handle(mut self, handle: AssetHandle) -> Self {
+            self.asset = Some(handle);
+            self
+        }
+
+        /// the textures it uses if it was loaded with `Assets`
+        pub fn get_asset_handle(&self) -> Option<&AssetHandle> {
+            self.asset.as_ref()
+        }
     }
 
     impl Drawable for Sprite {
@@ -1057,6 +1505,519 @@ pub mod primitives {
             &mut self.lumen_sphere
         }
     }
+
+    /// How the ends of a `Line` or `Polyline` look
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub enum LineCap {
+        /// the line stops right at its end points
+        #[default]
+        Butt,
+        /// the line goes past its end points by half its thickness
+        Square,
+        /// half a circle around each end point
+        Round,
+    }
+
+    /// the color, height and roughness methods and the drawable impl every flat shape shares
+    /// the shape needs a `shape: Shape` field, the noun is only for the docs
+    macro_rules! shape_drawable {
+        ($name:ident, $noun:literal) => {
+            impl $name {
+                #[doc = concat!("how high the flat top of the ", $noun, " is, in pixels")]
+                pub fn with_height(mut self, height: f32) -> Self {
+                    self.shape.height = height;
+                    self
+                }
+
+                /// from 0 (shiny) to 1
+                pub fn with_roughness(mut self, roughness: f32) -> Self {
+                    self.shape.roughness = roughness;
+                    self
+                }
+
+                pub fn set_color(&mut self, color: [f32; 4]) {
+                    self.shape.color = color;
+                }
+
+                pub fn get_color(&self) -> [f32; 4] {
+                    self.shape.color
+                }
+
+                pub fn set_height(&mut self, height: f32) {
+                    self.shape.height = height;
+                }
+
+                pub fn get_height(&self) -> f32 {
+                    self.shape.height
+                }
+
+                pub fn set_roughness(&mut self, roughness: f32) {
+                    self.shape.roughness = roughness;
+                }
+
+                pub fn get_roughness(&self) -> f32 {
+                    self.shape.roughness
+                }
+
+                pub fn set_shadow_strength(&mut self, shadow_strength: f32) {
+                    self.shape.shadow_strength = shadow_strength;
+                }
+            }
+
+            impl Drawable for $name {
+                fn draw_albedo(
+                    &self,
+                    program: &LumenpyxProgram,
+                    transform: &Transform,
+                    albedo_framebuffer: &mut glium::framebuffer::SimpleFrameBuffer,
+                ) {
+                    self.shape
+                        .draw_albedo(program, transform, albedo_framebuffer);
+                }
+
+                fn draw_height(
+                    &self,
+                    program: &LumenpyxProgram,
+                    transform: &Transform,
+                    height_framebuffer: &mut glium::framebuffer::SimpleFrameBuffer,
+                ) {
+                    self.shape
+                        .draw_height(program, transform, height_framebuffer);
+                }
+
+                fn draw_normal(
+                    &self,
+                    program: &LumenpyxProgram,
+                    transform: &Transform,
+                    normal_framebuffer: &mut glium::framebuffer::SimpleFrameBuffer,
+                ) {
+                    self.shape
+                        .draw_normal(program, transform, normal_framebuffer);
+                }
+
+                fn draw_roughness(
+                    &self,
+                    program: &LumenpyxProgram,
+                    transform: &Transform,
+                    roughness_framebuffer: &mut glium::framebuffer::SimpleFrameBuffer,
+                ) {
+                    self.shape
+                        .draw_roughness(program, transform, roughness_framebuffer);
+                }
+
+                fn set_transform(&mut self, transform: Transform) {
+                    self.shape.set_transform(transform);
+                }
+
+                fn try_load_shaders(&self, program: &mut LumenpyxProgram) {
+                    self.shape.try_load_shaders(program);
+                }
+
+                fn get_transform(&self) -> Transform {
+                    self.shape.get_transform()
+                }
+
+                fn get_recieve_shadows_strength(&self) -> f32 {
+                    self.shape.get_recieve_shadows_strength()
+                }
+            }
+        };
+    }
+
+    /// A straight line between two points, relative to the transform
+    pub struct Line {
+        start: [f32; 2],
+        end: [f32; 2],
+        thickness: f32,
+        cap: LineCap,
+        shape: Shape,
+    }
+
+    impl Line {
+        pub fn new(color: [f32; 4], start: [f32; 2], end: [f32; 2], thickness: f32) -> Self {
+            let mut line = Self {
+                start,
+                end,
+                thickness,
+                cap: LineCap::Butt,
+                shape: Shape::new(color),
+            };
+            line.update_shape();
+            line
+        }
+
+        pub fn with_cap(mut self, cap: LineCap) -> Self {
+            self.set_cap(cap);
+            self
+        }
+
+        pub fn set_points(&mut self, start: [f32; 2], end: [f32; 2]) {
+            self.start = start;
+            self.end = end;
+            self.update_shape();
+        }
+
+        pub fn get_start(&self) -> [f32; 2] {
+            self.start
+        }
+
+        pub fn get_end(&self) -> [f32; 2] {
+            self.end
+        }
+
+        pub fn set_thickness(&mut self, thickness: f32) {
+            self.thickness = thickness;
+            self.update_shape();
+        }
+
+        pub fn get_thickness(&self) -> f32 {
+            self.thickness
+        }
+
+        pub fn set_cap(&mut self, cap: LineCap) {
+            self.cap = cap;
+            self.update_shape();
+        }
+
+        pub fn get_cap(&self) -> LineCap {
+            self.cap
+        }
+
+        fn update_shape(&mut self) {
+            self.shape.set_triangles(stroke_polyline(
+                &[self.start, self.end],
+                self.thickness,
+                self.cap,
+                false,
+            ));
+        }
+    }
+
+    shape_drawable!(Line, "line");
+
+    /// A line through any number of points, relative to the transform
+    /// the corners are mitered, and cut off when they're very sharp
+    pub struct Polyline {
+        points: Vec<[f32; 2]>,
+        thickness: f32,
+        cap: LineCap,
+        closed: bool,
+        shape: Shape,
+    }
+
+    impl Polyline {
+        pub fn new(color: [f32; 4], points: Vec<[f32; 2]>, thickness: f32) -> Self {
+            let mut polyline = Self {
+                points,
+                thickness,
+                cap: LineCap::Butt,
+                closed: false,
+                shape: Shape::new(color),
+            };
+            polyline.update_shape();
+            polyline
+        }
+
+        pub fn with_cap(mut self, cap: LineCap) -> Self {
+            self.set_cap(cap);
+            self
+        }
+
+        /// join the last point back to the first, a closed line has no caps
+        pub fn with_closed(mut self, closed: bool) -> Self {
+            self.set_closed(closed);
+            self
+        }
+
+        pub fn set_points(&mut self, points: Vec<[f32; 2]>) {
+            self.points = points;
+            self.update_shape();
+        }
+
+        pub fn get_points(&self) -> &[[f32; 2]] {
+            &self.points
+        }
+
+        pub fn set_thickness(&mut self, thickness: f32) {
+            self.thickness = thickness;
+            self.update_shape();
+        }
+
+        pub fn get_thickness(&self) -> f32 {
+            self.thickness
+        }
+
+        pub fn set_cap(&mut self, cap: LineCap) {
+            self.cap = cap;
+            self.update_shape();
+        }
+
+        pub fn get_cap(&self) -> LineCap {
+            self.cap
+        }
+
+        pub fn set_closed(&mut self, closed: bool) {
+            self.closed = closed;
+            self.update_shape();
+        }
+
+        pub fn is_closed(&self) -> bool {
+            self.closed
+        }
+
+        fn update_shape(&mut self) {
+            self.shape.set_triangles(stroke_polyline(
+                &self.points,
+                self.thickness,
+                self.cap,
+                self.closed,
+            ));
+        }
+    }
+
+    shape_drawable!(Polyline, "line");
+
+    /// A filled shape with any number of corners, relative to the transform
+    /// it can be concave, but the edges shouldn't cross each other
+    pub struct Polygon {
+        points: Vec<[f32; 2]>,
+        shape: Shape,
+    }
+
+    impl Polygon {
+        pub fn new(color: [f32; 4], points: Vec<[f32; 2]>) -> Self {
+            let mut polygon = Self {
+                points,
+                shape: Shape::new(color),
+            };
+            polygon.update_shape();
+            polygon
+        }
+
+        pub fn set_points(&mut self, points: Vec<[f32; 2]>) {
+            self.points = points;
+            self.update_shape();
+        }
+
+        pub fn get_points(&self) -> &[[f32; 2]] {
+            &self.points
+        }
+
+        fn update_shape(&mut self) {
+            self.shape.set_triangles(fill_polygon(&self.points));
+        }
+    }
+
+    shape_drawable!(Polygon, "polygon");
+
+    /// A filled ellipse centered on the transform
+    pub struct Ellipse {
+        radius_x: f32,
+        radius_y: f32,
+        shape: Shape,
+    }
+
+    impl Ellipse {
+        pub fn new(color: [f32; 4], radius_x: f32, radius_y: f32) -> Self {
+            let mut ellipse = Self {
+                radius_x,
+                radius_y,
+                shape: Shape::new(color),
+            };
+            ellipse.update_shape();
+            ellipse
+        }
+
+        pub fn set_radii(&mut self, radius_x: f32, radius_y: f32) {
+            self.radius_x = radius_x;
+            self.radius_y = radius_y;
+            self.update_shape();
+        }
+
+        pub fn get_radii(&self) -> [f32; 2] {
+            [self.radius_x, self.radius_y]
+        }
+
+        fn update_shape(&mut self) {
+            self.shape
+                .set_triangles(fill_ellipse(self.radius_x, self.radius_y));
+        }
+    }
+
+    shape_drawable!(Ellipse, "ellipse");
+
+    /// A filled rectangle centered on the transform, with round corners
+    /// the corner radius is made smaller if it's more than half of the shortest side
+    pub struct RoundedRectangle {
+        width: f32,
+        height: f32,
+        corner_radius: f32,
+        shape: Shape,
+    }
+
+    impl RoundedRectangle {
+        pub fn new(color: [f32; 4], width: f32, height: f32, corner_radius: f32) -> Self {
+            let mut rounded_rectangle = Self {
+                width,
+                height,
+                corner_radius,
+                shape: Shape::new(color),
+            };
+            rounded_rectangle.update_shape();
+            rounded_rectangle
+        }
+
+        pub fn set_size(&mut self, width: f32, height: f32) {
+            self.width = width;
+            self.height = height;
+            self.update_shape();
+        }
+
+        pub fn get_size(&self) -> [f32; 2] {
+            [self.width, self.height]
+        }
+
+        pub fn set_corner_radius(&mut self, corner_radius: f32) {
+            self.corner_radius = corner_radius;
+            self.update_shape();
+        }
+
+        pub fn get_corner_radius(&self) -> f32 {
+            self.corner_radius
+        }
+
+        fn update_shape(&mut self) {
+            self.shape.set_triangles(fill_rounded_rectangle(
+                self.width,
+                self.height,
+                self.corner_radius,
+            ));
+        }
+    }
+
+    shape_drawable!(RoundedRectangle, "rectangle");
+
+    /// A circle with a round hole in the middle, centered on the transform
+    pub struct Ring {
+        radius: f32,
+        inner_radius: f32,
+        shape: Shape,
+    }
+
+    impl Ring {
+        pub fn new(color: [f32; 4], radius: f32, inner_radius: f32) -> Self {
+            let mut ring = Self {
+                radius,
+                inner_radius,
+                shape: Shape::new(color),
+            };
+            ring.update_shape();
+            ring
+        }
+
+        pub fn set_radius(&mut self, radius: f32) {
+            self.radius = radius;
+            self.update_shape();
+        }
+
+        pub fn get_radius(&self) -> f32 {
+            self.radius
+        }
+
+        pub fn set_inner_radius(&mut self, inner_radius: f32) {
+            self.inner_radius = inner_radius;
+            self.update_shape();
+        }
+
+        pub fn get_inner_radius(&self) -> f32 {
+            self.inner_radius
+        }
+
+        fn update_shape(&mut self) {
+            self.shape.set_triangles(fill_arc(
+                self.radius,
+                self.inner_radius,
+                0.0,
+                std::f32::consts::TAU,
+            ));
+        }
+    }
+
+    shape_drawable!(Ring, "ring");
+
+    /// A slice of a circle between two angles, centered on the transform
+    /// angles are in radians, counterclockwise from the right
+    /// without an inner radius it's a pie slice, with one it's part of a ring, like a cooldown indicator
+    /// called ArcShape so it doesn't clash with `std::sync::Arc` when the prelude is glob imported
+    pub struct ArcShape {
+        radius: f32,
+        inner_radius: f32,
+        start_angle: f32,
+        end_angle: f32,
+        shape: Shape,
+    }
+
+    impl ArcShape {
+        pub fn new(color: [f32; 4], radius: f32, start_angle: f32, end_angle: f32) -> Self {
+            let mut arc = Self {
+                radius,
+                inner_radius: 0.0,
+                start_angle,
+                end_angle,
+                shape: Shape::new(color),
+            };
+            arc.update_shape();
+            arc
+        }
+
+        pub fn with_inner_radius(mut self, inner_radius: f32) -> Self {
+            self.set_inner_radius(inner_radius);
+            self
+        }
+
+        pub fn set_radius(&mut self, radius: f32) {
+            self.radius = radius;
+            self.update_shape();
+        }
+
+        pub fn get_radius(&self) -> f32 {
+            self.radius
+        }
+
+        pub fn set_inner_radius(&mut self, inner_radius: f32) {
+            self.inner_radius = inner_radius;
+            self.update_shape();
+        }
+
+        pub fn get_inner_radius(&self) -> f32 {
+            self.inner_radius
+        }
+
+        pub fn set_angles(&mut self, start_angle: f32, end_angle: f32) {
+            self.start_angle = start_angle;
+            self.end_angle = end_angle;
+            self.update_shape();
+        }
+
+        pub fn get_start_angle(&self) -> f32 {
+            self.start_angle
+        }
+
+        pub fn get_end_angle(&self) -> f32 {
+            self.end_angle
+        }
+
+        fn update_shape(&mut self) {
+            self.shape.set_triangles(fill_arc(
+                self.radius,
+                self.inner_radius,
+                self.start_angle,
+                self.end_angle,
+            ));
+        }
+    }
+
+    shape_drawable!(ArcShape, "arc");
 }
 
 // now for the lights
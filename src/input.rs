@@ -0,0 +1,117 @@
+//! Extra input state that doesn't fit in the ABC Game Engine's `Input` resource
+
+use winit::event::MouseScrollDelta;
+
+pub mod actions;
+pub mod gamepad;
+pub mod recording;
+
+/// the amount of pixels a single line of scrolling is worth by default
+pub const DEFAULT_PIXELS_PER_LINE: f32 = 20.0;
+
+/// The amount the mouse wheel (or trackpad) scrolled during a frame
+/// line and pixel scrolling are kept apart so precise devices like trackpads aren't treated like wheel notches
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MouseWheelDelta {
+    /// [x, y] scroll reported in lines, usually by a mouse wheel
+    pub lines: [f32; 2],
+    /// [x, y] scroll reported in pixels, usually by a trackpad
+    pub pixels: [f32; 2],
+}
+
+impl MouseWheelDelta {
+    /// adds a winit scroll event to the delta, multiple events in the same frame are summed
+    pub fn accumulate(&mut self, delta: MouseScrollDelta) {
+        match delta {
+            MouseScrollDelta::LineDelta(x, y) => {
+                self.lines[0] += x;
+                self.lines[1] += y;
+            }
+            MouseScrollDelta::PixelDelta(physical_position) => {
+                self.pixels[0] += physical_position.x as f32;
+                self.pixels[1] += physical_position.y as f32;
+            }
+        }
+    }
+
+    /// the total scroll converted to lines
+    pub fn in_lines(&self, pixels_per_line: f32) -> [f32; 2] {
+        [
+            self.lines[0] + self.pixels[0] / pixels_per_line,
+            self.lines[1] + self.pixels[1] / pixels_per_line,
+        ]
+    }
+
+    /// the total scroll converted to pixels
+    pub fn in_pixels(&self, pixels_per_line: f32) -> [f32; 2] {
+        [
+            self.lines[0] * pixels_per_line + self.pixels[0],
+            self.lines[1] * pixels_per_line + self.pixels[1],
+        ]
+    }
+
+    /// returns true if nothing was scrolled
+    pub fn is_zero(&self) -> bool {
+        self.lines == [0.0, 0.0] && self.pixels == [0.0, 0.0]
+    }
+}
+
+/// turn a mouse button id from winit into the engine's mouse button
+pub(crate) fn mouse_button_from_id(button: u32) -> ABC_Game_Engine::MouseButton {
+    match button {
+        0 => ABC_Game_Engine::MouseButton::Left,
+        1 => ABC_Game_Engine::MouseButton::Right,
+        2 => ABC_Game_Engine::MouseButton::Middle,
+        other => ABC_Game_Engine::MouseButton::Other(other),
+    }
+}
+
+/// The state of a button for the current frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ButtonState {
+    /// the button went down this frame
+    Pressed,
+    /// the button has been down for more than one frame
+    Held,
+    /// the button went up this frame
+    Released,
+    NotPressed,
+}
+
+impl ButtonState {
+    /// get the state from whether the button was down last frame and whether it is down this frame
+    pub fn from_frames(was_down: bool, is_down: bool) -> Self {
+        match (was_down, is_down) {
+            (false, true) => ButtonState::Pressed,
+            (true, true) => ButtonState::Held,
+            (true, false) => ButtonState::Released,
+            (false, false) => ButtonState::NotPressed,
+        }
+    }
+
+    /// returns true if the button is down, either pressed this frame or held
+    pub fn is_down(&self) -> bool {
+        matches!(self, ButtonState::Pressed | ButtonState::Held)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::dpi::PhysicalPosition;
+
+    #[test]
+    fn test_mouse_wheel_accumulates() {
+        let mut delta = MouseWheelDelta::default();
+        delta.accumulate(MouseScrollDelta::LineDelta(0.0, 1.0));
+        delta.accumulate(MouseScrollDelta::LineDelta(1.0, 2.0));
+        delta.accumulate(MouseScrollDelta::PixelDelta(PhysicalPosition::new(
+            10.0, -20.0,
+        )));
+
+        assert_eq!(delta.lines, [1.0, 3.0]);
+        assert_eq!(delta.pixels, [10.0, -20.0]);
+        assert_eq!(delta.in_lines(10.0), [2.0, 1.0]);
+        assert_eq!(delta.in_pixels(10.0), [20.0, 10.0]);
+    }
+}
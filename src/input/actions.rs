@@ -0,0 +1,757 @@
+//! Named actions and axes on top of the raw input, so games don't have to hardcode keys
+//! bindings can be changed at runtime and saved to or loaded from a config file
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+
+use ABC_Game_Engine::{KeyCode, Resource};
+
+use super::gamepad::{GamepadAxis, GamepadButton, Gamepads};
+use super::ButtonState;
+
+/// an action is down when its value is at least this
+pub const ACTION_PRESS_THRESHOLD: f32 = 0.5;
+
+/// A single input that can be bound to an action or an axis
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(KeyCode),
+    /// a mouse button by the id winit gives it, 0 is left, 1 is right and 2 is middle
+    MouseButton(u32),
+    /// scrolling in a direction, the value is the amount of lines scrolled
+    MouseWheel(WheelDirection),
+    /// a button on any connected gamepad
+    GamepadButton(GamepadButton),
+    /// one direction of an axis on any connected gamepad, deadzones are applied
+    GamepadAxis(GamepadAxis, AxisDirection),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WheelDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AxisDirection {
+    Positive,
+    Negative,
+}
+
+/// The raw input of a frame that the action map reads from
+pub struct RawInput<'a> {
+    pub keys_down: &'a HashSet<KeyCode>,
+    pub mouse_buttons_down: &'a HashSet<u32>,
+    /// the [x, y] scroll of the frame in lines
+    pub mouse_wheel_lines: [f32; 2],
+    pub gamepads: Option<&'a Gamepads>,
+}
+
+impl RawInput<'_> {
+    /// keys and buttons are worth 1.0 when down, the wheel and gamepad axes are worth how far they moved
+    fn get_binding_value(&self, binding: &Binding) -> f32 {
+        match binding {
+            Binding::Key(key) => bool_to_value(self.keys_down.contains(key)),
+            Binding::MouseButton(button) => bool_to_value(self.mouse_buttons_down.contains(button)),
+            Binding::MouseWheel(direction) => {
+                let [x, y] = self.mouse_wheel_lines;
+                match direction {
+                    WheelDirection::Up => y.max(0.0),
+                    WheelDirection::Down => (-y).max(0.0),
+                    WheelDirection::Right => x.max(0.0),
+                    WheelDirection::Left => (-x).max(0.0),
+                }
+            }
+            Binding::GamepadButton(button) => match self.gamepads {
+                Some(gamepads) => bool_to_value(
+                    gamepads
+                        .get_connected()
+                        .into_iter()
+                        .any(|id| gamepads.get_button_state(id, *button).is_down()),
+                ),
+                None => 0.0,
+            },
+            Binding::GamepadAxis(axis, direction) => match self.gamepads {
+                Some(gamepads) => gamepads
+                    .get_connected()
+                    .into_iter()
+                    .map(|id| {
+                        let value = gamepads.get_axis(id, *axis);
+                        match direction {
+                            AxisDirection::Positive => value.max(0.0),
+                            AxisDirection::Negative => (-value).max(0.0),
+                        }
+                    })
+                    .fold(0.0, f32::max),
+                None => 0.0,
+            },
+        }
+    }
+
+    /// the value of the strongest binding in the list
+    fn get_bindings_value(&self, bindings: &[Binding]) -> f32 {
+        bindings
+            .iter()
+            .map(|binding| self.get_binding_value(binding))
+            .fold(0.0, f32::max)
+    }
+}
+
+fn bool_to_value(down: bool) -> f32 {
+    if down {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+#[derive(Clone, Default)]
+struct AxisBindings {
+    negative: Vec<Binding>,
+    positive: Vec<Binding>,
+}
+
+/// The values of every action and axis for one frame
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ActionValues {
+    actions: HashMap<String, f32>,
+    axes: HashMap<String, f32>,
+}
+
+/// The resource holding the bindings of every action and axis
+/// updated by the event loop right before the update function is called
+#[derive(Clone, Default)]
+pub struct ActionMap {
+    actions: BTreeMap<String, Vec<Binding>>,
+    axes: BTreeMap<String, AxisBindings>,
+    values: ActionValues,
+    previous_values: ActionValues,
+}
+
+impl ActionMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// add a binding to an action, the action is created if it doesn't exist
+    pub fn bind_action(&mut self, name: &str, binding: Binding) {
+        let bindings = self.actions.entry(name.to_string()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// replace every binding of an action
+    pub fn set_action_bindings(&mut self, name: &str, bindings: Vec<Binding>) {
+        self.actions.insert(name.to_string(), bindings);
+    }
+
+    /// remove a binding from an action, returns false if the action didn't have the binding
+    pub fn unbind_action(&mut self, name: &str, binding: Binding) -> bool {
+        match self.actions.get_mut(name) {
+            Some(bindings) => {
+                let length_before = bindings.len();
+                bindings.retain(|other| *other != binding);
+                bindings.len() != length_before
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove_action(&mut self, name: &str) {
+        self.actions.remove(name);
+    }
+
+    /// get the bindings of an action, empty if the action doesn't exist
+    pub fn get_action_bindings(&self, name: &str) -> &[Binding] {
+        self.actions.get(name).map(|b| b.as_slice()).unwrap_or(&[])
+    }
+
+    /// get the names of every action
+    pub fn get_action_names(&self) -> impl Iterator<Item = &str> {
+        self.actions.keys().map(|name| name.as_str())
+    }
+
+    /// add a pair of bindings to an axis, the axis is created if it doesn't exist
+    pub fn bind_axis(&mut self, name: &str, negative: Binding, positive: Binding) {
+        let axis = self.axes.entry(name.to_string()).or_default();
+        if !axis.negative.contains(&negative) {
+            axis.negative.push(negative);
+        }
+        if !axis.positive.contains(&positive) {
+            axis.positive.push(positive);
+        }
+    }
+
+    /// replace every binding of an axis
+    pub fn set_axis_bindings(
+        &mut self,
+        name: &str,
+        negative: Vec<Binding>,
+        positive: Vec<Binding>,
+    ) {
+        self.axes
+            .insert(name.to_string(), AxisBindings { negative, positive });
+    }
+
+    pub fn remove_axis(&mut self, name: &str) {
+        self.axes.remove(name);
+    }
+
+    /// get the (negative, positive) bindings of an axis
+    pub fn get_axis_bindings(&self, name: &str) -> Option<(&[Binding], &[Binding])> {
+        self.axes
+            .get(name)
+            .map(|axis| (axis.negative.as_slice(), axis.positive.as_slice()))
+    }
+
+    /// get the names of every axis
+    pub fn get_axis_names(&self) -> impl Iterator<Item = &str> {
+        self.axes.keys().map(|name| name.as_str())
+    }
+
+    /// get the state of an action this frame, unknown actions are never pressed
+    pub fn get_action_state(&self, name: &str) -> ButtonState {
+        let was_down = get_value(&self.previous_values.actions, name) >= ACTION_PRESS_THRESHOLD;
+        let is_down = get_value(&self.values.actions, name) >= ACTION_PRESS_THRESHOLD;
+
+        ButtonState::from_frames(was_down, is_down)
+    }
+
+    /// get the value of the strongest binding of an action this frame
+    pub fn get_action_value(&self, name: &str) -> f32 {
+        get_value(&self.values.actions, name)
+    }
+
+    /// get the value of an axis this frame, the negative value is subtracted from the positive one
+    pub fn get_axis(&self, name: &str) -> f32 {
+        get_value(&self.values.axes, name)
+    }
+
+    /// work out the value of every action and axis from the raw input
+    pub fn evaluate(&self, input: &RawInput) -> ActionValues {
+        let mut values = ActionValues::default();
+
+        for (name, bindings) in &self.actions {
+            values
+                .actions
+                .insert(name.clone(), input.get_bindings_value(bindings));
+        }
+
+        for (name, axis) in &self.axes {
+            let value =
+                input.get_bindings_value(&axis.positive) - input.get_bindings_value(&axis.negative);
+            values.axes.insert(name.clone(), value);
+        }
+
+        values
+    }
+
+    /// move to the next frame using values from `evaluate`
+    pub fn set_values(&mut self, values: ActionValues) {
+        self.previous_values = std::mem::replace(&mut self.values, values);
+    }
+
+    /// move to the next frame, this is called by the event loop
+    /// you only need to call it if you drive the world yourself
+    pub fn update_from_input(&mut self, input: &RawInput) {
+        let values = self.evaluate(input);
+        self.set_values(values);
+    }
+
+    /// write the bindings in the config format, one action or axis per line
+    /// keys that don't have a config name are left out, so the config can always be read back
+    /// actions look like `action jump = key:Space, gamepad:South`
+    /// axes put the negative bindings before the positive ones `axis move_x = key:A ; key:D`
+    pub fn to_config_string(&self) -> String {
+        let mut config = String::new();
+
+        for (name, bindings) in &self.actions {
+            config.push_str(&format!(
+                "action {} = {}\n",
+                name,
+                bindings_to_string(bindings)
+            ));
+        }
+
+        for (name, axis) in &self.axes {
+            config.push_str(&format!(
+                "axis {} = {} ; {}\n",
+                name,
+                bindings_to_string(&axis.negative),
+                bindings_to_string(&axis.positive)
+            ));
+        }
+
+        config
+    }
+
+    /// read bindings written by `to_config_string`, empty lines and lines starting with # are skipped
+    pub fn from_config_str(config: &str) -> Result<Self, ActionMapError> {
+        let mut action_map = Self::new();
+
+        for (index, line) in config.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let parse_error = |message: String| ActionMapError::Parse {
+                line: line_number,
+                message,
+            };
+
+            let (kind, rest) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| parse_error(format!("expected a name after `{}`", line)))?;
+            let (name, bindings) = rest
+                .split_once('=')
+                .ok_or_else(|| parse_error("expected `=` after the name".to_string()))?;
+
+            let name = name.trim();
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                return Err(parse_error(format!("`{}` is not a valid name", name)));
+            }
+
+            match kind {
+                "action" => {
+                    let bindings = parse_bindings(bindings).map_err(parse_error)?;
+                    action_map.set_action_bindings(name, bindings);
+                }
+                "axis" => {
+                    let (negative, positive) = bindings.split_once(';').ok_or_else(|| {
+                        parse_error(
+                            "expected `;` between the negative and positive bindings".to_string(),
+                        )
+                    })?;
+                    let negative = parse_bindings(negative).map_err(parse_error)?;
+                    let positive = parse_bindings(positive).map_err(parse_error)?;
+                    action_map.set_axis_bindings(name, negative, positive);
+                }
+                other => {
+                    return Err(parse_error(format!(
+                        "expected `action` or `axis`, found `{}`",
+                        other
+                    )))
+                }
+            }
+        }
+
+        Ok(action_map)
+    }
+
+    /// save the bindings to a config file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ActionMapError> {
+        std::fs::write(path, self.to_config_string())?;
+        Ok(())
+    }
+
+    /// load bindings from a config file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ActionMapError> {
+        let config = std::fs::read_to_string(path)?;
+        Self::from_config_str(&config)
+    }
+}
+
+impl Resource for ActionMap {
+    fn update(&mut self) {}
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+fn get_value(values: &HashMap<String, f32>, name: &str) -> f32 {
+    values.get(name).cloned().unwrap_or(0.0)
+}
+
+#[derive(Debug)]
+pub enum ActionMapError {
+    Io(std::io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for ActionMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActionMapError::Io(error) => write!(f, "failed to access the bindings file: {}", error),
+            ActionMapError::Parse { line, message } => {
+                write!(f, "failed to parse bindings on line {}: {}", line, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ActionMapError {}
+
+impl From<std::io::Error> for ActionMapError {
+    fn from(error: std::io::Error) -> Self {
+        ActionMapError::Io(error)
+    }
+}
+
+fn bindings_to_string(bindings: &[Binding]) -> String {
+    bindings
+        .iter()
+        .filter(|binding| match binding {
+            Binding::Key(key) => key_to_name(*key).is_some(),
+            _ => true,
+        })
+        .map(|binding| binding.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn parse_bindings(bindings: &str) -> Result<Vec<Binding>, String> {
+    bindings
+        .split(',')
+        .map(str::trim)
+        .filter(|binding| !binding.is_empty())
+        .map(|binding| binding.parse::<Binding>())
+        .collect()
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Binding::Key(key) => match key_to_name(*key) {
+                Some(name) => write!(f, "key:{}", name),
+                // config files skip these, see `to_config_string`
+                None => write!(f, "key:Unknown"),
+            },
+            Binding::MouseButton(button) => write!(f, "mouse:{}", button),
+            Binding::MouseWheel(direction) => {
+                let direction = match direction {
+                    WheelDirection::Up => "up",
+                    WheelDirection::Down => "down",
+                    WheelDirection::Left => "left",
+                    WheelDirection::Right => "right",
+                };
+                write!(f, "wheel:{}", direction)
+            }
+            Binding::GamepadButton(button) => write!(
+                f,
+                "gamepad:{}",
+                find_name(GAMEPAD_BUTTON_NAMES, button).expect("every button has a name")
+            ),
+            Binding::GamepadAxis(axis, direction) => write!(
+                f,
+                "gamepad_axis:{}{}",
+                find_name(GAMEPAD_AXIS_NAMES, axis).expect("every axis has a name"),
+                match direction {
+                    AxisDirection::Positive => "+",
+                    AxisDirection::Negative => "-",
+                }
+            ),
+        }
+    }
+}
+
+impl std::str::FromStr for Binding {
+    type Err = String;
+
+    fn from_str(binding: &str) -> Result<Self, Self::Err> {
+        let (device, input) = binding
+            .split_once(':')
+            .ok_or_else(|| format!("`{}` is missing the `device:` prefix", binding))?;
+        let input = input.trim();
+
+        match device.trim() {
+            "key" => key_from_name(input)
+                .map(Binding::Key)
+                .ok_or_else(|| format!("unknown key `{}`", input)),
+            "mouse" => input
+                .parse::<u32>()
+                .map(Binding::MouseButton)
+                .map_err(|_| format!("`{}` is not a mouse button id", input)),
+            "wheel" => match input {
+                "up" => Ok(Binding::MouseWheel(WheelDirection::Up)),
+                "down" => Ok(Binding::MouseWheel(WheelDirection::Down)),
+                "left" => Ok(Binding::MouseWheel(WheelDirection::Left)),
+                "right" => Ok(Binding::MouseWheel(WheelDirection::Right)),
+                other => Err(format!("unknown wheel direction `{}`", other)),
+            },
+            "gamepad" => find_value(GAMEPAD_BUTTON_NAMES, input)
+                .map(Binding::GamepadButton)
+                .ok_or_else(|| format!("unknown gamepad button `{}`", input)),
+            "gamepad_axis" => {
+                let (axis, direction) = if let Some(axis) = input.strip_suffix('+') {
+                    (axis, AxisDirection::Positive)
+                } else if let Some(axis) = input.strip_suffix('-') {
+                    (axis, AxisDirection::Negative)
+                } else {
+                    return Err(format!("gamepad axis `{}` must end in + or -", input));
+                };
+
+                find_value(GAMEPAD_AXIS_NAMES, axis)
+                    .map(|axis| Binding::GamepadAxis(axis, direction))
+                    .ok_or_else(|| format!("unknown gamepad axis `{}`", axis))
+            }
+            other => Err(format!("unknown device `{}`", other)),
+        }
+    }
+}
+
+/// the name of a key used in config files, None if the event loop can't produce the key
+pub(crate) fn key_to_name(key: KeyCode) -> Option<&'static str> {
+    find_name(KEY_NAMES, &key)
+}
+
+pub(crate) fn key_from_name(name: &str) -> Option<KeyCode> {
+    find_value(KEY_NAMES, name)
+}
+
+fn find_name<T: PartialEq>(table: &[(T, &'static str)], value: &T) -> Option<&'static str> {
+    table
+        .iter()
+        .find(|(other, _)| other == value)
+        .map(|(_, name)| *name)
+}
+
+fn find_value<T: Copy>(table: &[(T, &'static str)], name: &str) -> Option<T> {
+    table
+        .iter()
+        .find(|(_, other)| *other == name)
+        .map(|(value, _)| *value)
+}
+
+const GAMEPAD_BUTTON_NAMES: &[(GamepadButton, &str)] = &[
+    (GamepadButton::South, "South"),
+    (GamepadButton::East, "East"),
+    (GamepadButton::North, "North"),
+    (GamepadButton::West, "West"),
+    (GamepadButton::LeftBumper, "LeftBumper"),
+    (GamepadButton::RightBumper, "RightBumper"),
+    (GamepadButton::LeftTrigger, "LeftTrigger"),
+    (GamepadButton::RightTrigger, "RightTrigger"),
+    (GamepadButton::Select, "Select"),
+    (GamepadButton::Start, "Start"),
+    (GamepadButton::Mode, "Mode"),
+    (GamepadButton::LeftStick, "LeftStick"),
+    (GamepadButton::RightStick, "RightStick"),
+    (GamepadButton::DPadUp, "DPadUp"),
+    (GamepadButton::DPadDown, "DPadDown"),
+    (GamepadButton::DPadLeft, "DPadLeft"),
+    (GamepadButton::DPadRight, "DPadRight"),
+];
+
+const GAMEPAD_AXIS_NAMES: &[(GamepadAxis, &str)] = &[
+    (GamepadAxis::LeftStickX, "LeftStickX"),
+    (GamepadAxis::LeftStickY, "LeftStickY"),
+    (GamepadAxis::RightStickX, "RightStickX"),
+    (GamepadAxis::RightStickY, "RightStickY"),
+    (GamepadAxis::LeftTrigger, "LeftTrigger"),
+    (GamepadAxis::RightTrigger, "RightTrigger"),
+];
+
+/// the names used for keys in the config file, these are the keys the event loop can produce
+const KEY_NAMES: &[(KeyCode, &str)] = &[
+    (KeyCode::Key0, "Key0"),
+    (KeyCode::Key1, "Key1"),
+    (KeyCode::Key2, "Key2"),
+    (KeyCode::Key3, "Key3"),
+    (KeyCode::Key4, "Key4"),
+    (KeyCode::Key5, "Key5"),
+    (KeyCode::Key6, "Key6"),
+    (KeyCode::Key7, "Key7"),
+    (KeyCode::Key8, "Key8"),
+    (KeyCode::Key9, "Key9"),
+    (KeyCode::A, "A"),
+    (KeyCode::B, "B"),
+    (KeyCode::C, "C"),
+    (KeyCode::D, "D"),
+    (KeyCode::E, "E"),
+    (KeyCode::F, "F"),
+    (KeyCode::G, "G"),
+    (KeyCode::H, "H"),
+    (KeyCode::I, "I"),
+    (KeyCode::J, "J"),
+    (KeyCode::K, "K"),
+    (KeyCode::L, "L"),
+    (KeyCode::M, "M"),
+    (KeyCode::N, "N"),
+    (KeyCode::O, "O"),
+    (KeyCode::P, "P"),
+    (KeyCode::Q, "Q"),
+    (KeyCode::R, "R"),
+    (KeyCode::S, "S"),
+    (KeyCode::T, "T"),
+    (KeyCode::U, "U"),
+    (KeyCode::V, "V"),
+    (KeyCode::W, "W"),
+    (KeyCode::X, "X"),
+    (KeyCode::Y, "Y"),
+    (KeyCode::Z, "Z"),
+    (KeyCode::Escape, "Escape"),
+    (KeyCode::F1, "F1"),
+    (KeyCode::F2, "F2"),
+    (KeyCode::F3, "F3"),
+    (KeyCode::F4, "F4"),
+    (KeyCode::F5, "F5"),
+    (KeyCode::F6, "F6"),
+    (KeyCode::F7, "F7"),
+    (KeyCode::F8, "F8"),
+    (KeyCode::F9, "F9"),
+    (KeyCode::F10, "F10"),
+    (KeyCode::F11, "F11"),
+    (KeyCode::F12, "F12"),
+    (KeyCode::F13, "F13"),
+    (KeyCode::F14, "F14"),
+    (KeyCode::F15, "F15"),
+    (KeyCode::F16, "F16"),
+    (KeyCode::F17, "F17"),
+    (KeyCode::F18, "F18"),
+    (KeyCode::F19, "F19"),
+    (KeyCode::F20, "F20"),
+    (KeyCode::F21, "F21"),
+    (KeyCode::F22, "F22"),
+    (KeyCode::F23, "F23"),
+    (KeyCode::F24, "F24"),
+    (KeyCode::Snapshot, "Snapshot"),
+    (KeyCode::Scroll, "Scroll"),
+    (KeyCode::Pause, "Pause"),
+    (KeyCode::Insert, "Insert"),
+    (KeyCode::Home, "Home"),
+    (KeyCode::Delete, "Delete"),
+    (KeyCode::End, "End"),
+    (KeyCode::PageDown, "PageDown"),
+    (KeyCode::PageUp, "PageUp"),
+    (KeyCode::Left, "Left"),
+    (KeyCode::Up, "Up"),
+    (KeyCode::Right, "Right"),
+    (KeyCode::Down, "Down"),
+    (KeyCode::Backspace, "Backspace"),
+    (KeyCode::Return, "Return"),
+    (KeyCode::Space, "Space"),
+    (KeyCode::Comma, "Comma"),
+    (KeyCode::Minus, "Minus"),
+    (KeyCode::Period, "Period"),
+    (KeyCode::Slash, "Slash"),
+    (KeyCode::Semicolon, "Semicolon"),
+    (KeyCode::Equals, "Equals"),
+    (KeyCode::Apostrophe, "Apostrophe"),
+    (KeyCode::Backslash, "Backslash"),
+    (KeyCode::LBracket, "LBracket"),
+    (KeyCode::RBracket, "RBracket"),
+    (KeyCode::Grave, "Grave"),
+    (KeyCode::LControl, "LControl"),
+    (KeyCode::LShift, "LShift"),
+    (KeyCode::LAlt, "LAlt"),
+    (KeyCode::RControl, "RControl"),
+    (KeyCode::RShift, "RShift"),
+    (KeyCode::RAlt, "RAlt"),
+    (KeyCode::Numlock, "Numlock"),
+    (KeyCode::Numpad0, "Numpad0"),
+    (KeyCode::Numpad1, "Numpad1"),
+    (KeyCode::Numpad2, "Numpad2"),
+    (KeyCode::Numpad3, "Numpad3"),
+    (KeyCode::Numpad4, "Numpad4"),
+    (KeyCode::Numpad5, "Numpad5"),
+    (KeyCode::Numpad6, "Numpad6"),
+    (KeyCode::Numpad7, "Numpad7"),
+    (KeyCode::Numpad8, "Numpad8"),
+    (KeyCode::Numpad9, "Numpad9"),
+    (KeyCode::NumpadAdd, "NumpadAdd"),
+    (KeyCode::NumpadDecimal, "NumpadDecimal"),
+    (KeyCode::NumpadDivide, "NumpadDivide"),
+    (KeyCode::NumpadEnter, "NumpadEnter"),
+    (KeyCode::NumpadEquals, "NumpadEquals"),
+    (KeyCode::NumpadMultiply, "NumpadMultiply"),
+    (KeyCode::NumpadSubtract, "NumpadSubtract"),
+    (KeyCode::Capital, "Capital"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_states_and_axes() {
+        let mut action_map = ActionMap::new();
+        action_map.bind_action("jump", Binding::Key(KeyCode::Space));
+        action_map.bind_action("jump", Binding::MouseButton(0));
+        action_map.bind_axis("move_x", Binding::Key(KeyCode::A), Binding::Key(KeyCode::D));
+
+        let mut keys_down = HashSet::new();
+        let mouse_buttons_down = HashSet::new();
+        keys_down.insert(KeyCode::Space);
+        keys_down.insert(KeyCode::A);
+
+        action_map.update_from_input(&RawInput {
+            keys_down: &keys_down,
+            mouse_buttons_down: &mouse_buttons_down,
+            mouse_wheel_lines: [0.0, 0.0],
+            gamepads: None,
+        });
+
+        assert_eq!(action_map.get_action_state("jump"), ButtonState::Pressed);
+        assert_eq!(action_map.get_action_value("jump"), 1.0);
+        assert_eq!(action_map.get_axis("move_x"), -1.0);
+        assert_eq!(
+            action_map.get_action_state("missing"),
+            ButtonState::NotPressed
+        );
+
+        keys_down.clear();
+        action_map.update_from_input(&RawInput {
+            keys_down: &keys_down,
+            mouse_buttons_down: &mouse_buttons_down,
+            mouse_wheel_lines: [0.0, 0.0],
+            gamepads: None,
+        });
+
+        assert_eq!(action_map.get_action_state("jump"), ButtonState::Released);
+        assert_eq!(action_map.get_axis("move_x"), 0.0);
+    }
+
+    #[test]
+    fn test_action_config_round_trip() {
+        let mut action_map = ActionMap::new();
+        action_map.bind_action("jump", Binding::Key(KeyCode::Space));
+        action_map.bind_action("jump", Binding::GamepadButton(GamepadButton::South));
+        action_map.bind_action("zoom_in", Binding::MouseWheel(WheelDirection::Up));
+        action_map.set_axis_bindings(
+            "move_x",
+            vec![
+                Binding::Key(KeyCode::A),
+                Binding::GamepadAxis(GamepadAxis::LeftStickX, AxisDirection::Negative),
+            ],
+            vec![Binding::Key(KeyCode::D), Binding::MouseButton(4)],
+        );
+
+        let config = action_map.to_config_string();
+        let loaded = ActionMap::from_config_str(&config).expect("failed to parse config");
+
+        assert_eq!(loaded.to_config_string(), config);
+        assert_eq!(
+            loaded.get_action_bindings("jump"),
+            action_map.get_action_bindings("jump")
+        );
+
+        // keys without a name are left out instead of making the config unreadable
+        action_map.bind_action("jump", Binding::Key(KeyCode::NumpadComma));
+        let config = action_map.to_config_string();
+        let loaded = ActionMap::from_config_str(&config).expect("failed to parse config");
+        assert_eq!(loaded.get_action_bindings("jump").len(), 2);
+    }
+
+    #[test]
+    fn test_action_config_errors() {
+        let error = ActionMap::from_config_str("# comment\n\naction jump = key:Nope")
+            .err()
+            .expect("unknown key should fail");
+
+        match error {
+            ActionMapError::Parse { line, .. } => assert_eq!(line, 3),
+            ActionMapError::Io(_) => panic!("expected a parse error"),
+        }
+
+        assert!(ActionMap::from_config_str("axis move_x = key:A").is_err());
+    }
+}
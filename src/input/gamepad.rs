@@ -0,0 +1,532 @@
+//! Gamepad support, the gamepads are polled once per frame from a backend
+//! by default gilrs is used, but any backend can be plugged in (like the virtual one for tests)
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use ABC_Game_Engine::Resource;
+
+use super::ButtonState;
+
+/// The id of a connected gamepad, ids are given out by the backend
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GamepadId(pub usize);
+
+/// The buttons on a gamepad, named after their position on the gamepad rather than their label
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    Mode,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+/// The analog inputs on a gamepad
+/// sticks go from -1.0 to 1.0 with up and right being positive, triggers go from 0.0 to 1.0
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GamepadEvent {
+    Connected(GamepadId),
+    Disconnected(GamepadId),
+    ButtonPressed(GamepadId, GamepadButton),
+    ButtonReleased(GamepadId, GamepadButton),
+    AxisChanged(GamepadId, GamepadAxis, f32),
+}
+
+/// A source of gamepad events
+pub trait GamepadBackend {
+    /// returns every event that happened since the last poll, in the order they happened
+    fn poll_events(&mut self) -> Vec<GamepadEvent>;
+}
+
+/// The deadzones applied to the analog inputs
+/// values inside the deadzone read as 0.0 and the rest of the range is rescaled to still reach 1.0
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GamepadDeadzones {
+    /// radial deadzone applied to each stick as a whole
+    pub stick: f32,
+    pub trigger: f32,
+}
+
+impl Default for GamepadDeadzones {
+    fn default() -> Self {
+        Self {
+            stick: 0.15,
+            trigger: 0.05,
+        }
+    }
+}
+
+#[derive(Default)]
+struct GamepadState {
+    buttons_down: HashSet<GamepadButton>,
+    previous_buttons_down: HashSet<GamepadButton>,
+    /// raw values, the deadzones are applied when they are read
+    axes: HashMap<GamepadAxis, f32>,
+}
+
+/// The resource holding the state of every connected gamepad
+/// updated by the event loop right before the update function is called
+pub struct Gamepads {
+    backend: Option<Box<dyn GamepadBackend>>,
+    gamepads: BTreeMap<GamepadId, GamepadState>,
+    events: Vec<GamepadEvent>,
+    /// events from between frames, like disconnects from replacing the backend, given out on the next update
+    pending_events: Vec<GamepadEvent>,
+    deadzones: GamepadDeadzones,
+}
+
+impl Gamepads {
+    /// create the resource without a backend, no gamepads will connect until one is set
+    pub fn new() -> Self {
+        Self {
+            backend: None,
+            gamepads: BTreeMap::new(),
+            events: vec![],
+            pending_events: vec![],
+            deadzones: GamepadDeadzones::default(),
+        }
+    }
+
+    pub fn with_backend(backend: impl GamepadBackend + 'static) -> Self {
+        let mut gamepads = Self::new();
+        gamepads.set_backend(backend);
+        gamepads
+    }
+
+    /// replace the backend, every gamepad from the old backend is disconnected
+    pub fn set_backend(&mut self, backend: impl GamepadBackend + 'static) {
+        for id in self.gamepads.keys() {
+            self.pending_events.push(GamepadEvent::Disconnected(*id));
+        }
+        self.gamepads.clear();
+        self.backend = Some(Box::new(backend));
+    }
+
+    pub fn set_deadzones(&mut self, deadzones: GamepadDeadzones) {
+        self.deadzones = deadzones;
+    }
+
+    pub fn get_deadzones(&self) -> GamepadDeadzones {
+        self.deadzones
+    }
+
+    /// poll the backend and move to the next frame
+    /// this is called by the event loop, you only need to call it if you drive the world yourself
+    pub fn update_gamepads(&mut self) {
+        for gamepad in self.gamepads.values_mut() {
+            gamepad.previous_buttons_down = gamepad.buttons_down.clone();
+        }
+
+        // anything left in events was from a previous frame
+        self.events.clear();
+        self.events.append(&mut self.pending_events);
+
+        let events = match self.backend {
+            Some(ref mut backend) => backend.poll_events(),
+            None => vec![],
+        };
+
+        for event in events {
+            self.apply_event(event);
+        }
+    }
+
+    fn apply_event(&mut self, event: GamepadEvent) {
+        match event {
+            GamepadEvent::Connected(id) => {
+                self.gamepads.entry(id).or_default();
+            }
+            GamepadEvent::Disconnected(id) => {
+                self.gamepads.remove(&id);
+            }
+            GamepadEvent::ButtonPressed(id, button) => {
+                self.gamepads
+                    .entry(id)
+                    .or_default()
+                    .buttons_down
+                    .insert(button);
+            }
+            GamepadEvent::ButtonReleased(id, button) => {
+                self.gamepads
+                    .entry(id)
+                    .or_default()
+                    .buttons_down
+                    .remove(&button);
+            }
+            GamepadEvent::AxisChanged(id, axis, value) => {
+                self.gamepads
+                    .entry(id)
+                    .or_default()
+                    .axes
+                    .insert(axis, value);
+            }
+        }
+
+        self.events.push(event);
+    }
+
+    /// every event that happened this frame, including connects and disconnects
+    pub fn get_events(&self) -> &[GamepadEvent] {
+        &self.events
+    }
+
+    /// the ids of every connected gamepad, in ascending order
+    pub fn get_connected(&self) -> Vec<GamepadId> {
+        self.gamepads.keys().cloned().collect()
+    }
+
+    pub fn is_connected(&self, id: GamepadId) -> bool {
+        self.gamepads.contains_key(&id)
+    }
+
+    /// get the state of a button, a disconnected gamepad has every button not pressed
+    pub fn get_button_state(&self, id: GamepadId, button: GamepadButton) -> ButtonState {
+        match self.gamepads.get(&id) {
+            Some(gamepad) => ButtonState::from_frames(
+                gamepad.previous_buttons_down.contains(&button),
+                gamepad.buttons_down.contains(&button),
+            ),
+            None => ButtonState::NotPressed,
+        }
+    }
+
+    /// get the value of an axis with the deadzones applied
+    pub fn get_axis(&self, id: GamepadId, axis: GamepadAxis) -> f32 {
+        match axis {
+            GamepadAxis::LeftStickX => self.get_left_stick(id)[0],
+            GamepadAxis::LeftStickY => self.get_left_stick(id)[1],
+            GamepadAxis::RightStickX => self.get_right_stick(id)[0],
+            GamepadAxis::RightStickY => self.get_right_stick(id)[1],
+            GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger => {
+                apply_deadzone(self.get_raw_axis(id, axis).abs(), self.deadzones.trigger)
+            }
+        }
+    }
+
+    /// get the value of an axis without any deadzone
+    pub fn get_raw_axis(&self, id: GamepadId, axis: GamepadAxis) -> f32 {
+        self.gamepads
+            .get(&id)
+            .and_then(|gamepad| gamepad.axes.get(&axis).cloned())
+            .unwrap_or(0.0)
+    }
+
+    /// get the [x, y] of the left stick with the deadzone applied
+    pub fn get_left_stick(&self, id: GamepadId) -> [f32; 2] {
+        self.get_stick(id, GamepadAxis::LeftStickX, GamepadAxis::LeftStickY)
+    }
+
+    /// get the [x, y] of the right stick with the deadzone applied
+    pub fn get_right_stick(&self, id: GamepadId) -> [f32; 2] {
+        self.get_stick(id, GamepadAxis::RightStickX, GamepadAxis::RightStickY)
+    }
+
+    fn get_stick(&self, id: GamepadId, x_axis: GamepadAxis, y_axis: GamepadAxis) -> [f32; 2] {
+        let x = self.get_raw_axis(id, x_axis);
+        let y = self.get_raw_axis(id, y_axis);
+
+        let magnitude = (x * x + y * y).sqrt();
+        if magnitude == 0.0 {
+            return [0.0, 0.0];
+        }
+
+        let scaled_magnitude = apply_deadzone(magnitude.min(1.0), self.deadzones.stick);
+        [
+            x / magnitude * scaled_magnitude,
+            y / magnitude * scaled_magnitude,
+        ]
+    }
+}
+
+impl Default for Gamepads {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Resource for Gamepads {
+    fn update(&mut self) {}
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+/// maps a value from [deadzone, 1.0] to [0.0, 1.0], anything below the deadzone is 0.0
+fn apply_deadzone(value: f32, deadzone: f32) -> f32 {
+    if value <= deadzone {
+        0.0
+    } else {
+        ((value - deadzone) / (1.0 - deadzone)).min(1.0)
+    }
+}
+
+/// A backend that only produces the events it is given
+/// clones share the same queue, so keep a clone around to drive the gamepad after giving it to `Gamepads`
+#[derive(Clone, Default)]
+pub struct VirtualGamepadBackend {
+    queue: Arc<Mutex<Vec<GamepadEvent>>>,
+}
+
+impl VirtualGamepadBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// queue an event to be returned on the next poll
+    pub fn send(&self, event: GamepadEvent) {
+        self.queue
+            .lock()
+            .expect("virtual gamepad queue was poisoned")
+            .push(event);
+    }
+
+    pub fn connect(&self, id: GamepadId) {
+        self.send(GamepadEvent::Connected(id));
+    }
+
+    pub fn disconnect(&self, id: GamepadId) {
+        self.send(GamepadEvent::Disconnected(id));
+    }
+
+    pub fn press(&self, id: GamepadId, button: GamepadButton) {
+        self.send(GamepadEvent::ButtonPressed(id, button));
+    }
+
+    pub fn release(&self, id: GamepadId, button: GamepadButton) {
+        self.send(GamepadEvent::ButtonReleased(id, button));
+    }
+
+    pub fn set_axis(&self, id: GamepadId, axis: GamepadAxis, value: f32) {
+        self.send(GamepadEvent::AxisChanged(id, axis, value));
+    }
+}
+
+impl GamepadBackend for VirtualGamepadBackend {
+    fn poll_events(&mut self) -> Vec<GamepadEvent> {
+        std::mem::take(
+            &mut *self
+                .queue
+                .lock()
+                .expect("virtual gamepad queue was poisoned"),
+        )
+    }
+}
+
+/// The default backend, reads real gamepads through gilrs
+pub struct GilrsBackend {
+    gilrs: gilrs::Gilrs,
+}
+
+impl GilrsBackend {
+    /// returns None if gilrs failed to start, for example if the platform isn't supported
+    pub fn new() -> Option<Self> {
+        // the default filters include a deadzone, we apply our own so it can be configured
+        let gilrs = gilrs::GilrsBuilder::new()
+            .with_default_filters(false)
+            .build()
+            .ok()?;
+
+        Some(Self { gilrs })
+    }
+}
+
+impl GamepadBackend for GilrsBackend {
+    fn poll_events(&mut self) -> Vec<GamepadEvent> {
+        let mut events = vec![];
+
+        while let Some(gilrs::Event { id, event, .. }) = self.gilrs.next_event() {
+            let id = GamepadId(usize::from(id));
+
+            match event {
+                gilrs::EventType::Connected => events.push(GamepadEvent::Connected(id)),
+                gilrs::EventType::Disconnected => events.push(GamepadEvent::Disconnected(id)),
+                gilrs::EventType::ButtonPressed(button, _) => {
+                    if let Some(button) = gilrs_button_to_gamepad_button(button) {
+                        events.push(GamepadEvent::ButtonPressed(id, button));
+                    }
+                }
+                gilrs::EventType::ButtonReleased(button, _) => {
+                    if let Some(button) = gilrs_button_to_gamepad_button(button) {
+                        events.push(GamepadEvent::ButtonReleased(id, button));
+                    }
+                }
+                // analog triggers are reported as buttons with a value by gilrs
+                gilrs::EventType::ButtonChanged(gilrs::Button::LeftTrigger2, value, _) => {
+                    events.push(GamepadEvent::AxisChanged(
+                        id,
+                        GamepadAxis::LeftTrigger,
+                        value,
+                    ));
+                }
+                gilrs::EventType::ButtonChanged(gilrs::Button::RightTrigger2, value, _) => {
+                    events.push(GamepadEvent::AxisChanged(
+                        id,
+                        GamepadAxis::RightTrigger,
+                        value,
+                    ));
+                }
+                gilrs::EventType::AxisChanged(axis, value, _) => {
+                    let axis = match axis {
+                        gilrs::Axis::LeftStickX => Some(GamepadAxis::LeftStickX),
+                        gilrs::Axis::LeftStickY => Some(GamepadAxis::LeftStickY),
+                        gilrs::Axis::RightStickX => Some(GamepadAxis::RightStickX),
+                        gilrs::Axis::RightStickY => Some(GamepadAxis::RightStickY),
+                        gilrs::Axis::LeftZ => Some(GamepadAxis::LeftTrigger),
+                        gilrs::Axis::RightZ => Some(GamepadAxis::RightTrigger),
+                        _ => None,
+                    };
+
+                    if let Some(axis) = axis {
+                        events.push(GamepadEvent::AxisChanged(id, axis, value));
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        events
+    }
+}
+
+fn gilrs_button_to_gamepad_button(button: gilrs::Button) -> Option<GamepadButton> {
+    match button {
+        gilrs::Button::South => Some(GamepadButton::South),
+        gilrs::Button::East => Some(GamepadButton::East),
+        gilrs::Button::North => Some(GamepadButton::North),
+        gilrs::Button::West => Some(GamepadButton::West),
+        gilrs::Button::LeftTrigger => Some(GamepadButton::LeftBumper),
+        gilrs::Button::RightTrigger => Some(GamepadButton::RightBumper),
+        gilrs::Button::LeftTrigger2 => Some(GamepadButton::LeftTrigger),
+        gilrs::Button::RightTrigger2 => Some(GamepadButton::RightTrigger),
+        gilrs::Button::Select => Some(GamepadButton::Select),
+        gilrs::Button::Start => Some(GamepadButton::Start),
+        gilrs::Button::Mode => Some(GamepadButton::Mode),
+        gilrs::Button::LeftThumb => Some(GamepadButton::LeftStick),
+        gilrs::Button::RightThumb => Some(GamepadButton::RightStick),
+        gilrs::Button::DPadUp => Some(GamepadButton::DPadUp),
+        gilrs::Button::DPadDown => Some(GamepadButton::DPadDown),
+        gilrs::Button::DPadLeft => Some(GamepadButton::DPadLeft),
+        gilrs::Button::DPadRight => Some(GamepadButton::DPadRight),
+        _ => None, // C, Z and unknown buttons aren't on standard gamepads
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_virtual_gamepad_button_states() {
+        let backend = VirtualGamepadBackend::new();
+        let mut gamepads = Gamepads::with_backend(backend.clone());
+        let id = GamepadId(0);
+
+        backend.connect(id);
+        backend.press(id, GamepadButton::South);
+        gamepads.update_gamepads();
+
+        assert!(gamepads.is_connected(id));
+        assert_eq!(
+            gamepads.get_events(),
+            &[
+                GamepadEvent::Connected(id),
+                GamepadEvent::ButtonPressed(id, GamepadButton::South)
+            ]
+        );
+        assert_eq!(
+            gamepads.get_button_state(id, GamepadButton::South),
+            ButtonState::Pressed
+        );
+
+        gamepads.update_gamepads();
+        assert!(gamepads.get_events().is_empty());
+        assert_eq!(
+            gamepads.get_button_state(id, GamepadButton::South),
+            ButtonState::Held
+        );
+
+        backend.release(id, GamepadButton::South);
+        gamepads.update_gamepads();
+        assert_eq!(
+            gamepads.get_button_state(id, GamepadButton::South),
+            ButtonState::Released
+        );
+
+        backend.disconnect(id);
+        gamepads.update_gamepads();
+        assert!(!gamepads.is_connected(id));
+        assert_eq!(
+            gamepads.get_button_state(id, GamepadButton::South),
+            ButtonState::NotPressed
+        );
+    }
+
+    #[test]
+    fn test_replaced_backend_disconnects() {
+        let backend = VirtualGamepadBackend::new();
+        let mut gamepads = Gamepads::with_backend(backend.clone());
+        let id = GamepadId(0);
+
+        backend.connect(id);
+        gamepads.update_gamepads();
+
+        gamepads.set_backend(VirtualGamepadBackend::new());
+        assert!(!gamepads.is_connected(id));
+
+        // the disconnect is seen on the next frame instead of being cleared
+        gamepads.update_gamepads();
+        assert_eq!(gamepads.get_events(), &[GamepadEvent::Disconnected(id)]);
+
+        gamepads.update_gamepads();
+        assert!(gamepads.get_events().is_empty());
+    }
+
+    #[test]
+    fn test_gamepad_deadzones() {
+        let backend = VirtualGamepadBackend::new();
+        let mut gamepads = Gamepads::with_backend(backend.clone());
+        gamepads.set_deadzones(GamepadDeadzones {
+            stick: 0.2,
+            trigger: 0.1,
+        });
+        let id = GamepadId(0);
+
+        backend.connect(id);
+        backend.set_axis(id, GamepadAxis::LeftStickX, 0.1);
+        backend.set_axis(id, GamepadAxis::LeftStickY, 0.1);
+        backend.set_axis(id, GamepadAxis::RightStickX, 0.6);
+        backend.set_axis(id, GamepadAxis::LeftTrigger, 1.0);
+        gamepads.update_gamepads();
+
+        assert_eq!(gamepads.get_left_stick(id), [0.0, 0.0]);
+        assert!((gamepads.get_axis(id, GamepadAxis::RightStickX) - 0.5).abs() < 1e-6);
+        assert_eq!(gamepads.get_axis(id, GamepadAxis::LeftTrigger), 1.0);
+        assert_eq!(gamepads.get_raw_axis(id, GamepadAxis::LeftStickX), 0.1);
+    }
+}
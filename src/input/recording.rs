@@ -0,0 +1,494 @@
+//! Recording the input of every frame and replaying it later
+//! this is meant for reproducing bug reports and for automated playthroughs without a window
+//!
+//! every frame stores its `GameTime` delta time, and applying a frame moves `GameTime` forward by it
+//! ABC's `DeltaTime` measures the real time and is scaled to match, so it's only close to the recording
+//! systems that should replay exactly the same must use `GameTime`
+
+use std::fmt;
+use std::path::Path;
+
+use ABC_Game_Engine::{DeltaTime, Input, KeyCode, Resource, World};
+
+use super::actions::{key_from_name, key_to_name, ActionMap, RawInput};
+use super::gamepad::Gamepads;
+use super::{mouse_button_from_id, MouseWheelDelta, DEFAULT_PIXELS_PER_LINE};
+use crate::time::GameTime;
+use crate::LumenpyxProgram;
+
+const RECORDING_HEADER: &str = "lumenpyx input recording v1";
+
+/// The input of one frame as it was given to the world
+/// gamepads aren't recorded, they are always read live
+#[derive(Clone, Default, PartialEq)]
+pub struct InputFrame {
+    pub keys_down: Vec<KeyCode>,
+    /// mouse buttons by the id winit gives them, 0 is left, 1 is right and 2 is middle
+    pub mouse_buttons_down: Vec<u32>,
+    /// the mouse position in world space, the same value that is given to `Input`
+    pub mouse_position: [f32; 2],
+    pub mouse_wheel: MouseWheelDelta,
+    /// the `GameTime` delta time of the frame, `apply_input_frame` moves `GameTime` forward by this
+    pub delta_time: f64,
+}
+
+/// Gives the input of a frame to the world, this is what the event loop does right before the update function
+/// when running a world without a window, call this before each `world.run()`
+pub fn apply_input_frame(world: &mut World, frame: &InputFrame) {
+    let entities_and_components = &mut world.entities_and_components;
+
+    if let Some(game_time) = entities_and_components.get_resource_mut::<GameTime>() {
+        game_time.advance_exact(frame.delta_time);
+        let time_scale = game_time.get_world_time_scale();
+
+        // DeltaTime measures the real time when the world runs, so it's scaled to the game time
+        if let Some(delta_time) = entities_and_components.get_resource_mut::<DeltaTime>() {
+            delta_time.set_time_scale(time_scale);
+        }
+    }
+
+    // the program doesn't exist when running without a window
+    let pixels_per_line = match entities_and_components.get_resource_mut::<LumenpyxProgram>() {
+        Some(lumen_program) => {
+            lumen_program.mouse_wheel = frame.mouse_wheel;
+            lumen_program.mouse_position = frame.mouse_position;
+            lumen_program.pixels_per_line
+        }
+        None => DEFAULT_PIXELS_PER_LINE,
+    };
+    let wheel_lines = frame.mouse_wheel.in_lines(pixels_per_line);
+
+    {
+        let input = entities_and_components
+            .get_resource_mut::<Input>()
+            .expect("failed to get input system probably a version mismatch");
+
+        input.clear_key_states();
+        for key in frame.keys_down.iter() {
+            input.set_key_down(*key);
+        }
+
+        input.clear_mouse_states();
+        for button in frame.mouse_buttons_down.iter() {
+            input.set_mouse_down(mouse_button_from_id(*button));
+        }
+
+        input.set_mouse_position(frame.mouse_position[0], frame.mouse_position[1]);
+
+        // Input only has a vertical wheel, so it gets the vertical scroll in lines
+        input.set_mouse_wheel(wheel_lines[1]);
+    }
+
+    let values = match entities_and_components.get_resource::<ActionMap>() {
+        Some(action_map) => {
+            let keys_down = frame.keys_down.iter().cloned().collect();
+            let mouse_buttons_down = frame.mouse_buttons_down.iter().cloned().collect();
+
+            Some(action_map.evaluate(&RawInput {
+                keys_down: &keys_down,
+                mouse_buttons_down: &mouse_buttons_down,
+                mouse_wheel_lines: wheel_lines,
+                gamepads: entities_and_components.get_resource::<Gamepads>(),
+            }))
+        }
+        None => None,
+    };
+
+    if let Some(values) = values {
+        entities_and_components
+            .get_resource_mut::<ActionMap>()
+            .expect("failed to get action map")
+            .set_values(values);
+    }
+}
+
+/// A list of recorded frames that can be saved to and loaded from a file
+#[derive(Clone, Default, PartialEq)]
+pub struct InputRecording {
+    frames: Vec<InputFrame>,
+}
+
+impl InputRecording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push_frame(&mut self, frame: InputFrame) {
+        self.frames.push(frame);
+    }
+
+    pub fn get_frames(&self) -> &[InputFrame] {
+        &self.frames
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// write the recording as text, one frame per line
+    /// a frame looks like `0.016 ; 10 -4.5 ; 0 ; 0 1 0 0 ; W LShift`
+    /// which is the delta time, mouse position, mouse buttons, wheel lines and pixels, and keys
+    /// floats are written so that they read back exactly, which keeps replays deterministic
+    pub fn to_recording_string(&self) -> String {
+        let mut recording = format!("{}\n", RECORDING_HEADER);
+
+        for frame in &self.frames {
+            // sorted so the same input is always written the same way
+            let mut buttons = frame.mouse_buttons_down.clone();
+            buttons.sort_unstable();
+            let buttons = buttons
+                .iter()
+                .map(|button| button.to_string())
+                .collect::<Vec<String>>()
+                .join(" ");
+
+            let mut keys = frame.keys_down.clone();
+            keys.sort_unstable();
+            // keys that can't be named can't come from the event loop, so there is nothing to lose
+            let keys = keys
+                .iter()
+                .filter_map(|key| key_to_name(*key))
+                .collect::<Vec<&str>>()
+                .join(" ");
+
+            recording.push_str(&format!(
+                "{} ; {} {} ; {} ; {} {} {} {} ; {}\n",
+                frame.delta_time,
+                frame.mouse_position[0],
+                frame.mouse_position[1],
+                buttons,
+                frame.mouse_wheel.lines[0],
+                frame.mouse_wheel.lines[1],
+                frame.mouse_wheel.pixels[0],
+                frame.mouse_wheel.pixels[1],
+                keys
+            ));
+        }
+
+        recording
+    }
+
+    /// read a recording written by `to_recording_string`
+    pub fn from_recording_str(recording: &str) -> Result<Self, RecordingError> {
+        let mut lines = recording.lines().enumerate();
+
+        match lines.next() {
+            Some((_, header)) if header.trim() == RECORDING_HEADER => (),
+            _ => {
+                return Err(RecordingError::Parse {
+                    line: 1,
+                    message: format!("expected the header `{}`", RECORDING_HEADER),
+                })
+            }
+        }
+
+        let mut frames = vec![];
+        for (index, line) in lines {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let frame = parse_frame(line).map_err(|message| RecordingError::Parse {
+                line: index + 1,
+                message,
+            })?;
+            frames.push(frame);
+        }
+
+        Ok(Self { frames })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), RecordingError> {
+        std::fs::write(path, self.to_recording_string())?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RecordingError> {
+        let recording = std::fs::read_to_string(path)?;
+        Self::from_recording_str(&recording)
+    }
+}
+
+fn parse_frame(line: &str) -> Result<InputFrame, String> {
+    let sections = line.split(';').map(str::trim).collect::<Vec<&str>>();
+    if sections.len() != 5 {
+        return Err(format!("expected 5 sections, found {}", sections.len()));
+    }
+
+    let delta_time = sections[0]
+        .parse::<f64>()
+        .map_err(|_| format!("`{}` is not a delta time", sections[0]))?;
+
+    let mouse_position = parse_floats::<2>(sections[1], "mouse position")?;
+
+    let mouse_buttons_down = sections[2]
+        .split_whitespace()
+        .map(|button| {
+            button
+                .parse::<u32>()
+                .map_err(|_| format!("`{}` is not a mouse button id", button))
+        })
+        .collect::<Result<Vec<u32>, String>>()?;
+
+    let wheel = parse_floats::<4>(sections[3], "mouse wheel")?;
+
+    let keys_down = sections[4]
+        .split_whitespace()
+        .map(|key| key_from_name(key).ok_or_else(|| format!("unknown key `{}`", key)))
+        .collect::<Result<Vec<KeyCode>, String>>()?;
+
+    Ok(InputFrame {
+        keys_down,
+        mouse_buttons_down,
+        mouse_position,
+        mouse_wheel: MouseWheelDelta {
+            lines: [wheel[0], wheel[1]],
+            pixels: [wheel[2], wheel[3]],
+        },
+        delta_time,
+    })
+}
+
+fn parse_floats<const N: usize>(section: &str, name: &str) -> Result<[f32; N], String> {
+    let mut values = [0.0; N];
+    let mut count = 0;
+
+    for value in section.split_whitespace() {
+        if count == N {
+            return Err(format!("too many values in the {}", name));
+        }
+        values[count] = value
+            .parse::<f32>()
+            .map_err(|_| format!("`{}` is not a number in the {}", value, name))?;
+        count += 1;
+    }
+
+    if count != N {
+        return Err(format!("expected {} values in the {}", N, name));
+    }
+
+    Ok(values)
+}
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Io(std::io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordingError::Io(error) => {
+                write!(f, "failed to access the recording file: {}", error)
+            }
+            RecordingError::Parse { line, message } => {
+                write!(f, "failed to parse recording on line {}: {}", line, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {}
+
+impl From<std::io::Error> for RecordingError {
+    fn from(error: std::io::Error) -> Self {
+        RecordingError::Io(error)
+    }
+}
+
+enum RecorderMode {
+    Idle,
+    Recording(InputRecording),
+    Replaying {
+        recording: InputRecording,
+        next_frame: usize,
+    },
+}
+
+/// The resource that records the input of each frame or replays a recording instead of the live input
+pub struct InputRecorder {
+    mode: RecorderMode,
+    /// the delta time of the frame currently being replayed
+    replayed_delta_time: Option<f64>,
+}
+
+impl InputRecorder {
+    pub fn new() -> Self {
+        Self {
+            mode: RecorderMode::Idle,
+            replayed_delta_time: None,
+        }
+    }
+
+    /// start recording from the next frame, this stops any replay
+    pub fn start_recording(&mut self) {
+        self.mode = RecorderMode::Recording(InputRecording::new());
+        self.replayed_delta_time = None;
+    }
+
+    /// stop recording and get everything recorded so far, None if nothing was being recorded
+    pub fn stop_recording(&mut self) -> Option<InputRecording> {
+        match std::mem::replace(&mut self.mode, RecorderMode::Idle) {
+            RecorderMode::Recording(recording) => Some(recording),
+            other => {
+                self.mode = other;
+                None
+            }
+        }
+    }
+
+    /// replay a recording from the next frame instead of the live input, this stops any recording
+    /// once every frame has been replayed the live input is used again
+    pub fn start_replay(&mut self, recording: InputRecording) {
+        self.mode = RecorderMode::Replaying {
+            recording,
+            next_frame: 0,
+        };
+    }
+
+    pub fn stop_replay(&mut self) {
+        if self.is_replaying() {
+            self.mode = RecorderMode::Idle;
+        }
+        self.replayed_delta_time = None;
+    }
+
+    pub fn is_recording(&self) -> bool {
+        matches!(self.mode, RecorderMode::Recording(_))
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        matches!(self.mode, RecorderMode::Replaying { .. })
+    }
+
+    /// the (frames replayed, total frames) of the current replay
+    pub fn get_replay_progress(&self) -> Option<(usize, usize)> {
+        match &self.mode {
+            RecorderMode::Replaying {
+                recording,
+                next_frame,
+            } => Some((*next_frame, recording.len())),
+            _ => None,
+        }
+    }
+
+    /// the recorded delta time of the frame currently being replayed
+    /// `GameTime` uses this while replaying, so systems using it match the recording
+    pub fn get_replayed_delta_time(&self) -> Option<f64> {
+        self.replayed_delta_time
+    }
+
+    /// get the next frame of the replay, None if nothing is being replayed
+    /// the recorder goes back to idle after the last frame
+    pub fn next_replay_frame(&mut self) -> Option<InputFrame> {
+        let frame = match &mut self.mode {
+            RecorderMode::Replaying {
+                recording,
+                next_frame,
+            } => {
+                let frame = recording.get_frames().get(*next_frame).cloned();
+                *next_frame += 1;
+                frame
+            }
+            _ => return None,
+        };
+
+        if frame.is_none() {
+            self.mode = RecorderMode::Idle;
+        }
+        self.replayed_delta_time = frame.as_ref().map(|frame| frame.delta_time);
+
+        frame
+    }
+
+    /// add a frame to the recording, does nothing if nothing is being recorded
+    pub fn record_frame(&mut self, frame: InputFrame) {
+        if let RecorderMode::Recording(recording) = &mut self.mode {
+            recording.push_frame(frame);
+        }
+    }
+}
+
+impl Default for InputRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Resource for InputRecorder {
+    fn update(&mut self) {}
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_frame(delta_time: f64) -> InputFrame {
+        InputFrame {
+            keys_down: vec![KeyCode::W, KeyCode::LShift],
+            mouse_buttons_down: vec![0, 4],
+            mouse_position: [10.25, -3.0],
+            mouse_wheel: MouseWheelDelta {
+                lines: [0.0, 1.0],
+                pixels: [-2.5, 0.0],
+            },
+            delta_time,
+        }
+    }
+
+    #[test]
+    fn test_recording_round_trip() {
+        let mut recording = InputRecording::new();
+        recording.push_frame(test_frame(1.0 / 60.0));
+        recording.push_frame(InputFrame::default());
+
+        let text = recording.to_recording_string();
+        let loaded = InputRecording::from_recording_str(&text).expect("failed to parse recording");
+
+        assert!(loaded == recording);
+        assert!(InputRecording::from_recording_str("not a recording").is_err());
+    }
+
+    #[test]
+    fn test_recording_order_independent() {
+        let mut recording = InputRecording::new();
+        recording.push_frame(test_frame(0.25));
+
+        let mut reordered_frame = test_frame(0.25);
+        reordered_frame.keys_down.reverse();
+        reordered_frame.mouse_buttons_down.reverse();
+        let mut reordered = InputRecording::new();
+        reordered.push_frame(reordered_frame);
+
+        assert_eq!(
+            recording.to_recording_string(),
+            reordered.to_recording_string()
+        );
+    }
+
+    #[test]
+    fn test_recorder_replays_then_goes_idle() {
+        let mut recording = InputRecording::new();
+        recording.push_frame(test_frame(0.5));
+
+        let mut recorder = InputRecorder::new();
+        recorder.start_replay(recording);
+
+        assert!(recorder.next_replay_frame() == Some(test_frame(0.5)));
+        assert_eq!(recorder.get_replayed_delta_time(), Some(0.5));
+        assert!(recorder.next_replay_frame().is_none());
+        assert!(!recorder.is_replaying());
+        assert_eq!(recorder.get_replayed_delta_time(), None);
+    }
+}
@@ -1,27 +1,30 @@
 //! The official ABC Game Engine implementation of lumenpyx
 
 //use lumenpyx::animation::Animation;
+mod blending;
 mod drawables;
-use std::collections::HashSet;
+mod pipeline;
+mod shapes;
+use std::collections::{HashMap, HashSet};
 use std::ops::{Deref, DerefMut};
 
 //pub use lumenpyx::*;
 use drawables::lights::{AreaLight, DirectionalLight, PointLight};
 use drawables::primitives::{
-    Animation, AnimationStateMachine, Circle, Cylinder, Rectangle, Sphere, Sprite, TextBox,
+    Animation, AnimationStateMachine, ArcShape, Circle, Cylinder, Ellipse, Line, Polygon, Polyline,
+    Rectangle, Ring, RoundedRectangle, Sphere, Sprite, TextBox,
 };
 
 pub use drawables::*;
-use lumenpyx::draw_all;
 use lumenpyx::Transform;
-use mouse_position::mouse_position::Mouse;
-use winit::dpi::PhysicalPosition;
-use ABC_Game_Engine::{self, get_transform, DeltaTime, Resource, World};
+pub use pipeline::{RenderBuffer, RenderSettings};
+use ABC_Game_Engine::{self, get_transform, Resource, World};
 use ABC_Game_Engine::{EntitiesAndComponents, Input};
 use ABC_Game_Engine::{Entity, KeyCode};
 
+use winit::error::EventLoopError;
 use winit::event::Event::DeviceEvent;
-use winit::event_loop::EventLoop;
+use winit::event_loop::{ControlFlow, EventLoop, EventLoopWindowTarget};
 
 // pub use everything from lumenpyx but exclude the things we override in drawables
 pub use lumenpyx::blending::BlendMode;
@@ -32,15 +35,77 @@ pub use lumenpyx::primitives::Normal;
 pub use lumenpyx::primitives::Texture;
 pub use lumenpyx::text::{FontFamily, FontStack, GenericFamily};
 pub use lumenpyx::DebugOption;
-pub use lumenpyx::RenderSettings;
 pub use lumenpyx::TextureHandle;
 
-use crate::primitives::{BlendComponent, LumenBlendObject};
+use crate::assets::{unload_unused_assets, Assets};
+use crate::atlas::AtlasSprite;
+use crate::capture::{capture_frame, Capture};
+use crate::input::actions::ActionMap;
+use crate::input::gamepad::{GamepadBackend, Gamepads, GilrsBackend};
+use crate::input::recording::{apply_input_frame, InputFrame, InputRecorder};
+use crate::input::{MouseWheelDelta, DEFAULT_PIXELS_PER_LINE};
+use crate::nine_slice::NineSliceSprite;
+use crate::pipeline::{
+    copy_target_to_texture, draw_scene, present_target, CameraPass, MAIN_TARGET,
+};
+use crate::post_processing::palette::Palette;
+use crate::post_processing::{apply_post_effects, PostEffect};
+use crate::primitives::{
+    BlendComponent, BlendDiagnostic, LumenBlendObject, LumenGroupObject, LumenMaskObject,
+    MaskComponent, MaskDiagnostic, MaskSource, OpacityGroup,
+};
+use crate::resolution::{window_to_render_position, ResolutionPolicy};
+use crate::tilemap::Tilemap;
+use crate::time::GameTime;
+use crate::window::secondary::{
+    finish_secondary_windows_frame, get_camera_passes, handle_secondary_window_event,
+    start_secondary_windows_frame, SecondaryWindows, WindowView,
+};
+use crate::window::{
+    update_custom_cursors, window_event_from_winit, Quit, WindowEvent, WindowEvents, WindowId,
+};
+pub mod assets;
+pub mod atlas;
+pub mod capture;
+pub mod input;
+pub mod nine_slice;
+pub mod post_processing;
 pub mod prelude;
+pub mod resolution;
+pub mod tilemap;
+pub mod time;
+pub mod window;
 
 pub struct LumenpyxProgram {
     pub internal_program: lumenpyx::LumenpyxProgram,
     keys_down: HashSet<KeyCode>,
+    /// mouse buttons by the id winit gives them
+    mouse_buttons_down: HashSet<u32>,
+    /// the scroll collected from events since the last frame
+    pending_mouse_wheel: MouseWheelDelta,
+    /// the scroll of the current frame
+    mouse_wheel: MouseWheelDelta,
+    /// the mouse position of the current frame in world space
+    mouse_position: [f32; 2],
+    /// the last cursor position winit gave us, in physical pixels from the top left of the window
+    cursor_position: Option<[f64; 2]>,
+    pixels_per_line: f32,
+    /// the resolution the resolution policy is based on
+    virtual_resolution: [u32; 2],
+    resolution_policy: ResolutionPolicy,
+    /// a copy of the render settings given to lumenpyx, because lumenpyx's can't be read
+    settings: RenderSettings,
+    post_effects: Vec<PostEffect>,
+    /// the textures of the palettes in the post processing chain, made when the effect is added
+    palette_textures: HashMap<Palette, glium::Texture2d>,
+    /// the target cameras rendering into a texture draw to, kept so the name stays the same every frame
+    texture_targets: HashMap<TextureHandle, String>,
+    /// only ever goes up, so two textures never get the same target name
+    next_texture_target: u64,
+    /// targets of textures that stopped being drawn, their textures are freed so they can be given out again
+    free_texture_targets: Vec<String>,
+    /// the resolutions drawn at so far this frame, their blending textures can't be resized until the next one
+    blending_resolutions: Vec<[u32; 2]>,
 }
 
 impl Resource for LumenpyxProgram {
@@ -56,6 +121,7 @@ impl Resource for LumenpyxProgram {
 /// The event loop for the lumenpyx program
 pub struct LumenpyxEventLoop {
     event_loop: EventLoop<()>,
+    shutdown_callback: Option<ShutdownCallback>,
 }
 
 impl LumenpyxEventLoop {
@@ -67,9 +133,63 @@ impl LumenpyxEventLoop {
         world.entities_and_components.add_resource(LumenpyxProgram {
             internal_program: program,
             keys_down: HashSet::new(),
+            mouse_buttons_down: HashSet::new(),
+            pending_mouse_wheel: MouseWheelDelta::default(),
+            mouse_wheel: MouseWheelDelta::default(),
+            mouse_position: [0.0, 0.0],
+            cursor_position: None,
+            pixels_per_line: DEFAULT_PIXELS_PER_LINE,
+            virtual_resolution: resolution,
+            resolution_policy: ResolutionPolicy::default(),
+            settings: RenderSettings::default(),
+            post_effects: vec![],
+            palette_textures: HashMap::new(),
+            texture_targets: HashMap::new(),
+            next_texture_target: 0,
+            free_texture_targets: vec![],
+            blending_resolutions: vec![],
         });
 
-        Self { event_loop }
+        // if gilrs can't start we still add the resource, so game code doesn't have to check for it
+        let mut gamepads = Gamepads::new();
+        if let Some(backend) = GilrsBackend::new() {
+            gamepads.set_backend(backend);
+        }
+        world.entities_and_components.add_resource(gamepads);
+        world.entities_and_components.add_resource(ActionMap::new());
+        world
+            .entities_and_components
+            .add_resource(InputRecorder::new());
+        world
+            .entities_and_components
+            .add_resource(WindowEvents::new());
+        world.entities_and_components.add_resource(Quit::new());
+        world.entities_and_components.add_resource(GameTime::new());
+        world
+            .entities_and_components
+            .add_resource(SecondaryWindows::new());
+        world.entities_and_components.add_resource(Capture::new());
+        world.entities_and_components.add_resource(Assets::new());
+
+        Self {
+            event_loop,
+            shutdown_callback: None,
+        }
+    }
+
+    /// set where gamepad events come from, by default gilrs is used
+    /// this is a convenience function for setting the backend of the gamepads resource
+    pub fn set_gamepad_backend(
+        &mut self,
+        world: &mut World,
+        backend: impl GamepadBackend + 'static,
+    ) {
+        let gamepads = world
+            .entities_and_components
+            .get_resource_mut::<Gamepads>()
+            .expect("failed to get gamepads");
+
+        gamepads.set_backend(backend);
     }
 
     /// set the render settings for the program
@@ -80,7 +200,7 @@ impl LumenpyxEventLoop {
             .get_resource_mut::<LumenpyxProgram>()
             .expect("failed to get lumen program");
 
-        lumen_program.internal_program.set_render_settings(settings);
+        lumen_program.set_render_settings(settings);
     }
 
     /// set the debug options for the program
@@ -107,13 +227,25 @@ impl LumenpyxEventLoop {
 
     /// set the resolution of the window
     /// this is a convenience function for setting the resolution of the window
+    /// with a resolution policy other than letterbox, this is the resolution the policy is based on
     pub fn set_resolution(&mut self, world: &mut World, resolution: [u32; 2]) {
         let lumen_program = world
             .entities_and_components
             .get_resource_mut::<LumenpyxProgram>()
             .expect("failed to get lumen program");
 
-        lumen_program.internal_program.set_resolution(resolution);
+        lumen_program.set_virtual_resolution(resolution);
+    }
+
+    /// set what happens to the render resolution when the window is resized
+    /// this is a convenience function for setting the resolution policy
+    pub fn set_resolution_policy(&mut self, world: &mut World, policy: ResolutionPolicy) {
+        let lumen_program = world
+            .entities_and_components
+            .get_resource_mut::<LumenpyxProgram>()
+            .expect("failed to get lumen program");
+
+        lumen_program.set_resolution_policy(policy);
     }
 
     /// get the resolution of the window
@@ -128,251 +260,523 @@ impl LumenpyxEventLoop {
         lumen_program.internal_program.get_resolution()
     }
 
+    /// open another window that shares the world and textures with the main one
+    /// draw to it with a camera that has `CameraTarget::Window` and the returned id, or show a view of the main render
+    /// windows can only be opened before the event loop is run, the size is in logical pixels
+    pub fn add_window(
+        &mut self,
+        world: &mut World,
+        name: &str,
+        size: [u32; 2],
+        view: WindowView,
+    ) -> WindowId {
+        let window_builder = winit::window::WindowBuilder::new()
+            .with_title(name)
+            .with_inner_size(winit::dpi::LogicalSize::new(size[0], size[1]));
+        let (window, display) = glium::backend::glutin::SimpleWindowBuilder::new()
+            .set_window_builder(window_builder)
+            .build(&self.event_loop);
+
+        let resolution = world
+            .entities_and_components
+            .get_resource::<LumenpyxProgram>()
+            .expect("failed to get lumen program")
+            .get_virtual_resolution();
+
+        world
+            .entities_and_components
+            .get_resource_mut::<SecondaryWindows>()
+            .expect("failed to get secondary windows")
+            .add_window(window, display, view, resolution)
+    }
+
+    /// set a function that is called once when the event loop exits, after the last frame
+    /// use it to save the game or release resources that need the window to still exist
+    pub fn set_shutdown_callback(&mut self, callback: impl FnMut(&mut World) + 'static) {
+        self.shutdown_callback = Some(Box::new(callback));
+    }
+
     /// run the program with the given update function
-    pub fn run<F>(self, world: &mut World, mut update: F)
+    /// the loop exits when the window is closed or `Quit::request` is called,
+    /// on some platforms (web and ios) this never returns
+    pub fn run<F>(mut self, world: &mut World, mut update: F)
     where
         F: FnMut(&mut World),
     {
+        let mut shutdown_callback = self.shutdown_callback.take();
+
         self.event_loop
-            .run(move |ev, window_target| match ev {
-                winit::event::Event::WindowEvent { event, .. } => match event {
-                    winit::event::WindowEvent::Focused(_) => {
-                        let input = world
-                            .entities_and_components
-                            .get_resource_mut::<Input>()
-                            .expect("failed to get input system probably a version mismatch");
-
-                        // make sure the keys are cleared when the window is focused
-                        // without this, the keys will be stuck down while the window is not focused
-                        input.clear_mouse_states();
-                        input.clear_key_states();
-                    }
-                    winit::event::WindowEvent::CloseRequested => {
-                        window_target.exit();
-                    }
-                    winit::event::WindowEvent::Resized(physical_size) => {
-                        let lumen_program = world
-                            .entities_and_components
-                            .get_resource_mut::<LumenpyxProgram>()
-                            .expect("failed to get lumen program");
-                        lumen_program.display.resize(physical_size.into());
-                    }
-                    winit::event::WindowEvent::RedrawRequested => {
-                        let keys_down;
-                        {
-                            let lumen_program = world
-                                .entities_and_components
-                                .get_resource::<LumenpyxProgram>()
-                                .expect("failed to get lumen program");
-
-                            keys_down = lumen_program.keys_down.clone();
-                        }
+            .run(move |ev, window_target| {
+                handle_event(
+                    world,
+                    &mut update,
+                    &mut shutdown_callback,
+                    ev,
+                    window_target,
+                )
+            })
+            .expect("Failed to run event loop");
+    }
 
-                        {
-                            let input = world
-                                .entities_and_components
-                                .get_resource_mut::<Input>()
-                                .expect("failed to get input system probably a version mismatch");
+    /// like `run` but doesn't consume the event loop, so it can be run again after it exits
+    /// this is useful for tools and tests that need multiple sessions in one process
+    /// the window is hidden between sessions
+    #[cfg(not(any(target_arch = "wasm32", target_os = "ios")))]
+    pub fn run_returning<F>(
+        &mut self,
+        world: &mut World,
+        mut update: F,
+    ) -> Result<(), EventLoopError>
+    where
+        F: FnMut(&mut World),
+    {
+        use winit::platform::run_on_demand::EventLoopExtRunOnDemand;
+
+        set_window_visible(world, true);
+
+        let result = self.event_loop.run_on_demand(|ev, window_target| {
+            handle_event(
+                world,
+                &mut update,
+                &mut self.shutdown_callback,
+                ev,
+                window_target,
+            )
+        });
 
-                            input.clear_key_states();
-                            for key in keys_down.iter() {
-                                input.set_key_down(*key);
-                            }
-                        }
+        set_window_visible(world, false);
+
+        result
+    }
+}
 
-                        update_mouse_pos(world);
+/// the function called when the event loop exits
+type ShutdownCallback = Box<dyn FnMut(&mut World)>;
 
-                        update(world);
+#[cfg(not(any(target_arch = "wasm32", target_os = "ios")))]
+fn set_window_visible(world: &mut World, visible: bool) {
+    world
+        .entities_and_components
+        .get_resource::<LumenpyxProgram>()
+        .expect("failed to get lumen program")
+        .window
+        .set_visible(visible);
+}
 
-                        // without this, the wheel will be stuck at the last value
-                        let input = world
-                            .entities_and_components
-                            .get_resource_mut::<Input>()
-                            .expect("failed to get input system probably a version mismatch");
+/// handles a single event from the event loop
+fn handle_event<F>(
+    world: &mut World,
+    update: &mut F,
+    shutdown_callback: &mut Option<ShutdownCallback>,
+    ev: winit::event::Event<()>,
+    window_target: &EventLoopWindowTarget<()>,
+) where
+    F: FnMut(&mut World),
+{
+    match ev {
+        winit::event::Event::WindowEvent { window_id, event } => {
+            let main_window_id = world
+                .entities_and_components
+                .get_resource::<LumenpyxProgram>()
+                .expect("failed to get lumen program")
+                .window
+                .id();
 
-                        input.set_mouse_wheel(0.0);
-                    }
-                    winit::event::WindowEvent::KeyboardInput { event, .. } => {
-                        let lumen_program = world
-                            .entities_and_components
-                            .get_resource_mut::<LumenpyxProgram>()
-                            .expect("failed to get lumen program");
-
-                        if event.state == winit::event::ElementState::Pressed {
-                            // turn the key event into a key enum in winit
-                            match event.physical_key {
-                                winit::keyboard::PhysicalKey::Code(code) => {
-                                    let key = winit_input_to_abc_input(code);
-                                    if let Some(key) = key {
-                                        lumen_program.keys_down.insert(key);
-                                    }
-                                }
-                                // maybe we should log something here, once we have a logger...
-                                // for now, we just ignore it.
-                                winit::keyboard::PhysicalKey::Unidentified(_) => (),
-                            }
-                        } else if event.state == winit::event::ElementState::Released {
-                            // turn the key event into a key enum in winit
-                            match event.physical_key {
-                                winit::keyboard::PhysicalKey::Code(code) => {
-                                    let key = winit_input_to_abc_input(code);
-                                    if let Some(key) = key {
-                                        lumen_program.keys_down.remove(&key);
-                                    }
+            if window_id == main_window_id {
+                queue_window_event(world, &event);
+            } else {
+                let windows = world
+                    .entities_and_components
+                    .get_resource_mut::<SecondaryWindows>()
+                    .expect("failed to get secondary windows");
+
+                handle_secondary_window_event(windows, window_id, &event);
+
+                // keys and scrolling go to the game whichever of its windows has focus,
+                // everything else only matters to the window it happened to
+                if !matches!(
+                    event,
+                    winit::event::WindowEvent::KeyboardInput { .. }
+                        | winit::event::WindowEvent::MouseWheel { .. }
+                ) {
+                    return;
+                }
+            }
+
+            match event {
+                winit::event::WindowEvent::Focused(_) => {
+                    let input = world
+                        .entities_and_components
+                        .get_resource_mut::<Input>()
+                        .expect("failed to get input system probably a version mismatch");
+
+                    // make sure the keys are cleared when the window is focused
+                    // without this, the keys will be stuck down while the window is not focused
+                    input.clear_mouse_states();
+                    input.clear_key_states();
+
+                    let lumen_program = world
+                        .entities_and_components
+                        .get_resource_mut::<LumenpyxProgram>()
+                        .expect("failed to get lumen program");
+
+                    lumen_program.mouse_buttons_down.clear();
+                }
+                winit::event::WindowEvent::Resized(physical_size) => {
+                    let lumen_program = world
+                        .entities_and_components
+                        .get_resource_mut::<LumenpyxProgram>()
+                        .expect("failed to get lumen program");
+                    lumen_program.display.resize(physical_size.into());
+                    lumen_program.apply_resolution_policy();
+                }
+                winit::event::WindowEvent::ScaleFactorChanged { .. } => {
+                    // winit resizes the window to keep its logical size, which is handled in Resized,
+                    // but a policy based on logical pixels needs updating even if the physical size stays
+                    let lumen_program = world
+                        .entities_and_components
+                        .get_resource_mut::<LumenpyxProgram>()
+                        .expect("failed to get lumen program");
+
+                    lumen_program.apply_resolution_policy();
+                }
+                // the cursor position is relative to the window and in physical pixels, like the window size
+                // so they match on high DPI screens, unlike the global cursor position
+                winit::event::WindowEvent::CursorMoved { position, .. } => {
+                    let lumen_program = world
+                        .entities_and_components
+                        .get_resource_mut::<LumenpyxProgram>()
+                        .expect("failed to get lumen program");
+
+                    lumen_program.cursor_position = Some([position.x, position.y]);
+                }
+                winit::event::WindowEvent::RedrawRequested => {
+                    run_frame(world, update);
+                    exit_if_requested(world, window_target);
+                }
+                // a minimized or hidden window may not get a redraw for a long time,
+                // so the game gets a frame right away to see the close request and veto it
+                winit::event::WindowEvent::CloseRequested => {
+                    run_frame(world, update);
+                    exit_if_requested(world, window_target);
+                }
+                // the window event is used rather than the device event because trackpads
+                // only report precise pixel scrolling through the window
+                winit::event::WindowEvent::MouseWheel { delta, .. } => {
+                    let lumen_program = world
+                        .entities_and_components
+                        .get_resource_mut::<LumenpyxProgram>()
+                        .expect("failed to get lumen program");
+
+                    lumen_program.pending_mouse_wheel.accumulate(delta);
+                }
+                winit::event::WindowEvent::KeyboardInput { event, .. } => {
+                    let lumen_program = world
+                        .entities_and_components
+                        .get_resource_mut::<LumenpyxProgram>()
+                        .expect("failed to get lumen program");
+
+                    if event.state == winit::event::ElementState::Pressed {
+                        // turn the key event into a key enum in winit
+                        match event.physical_key {
+                            winit::keyboard::PhysicalKey::Code(code) => {
+                                let key = winit_input_to_abc_input(code);
+                                if let Some(key) = key {
+                                    lumen_program.keys_down.insert(key);
                                 }
-                                // maybe we should log something here, once we have a logger...
-                                // for now, we just ignore it.
-                                winit::keyboard::PhysicalKey::Unidentified(_) => (),
                             }
+                            // maybe we should log something here, once we have a logger...
+                            // for now, we just ignore it.
+                            winit::keyboard::PhysicalKey::Unidentified(_) => (),
                         }
-                    }
-                    _ => (),
-                },
-
-                // mouse events
-                DeviceEvent { event, device_id } => match event {
-                    // click events
-                    winit::event::DeviceEvent::Button { button, state } => {
-                        let input = world
-                            .entities_and_components
-                            .get_resource_mut::<Input>()
-                            .expect("failed to get input system probably a version mismatch");
-
-                        input.clear_mouse_states();
-
-                        match button {
-                            // left click
-                            0 => {
-                                if state == winit::event::ElementState::Pressed {
-                                    input.set_mouse_down(ABC_Game_Engine::MouseButton::Left);
-                                }
-                            }
-                            // right click
-                            1 => {
-                                if state == winit::event::ElementState::Pressed {
-                                    input.set_mouse_down(ABC_Game_Engine::MouseButton::Right);
-                                }
-                            }
-                            // middle click
-                            2 => {
-                                if state == winit::event::ElementState::Pressed {
-                                    input.set_mouse_down(ABC_Game_Engine::MouseButton::Middle);
+                    } else if event.state == winit::event::ElementState::Released {
+                        // turn the key event into a key enum in winit
+                        match event.physical_key {
+                            winit::keyboard::PhysicalKey::Code(code) => {
+                                let key = winit_input_to_abc_input(code);
+                                if let Some(key) = key {
+                                    lumen_program.keys_down.remove(&key);
                                 }
                             }
-                            other => {
-                                let input = world
-                                    .entities_and_components
-                                    .get_resource_mut::<Input>()
-                                    .expect(
-                                        "failed to get input system probably a version mismatch",
-                                    );
-
-                                if state == winit::event::ElementState::Pressed {
-                                    input
-                                        .set_mouse_down(ABC_Game_Engine::MouseButton::Other(other));
-                                }
-                            }
-                        }
-                    }
-                    winit::event::DeviceEvent::MouseWheel { delta } => {
-                        let input = world
-                            .entities_and_components
-                            .get_resource_mut::<Input>()
-                            .expect("failed to get input system probably a version mismatch");
-
-                        match delta {
-                            winit::event::MouseScrollDelta::LineDelta(x, y) => {
-                                input.set_mouse_wheel(y as f32);
-                            }
-                            winit::event::MouseScrollDelta::PixelDelta(physical_position) => {
-                                input.set_mouse_wheel(physical_position.y as f32);
-                            }
+                            // maybe we should log something here, once we have a logger...
+                            // for now, we just ignore it.
+                            winit::keyboard::PhysicalKey::Unidentified(_) => (),
                         }
                     }
-                    _ => (),
-                },
-                winit::event::Event::AboutToWait => {
-                    let lumen_program = world
-                        .entities_and_components
-                        .get_resource_mut::<LumenpyxProgram>()
-                        .expect("failed to get lumen program");
-
-                    // RedrawRequested will only when we resize the window, so we need to manually
-                    // request it.
-                    lumen_program.window.request_redraw();
                 }
                 _ => (),
-            })
-            .expect("Failed to run event loop");
+            }
+        }
+
+        // mouse click events
+        DeviceEvent {
+            event: winit::event::DeviceEvent::Button { button, state },
+            ..
+        } => {
+            let lumen_program = world
+                .entities_and_components
+                .get_resource_mut::<LumenpyxProgram>()
+                .expect("failed to get lumen program");
+
+            // the buttons are given to Input at the start of each frame
+            if state == winit::event::ElementState::Pressed {
+                lumen_program.mouse_buttons_down.insert(button);
+            } else {
+                lumen_program.mouse_buttons_down.remove(&button);
+            }
+        }
+        winit::event::Event::Suspended => {
+            world
+                .entities_and_components
+                .get_resource_mut::<WindowEvents>()
+                .expect("failed to get window events")
+                .push_event(WindowEvent::Suspended);
+        }
+        winit::event::Event::Resumed => {
+            world
+                .entities_and_components
+                .get_resource_mut::<WindowEvents>()
+                .expect("failed to get window events")
+                .push_event(WindowEvent::Resumed);
+        }
+        winit::event::Event::LoopExiting => {
+            // a gif isn't finished until its last frame is written
+            world
+                .entities_and_components
+                .get_resource_mut::<Capture>()
+                .expect("failed to get capture")
+                .finish_recording();
+
+            if let Some(shutdown_callback) = shutdown_callback {
+                shutdown_callback(world);
+            }
+        }
+        winit::event::Event::AboutToWait => {
+            let window_events = world
+                .entities_and_components
+                .get_resource::<WindowEvents>()
+                .expect("failed to get window events");
+
+            let throttled_frame_time = world
+                .entities_and_components
+                .get_resource::<GameTime>()
+                .expect("failed to get game time")
+                .get_throttled_frame_time(window_events);
+
+            // while the window is hidden, wait instead of drawing frames nobody can see
+            if let Some(throttled_frame_time) = throttled_frame_time {
+                window_target.set_control_flow(ControlFlow::WaitUntil(throttled_frame_time));
+                return;
+            }
+            window_target.set_control_flow(ControlFlow::Wait);
+
+            let lumen_program = world
+                .entities_and_components
+                .get_resource_mut::<LumenpyxProgram>()
+                .expect("failed to get lumen program");
+
+            // RedrawRequested will only when we resize the window, so we need to manually
+            // request it.
+            lumen_program.window.request_redraw();
+        }
+        _ => (),
     }
 }
 
-fn update_mouse_pos(world: &mut World) {
+/// exits the event loop if the window should close or the game requested to quit during the last frame
+fn exit_if_requested(world: &mut World, window_target: &EventLoopWindowTarget<()>) {
+    // the game had the whole frame to veto closing the window
+    let close = world
+        .entities_and_components
+        .get_resource::<WindowEvents>()
+        .expect("failed to get window events")
+        .should_close();
+
+    let quit = world
+        .entities_and_components
+        .get_resource_mut::<Quit>()
+        .expect("failed to get quit");
+
+    if close || quit.is_requested() {
+        // if the loop is run again it shouldn't exit right away
+        quit.cancel();
+        window_target.exit();
+    }
+}
+
+/// gives the world the input of this frame, runs the update function and records the frame if needed
+fn run_frame<F>(world: &mut World, update: &mut F)
+where
+    F: FnMut(&mut World),
+{
+    world
+        .entities_and_components
+        .get_resource_mut::<WindowEvents>()
+        .expect("failed to get window events")
+        .start_frame();
+    start_secondary_windows_frame(&mut world.entities_and_components);
+
+    world
+        .entities_and_components
+        .get_resource_mut::<Gamepads>()
+        .expect("failed to get gamepads")
+        .update_gamepads();
+
+    let live_frame = take_live_input_frame(world);
+
+    // measured for replays too, so the first live frame after one doesn't include the whole replay
+    let real_delta_time = world
+        .entities_and_components
+        .get_resource_mut::<GameTime>()
+        .expect("failed to get game time")
+        .measure_frame();
+
+    // a replay takes the place of the live input
+    let replay_frame = world
+        .entities_and_components
+        .get_resource_mut::<InputRecorder>()
+        .expect("failed to get input recorder")
+        .next_replay_frame();
+
+    // applying the frame moves game time forward by its delta time
+    let frame = match replay_frame {
+        Some(replay_frame) => replay_frame,
+        None => InputFrame {
+            delta_time: live_game_delta_time(world, real_delta_time),
+            ..live_frame
+        },
+    };
+
+    apply_input_frame(world, &frame);
+    update_custom_cursors(&mut world.entities_and_components, frame.mouse_position);
+
+    update(world);
+
+    finish_secondary_windows_frame(&mut world.entities_and_components);
+    capture_frame(&mut world.entities_and_components);
+    unload_unused_assets(&mut world.entities_and_components);
+
+    // without this, the wheel will be stuck at the last value
+    let input = world
+        .entities_and_components
+        .get_resource_mut::<Input>()
+        .expect("failed to get input system probably a version mismatch");
+
+    input.set_mouse_wheel(0.0);
+
+    world
+        .entities_and_components
+        .get_resource_mut::<InputRecorder>()
+        .expect("failed to get input recorder")
+        .record_frame(frame);
+}
+
+/// the game time delta time of this frame from the real time since the last frame
+fn live_game_delta_time(world: &mut World, real_delta_time: f64) -> f64 {
+    let window_events = world
+        .entities_and_components
+        .get_resource::<WindowEvents>()
+        .expect("failed to get window events");
+    let window_focused = window_events.is_focused();
+    let window_minimized = window_events.is_minimized();
+
+    let game_time = world
+        .entities_and_components
+        .get_resource_mut::<GameTime>()
+        .expect("failed to get game time");
+
+    game_time.next_delta_time(real_delta_time, window_focused, window_minimized)
+}
+
+/// gives a winit window event to the game through the `WindowEvents` resource
+fn queue_window_event(world: &mut World, event: &winit::event::WindowEvent) {
     let lumen_program = world
         .entities_and_components
         .get_resource::<LumenpyxProgram>()
         .expect("failed to get lumen program");
 
-    let inner_pos = lumen_program
-        .window
-        .inner_position()
-        .unwrap_or(PhysicalPosition::new(0, 0));
+    let was_minimized = world
+        .entities_and_components
+        .get_resource::<WindowEvents>()
+        .expect("failed to get window events")
+        .is_minimized();
 
-    let inner_size = lumen_program.window.inner_size();
+    let Some(event) = window_event_from_winit(event, &lumen_program.window, was_minimized) else {
+        return;
+    };
 
-    let mouse_position = Mouse::get_mouse_position();
+    world
+        .entities_and_components
+        .get_resource_mut::<WindowEvents>()
+        .expect("failed to get window events")
+        .push_event(event);
+}
 
-    match mouse_position {
-        Mouse::Position { x, y } => {
-            let local_x = x - inner_pos.x;
-            let mut local_y = y - inner_pos.y;
-            // the y axis is flipped in lumen compared to winit
-            local_y = inner_size.height as i32 - local_y;
+/// collects the input from the events since the last frame
+fn take_live_input_frame(world: &mut World) -> InputFrame {
+    let mouse_position = get_mouse_world_pos(world);
 
-            let mut local_x = local_x as f64 / inner_size.width as f64;
-            let mut local_y = local_y as f64 / inner_size.height as f64;
+    let lumen_program = world
+        .entities_and_components
+        .get_resource_mut::<LumenpyxProgram>()
+        .expect("failed to get lumen program");
 
-            local_x = local_x.clamp(0.0, 1.0);
-            local_x -= 0.5;
-            local_y = local_y.clamp(0.0, 1.0);
-            local_y -= 0.5;
+    let mut keys_down: Vec<KeyCode> = lumen_program.keys_down.iter().cloned().collect();
+    keys_down.sort_unstable();
+    let mut mouse_buttons_down: Vec<u32> =
+        lumen_program.mouse_buttons_down.iter().cloned().collect();
+    mouse_buttons_down.sort_unstable();
+
+    InputFrame {
+        keys_down,
+        mouse_buttons_down,
+        // if the mouse position can't be read, the last one is kept
+        mouse_position: mouse_position.unwrap_or(lumen_program.mouse_position),
+        // everything scrolled since the last frame belongs to this frame
+        mouse_wheel: std::mem::take(&mut lumen_program.pending_mouse_wheel),
+        delta_time: 0.0,
+    }
+}
 
-            // now we have the local x and y relative to the window
-            // but we need to convert it to the local x and y relative to the world
-            // so we need to get the camera and the camera's position
+/// get the position of the mouse in world space, None if the cursor hasn't been over the window yet
+fn get_mouse_world_pos(world: &World) -> Option<[f32; 2]> {
+    let lumen_program = world
+        .entities_and_components
+        .get_resource::<LumenpyxProgram>()
+        .expect("failed to get lumen program");
 
-            let camera_pos = get_camera_pos(&world.entities_and_components)
-                .expect("failed to get camera position");
+    // both are in physical pixels
+    let [cursor_x, cursor_y] = lumen_program.cursor_position?;
+    let inner_size = lumen_program.window.inner_size();
 
-            let resolution = lumen_program.get_dimensions();
+    // the y axis is flipped in lumen compared to winit
+    let local_x = cursor_x / inner_size.width as f64;
+    let local_y = (inner_size.height as f64 - cursor_y) / inner_size.height as f64;
 
-            // find the dimension that is cropped
-            let width_ratio = resolution[0] as f64 / inner_size.width as f64;
-            let height_ratio = resolution[1] as f64 / inner_size.height as f64;
+    let local_x = local_x.clamp(0.0, 1.0) - 0.5;
+    let local_y = local_y.clamp(0.0, 1.0) - 0.5;
 
-            if width_ratio > height_ratio {
-                // we are cropping the height
-                local_y *= width_ratio / height_ratio;
-            } else {
-                // we are cropping the width
-                local_x *= height_ratio / width_ratio;
-            }
+    // now we have the local x and y relative to the window
+    // but we need to convert it to the local x and y relative to the world
+    // so we need to get the camera and the camera's position
 
-            local_x *= resolution[0] as f64;
-            local_y *= resolution[1] as f64;
+    // scenes with only offscreen cameras have no mouse position
+    let camera_pos = get_camera_pos(&world.entities_and_components)?;
 
-            local_x += camera_pos[0] as f64;
-            local_y += camera_pos[1] as f64;
+    // the resolution is kept up to date by the resolution policy when the window is resized
+    let resolution = lumen_program.get_dimensions();
 
-            let input = world
-                .entities_and_components
-                .get_resource_mut::<Input>()
-                .expect("failed to get input system probably a version mismatch");
+    let [render_x, render_y] = window_to_render_position(
+        [local_x, local_y],
+        inner_size.into(),
+        resolution,
+        lumen_program.resolution_policy.is_integer_scaled(),
+    );
 
-            input.set_mouse_position(local_x as f32, local_y as f32);
-        }
-        Mouse::Error => {}
-    }
+    Some([
+        (render_x + camera_pos[0] as f64) as f32,
+        (render_y + camera_pos[1] as f64) as f32,
+    ])
 }
 
 impl LumenpyxProgram {
@@ -385,10 +789,57 @@ impl LumenpyxProgram {
             Self {
                 internal_program: program,
                 keys_down,
+                mouse_buttons_down: HashSet::new(),
+                pending_mouse_wheel: MouseWheelDelta::default(),
+                mouse_wheel: MouseWheelDelta::default(),
+                mouse_position: [0.0, 0.0],
+                cursor_position: None,
+                pixels_per_line: DEFAULT_PIXELS_PER_LINE,
+                virtual_resolution: resolution,
+                resolution_policy: ResolutionPolicy::default(),
+                settings: RenderSettings::default(),
+                post_effects: vec![],
+                palette_textures: HashMap::new(),
+                texture_targets: HashMap::new(),
+                next_texture_target: 0,
+                free_texture_targets: vec![],
+                blending_resolutions: vec![],
             },
             event_loop,
         )
     }
+
+    /// get how much the mouse wheel scrolled this frame, on both axes
+    /// every scroll event since the last frame is summed together
+    pub fn get_mouse_wheel(&self) -> MouseWheelDelta {
+        self.mouse_wheel
+    }
+
+    /// get the [x, y] scroll of this frame in lines, pixel scrolling is converted with the pixels per line
+    pub fn get_mouse_wheel_lines(&self) -> [f32; 2] {
+        self.mouse_wheel.in_lines(self.pixels_per_line)
+    }
+
+    /// get the [x, y] scroll of this frame in pixels, line scrolling is converted with the pixels per line
+    pub fn get_mouse_wheel_pixels(&self) -> [f32; 2] {
+        self.mouse_wheel.in_pixels(self.pixels_per_line)
+    }
+
+    /// set how many pixels one line of scrolling is worth
+    /// this is used to convert between line and pixel scrolling
+    pub fn set_pixels_per_line(&mut self, pixels_per_line: f32) {
+        assert!(pixels_per_line > 0.0, "pixels per line must be positive");
+        self.pixels_per_line = pixels_per_line;
+    }
+
+    pub fn get_pixels_per_line(&self) -> f32 {
+        self.pixels_per_line
+    }
+
+    /// get the mouse position of this frame in world space
+    pub fn get_mouse_position(&self) -> [f32; 2] {
+        self.mouse_position
+    }
 }
 
 impl Deref for LumenpyxProgram {
@@ -537,10 +988,23 @@ fn winit_input_to_abc_input(
     }
 }
 
+/// Where a camera draws to
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CameraTarget {
+    #[default]
+    MainWindow,
+    /// a window made with `LumenpyxEventLoop::add_window` that shows `WindowView::Camera`
+    Window(WindowId),
+    /// a texture made with `LumenpyxProgram::add_render_texture`, it's drawn before the main window
+    /// so sprites using it show this frame, the render is the size of the texture
+    Texture(TextureHandle),
+}
+
 #[derive(Clone, Copy)]
 pub struct Camera {
     lumen_camera: lumenpyx::Camera,
     is_active: bool,
+    target: CameraTarget,
 }
 
 impl Camera {
@@ -548,15 +1012,51 @@ impl Camera {
         Self {
             lumen_camera: lumenpyx::Camera::new([0.0, 0.0, 0.0]), // should be set by the transform in the ecs
             is_active: true,
+            target: CameraTarget::MainWindow,
         }
     }
+
+    /// make a camera that renders into a new texture, returns the camera and the texture
+    pub fn new_render_texture(
+        program: &mut LumenpyxProgram,
+        resolution: [u32; 2],
+    ) -> (Self, TextureHandle) {
+        let texture = program.add_render_texture(resolution);
+
+        (
+            Self::new().with_target(CameraTarget::Texture(texture)),
+            texture,
+        )
+    }
+
+    pub fn with_target(mut self, target: CameraTarget) -> Self {
+        self.target = target;
+        self
+    }
+
+    pub fn set_target(&mut self, target: CameraTarget) {
+        self.target = target;
+    }
+
+    pub fn get_target(&self) -> CameraTarget {
+        self.target
+    }
 }
 
 /// A component that prevents an entity and it's children from being rendered
 #[derive(Clone, Copy)]
 pub struct NotActive;
 
+/// get the position of the active camera that draws to the main window
 pub fn get_camera_pos(scene: &EntitiesAndComponents) -> Option<[f32; 3]> {
+    get_target_camera_pos(scene, CameraTarget::MainWindow)
+}
+
+/// get the position of the active camera that draws to a target
+pub fn get_target_camera_pos(
+    scene: &EntitiesAndComponents,
+    target: CameraTarget,
+) -> Option<[f32; 3]> {
     let camera_entities = scene
         .get_entities_with_component::<Camera>()
         .cloned()
@@ -575,7 +1075,7 @@ pub fn get_camera_pos(scene: &EntitiesAndComponents) -> Option<[f32; 3]> {
                 camera_component = (&**camera_component_ref).clone();
             }
 
-            if camera_component.is_active {
+            if camera_component.is_active && camera_component.target == target {
                 let camera_transform = get_transform(camera_entity, scene);
 
                 let camera_pos = [
@@ -601,6 +1101,20 @@ pub fn render(scene: &mut EntitiesAndComponents) {
     render_objects(scene, &camera);
 }
 
+/// whether the entity or one of its parents has `NotActive`
+fn is_not_active(entities_and_components: &EntitiesAndComponents, entity: Entity) -> bool {
+    let mut current_entity = Some(entity);
+    while let Some(entity) = current_entity {
+        let (not_active,) = entities_and_components.try_get_components::<(NotActive,)>(entity);
+        if not_active.is_some() {
+            return true;
+        }
+        current_entity = entities_and_components.get_parent(entity);
+    }
+
+    false
+}
+
 fn get_all_lights_on_object_mut(
     entities_and_components: &mut EntitiesAndComponents,
     entity: Entity,
@@ -608,6 +1122,11 @@ fn get_all_lights_on_object_mut(
     Vec<&mut dyn LightDrawable>,
     Option<&mut ABC_Game_Engine::Transform>,
 ) {
+    // lights light up the scene around them, but not active still hides them like it hides drawables
+    if is_not_active(entities_and_components, entity) {
+        return (vec![], None);
+    }
+
     let (point_light, spot_light, directional_light, transform) = entities_and_components
         .try_get_components_mut::<(
             PointLight,
@@ -667,105 +1186,366 @@ fn get_blend_object(
     total_time: f64,
 ) -> Option<Box<LumenBlendObject<'_>>> {
     let children = entities_and_components.get_children(entity);
+    let depth = get_layer_depth(entities_and_components, entity);
+
+    // copied out, so nothing on this entity is borrowed while the children are
+    let (blend_component, transform) = entities_and_components
+        .try_get_components::<(BlendComponent, ABC_Game_Engine::Transform)>(entity);
+
+    let (Some(blend_component), Some(transform)) = (blend_component, transform) else {
+        return None; // no blend mode or no transform
+    };
+    let blend_mode = blend_component.lumen_blend_mode;
+    let transform = *transform;
 
     let entities_and_components_ptr = entities_and_components as *mut EntitiesAndComponents;
 
-    // SAFETY: the transform and blend mode are dropped at the end of this block before they are mutably borrowed again
-    let (blend_mode, transform) = unsafe { &mut *entities_and_components_ptr }
-        .try_get_components::<(BlendComponent, ABC_Game_Engine::Transform)>(entity);
+    // SAFETY: the layers only borrow the drawables of the children
+    let layers = get_child_layers(
+        unsafe { &mut *entities_and_components_ptr },
+        children,
+        total_time,
+    );
 
-    let transform_clone = if let Some(ref transform) = transform {
-        Some(transform)
-    } else {
-        None
+    // we need at least 2 drawables to blend
+    let diagnostic = (layers.len() < 2).then_some(BlendDiagnostic::TooFewLayers {
+        found: layers.len(),
+    });
+
+    // SAFETY: the blend component isn't one of the drawables the layers borrow, and this borrow ends right away
+    if let Some(blend_component) =
+        unsafe { &mut *entities_and_components_ptr }.try_get_component_mut::<BlendComponent>(entity)
+    {
+        blend_component.diagnostic = diagnostic;
+    }
+
+    if diagnostic.is_some() {
+        return None;
+    }
+
+    let mut blend_object = LumenBlendObject::new(layers, blend_mode, depth);
+    blend_object.set_transform(abc_transform_to_lumen_transform(transform));
+
+    Some(Box::new(blend_object))
+}
+
+fn get_mask_object(
+    entities_and_components: &mut EntitiesAndComponents,
+    entity: Entity,
+    total_time: f64,
+) -> Option<Box<LumenMaskObject<'_>>> {
+    let (mask_component, blend_component) =
+        entities_and_components.try_get_components::<(MaskComponent, BlendComponent)>(entity);
+    let source = mask_component?.source;
+
+    let mut diagnostic = None;
+    let mut mask_entity = None;
+    if let MaskSource::Entity(source_entity) = source {
+        diagnostic = get_mask_entity_diagnostic(entities_and_components, entity, source_entity);
+        mask_entity = Some(source_entity);
+    }
+
+    // the blend already borrows the children
+    if blend_component.is_some() {
+        diagnostic = Some(MaskDiagnostic::HasBlend);
+    }
+
+    let children = entities_and_components.get_children(entity);
+    let depth = get_layer_depth(entities_and_components, entity);
+
+    // the mask entity can be anywhere, so it's placed by where it is compared to this entity
+    let mask_transform = mask_entity.map(|mask_entity| {
+        let entity_transform = get_transform(entity, entities_and_components);
+        let mut mask_transform = get_transform(mask_entity, entities_and_components);
+        mask_transform.x -= entity_transform.x;
+        mask_transform.y -= entity_transform.y;
+        abc_transform_to_lumen_transform(mask_transform)
+    });
+
+    let entities_and_components_ptr = entities_and_components as *mut EntitiesAndComponents;
+
+    // SAFETY: the mask component and transform are on this entity, the drawables borrowed below are on its children and the mask entity
+    let (mask_component, transform) = unsafe { &mut *entities_and_components_ptr }
+        .try_get_components_mut::<(MaskComponent, ABC_Game_Engine::Transform)>(entity);
+
+    let (Some(mask_component), Some(transform)) = (mask_component, transform) else {
+        return None; // no mask or no transform
     };
 
-    let mut final_drawable = None;
-
-    match (blend_mode, transform_clone) {
-        (Some(blend_mode), Some(transform)) => {
-            let mut drawables_in_children = vec![];
-
-            // collect the drawables in the children
-            for child in children {
-                // SAFETY: This doesn't intersect with our only other borrow which is of transform and blend mode
-                let (drawables, transform) = get_all_drawables_on_object_mut(
-                    unsafe { &mut *entities_and_components_ptr },
-                    child,
-                    total_time,
-                    true,
-                );
-
-                // only the amount of drawables is checked
-                if drawables.len() != 0 {
-                    drawables_in_children.push(EntityDepthItem {
-                        entity: child,
-                        transform: transform.cloned().unwrap_or_default(),
-                    });
-                    if drawables_in_children.len() >= 2 {
-                        // we only need 2 drawables
-                        break;
-                    }
-                }
-            }
-            // by now all the drawables in the above loop are dropped
+    if diagnostic.is_some() {
+        mask_component.diagnostic = diagnostic;
+        return None;
+    }
 
-            // sort the drawables so we can blend the first two
-            drawables_in_children.sort();
+    // SAFETY: the mask entity isn't a layer of any blend or mask, or this entity or its parents, and no other mask uses it
+    let mut mask_layers = vec![];
+    if let (Some(mask_entity), Some(mask_transform)) = (mask_entity, mask_transform) {
+        let (drawables, _) = get_all_drawables_on_object_mut(
+            unsafe { &mut *entities_and_components_ptr },
+            mask_entity,
+            total_time,
+            true,
+        );
 
-            if drawables_in_children.len() < 2 {
-                // TODO: when we have a logger, log this as a warning
-                // we need at least 2 drawables to blend
-            } else {
-                // this would be a bug in the ecs if this happened but, it would be a huge safety issue so we check
-                assert!(drawables_in_children[0] != drawables_in_children[1]);
-
-                let drawable_entity_1 = drawables_in_children.remove(0).entity;
-                let drawable_entity_2 = drawables_in_children.remove(0).entity;
-
-                // SAFETY: we checked that the entities are different
-                // SAFETY: we also know this is not intersecting with the transform and blend mode borrow
-                let (mut drawables_on_1, transform_1) = get_all_drawables_on_object_mut(
-                    unsafe { &mut *entities_and_components_ptr },
-                    drawable_entity_1,
-                    total_time,
-                    true,
-                );
-
-                // SAFETY: we checked that the entities are different
-                // SAFETY: we also know this is not intersecting with the transform and blend mode borrow
-                let (mut drawables_on_2, transform_2) = get_all_drawables_on_object_mut(
-                    unsafe { &mut *entities_and_components_ptr },
-                    drawable_entity_2,
-                    total_time,
-                    true,
-                );
-
-                let mut drawable_1 = drawables_on_1.remove(0);
-                let mut drawable_2 = drawables_on_2.remove(0);
-
-                // we set the position of the children rather than the parent
-                // it's a bit weird, but i tried the other way and it didn't work so this is fine
-                if let Some(transform_1) = transform_1 {
-                    drawable_1.set_transform(abc_transform_to_lumen_transform(*transform_1));
-                }
+        for mut drawable in drawables {
+            drawable.set_transform(mask_transform);
+            mask_layers.push(drawable);
+        }
 
-                if let Some(transform_2) = transform_2 {
-                    drawable_2.set_transform(abc_transform_to_lumen_transform(*transform_2));
-                }
+        if mask_layers.is_empty() {
+            mask_component.diagnostic = Some(MaskDiagnostic::EmptyMask);
+            return None;
+        }
+    }
+
+    // SAFETY: this doesn't intersect with the mask component and transform borrow
+    let layers = get_child_layers(
+        unsafe { &mut *entities_and_components_ptr },
+        children,
+        total_time,
+    );
+
+    if layers.is_empty() {
+        mask_component.diagnostic = Some(MaskDiagnostic::NothingToClip);
+        return None;
+    }
+    mask_component.diagnostic = None;
+
+    let mut mask_object = LumenMaskObject::new(layers, mask_layers, *mask_component, depth);
+    mask_object.set_transform(abc_transform_to_lumen_transform(*transform));
+
+    Some(Box::new(mask_object))
+}
+
+/// why an entity can't be the mask of a mask component, None if it can
+fn get_mask_entity_diagnostic(
+    entities_and_components: &EntitiesAndComponents,
+    entity: Entity,
+    mask_entity: Entity,
+) -> Option<MaskDiagnostic> {
+    // the mask can't be drawn as part of itself
+    let mut current_entity = Some(entity);
+    while let Some(ancestor) = current_entity {
+        if ancestor == mask_entity {
+            return Some(MaskDiagnostic::MaskIsParent);
+        }
+        current_entity = entities_and_components.get_parent(ancestor);
+    }
+
+    // only the first mask component using an entity gets it
+    let is_shared = entities_and_components
+        .get_entities_with_component::<MaskComponent>()
+        .filter(|other| **other < entity)
+        .any(|other| {
+            entities_and_components
+                .try_get_component::<MaskComponent>(*other)
+                .is_some_and(|mask| mask.source == MaskSource::Entity(mask_entity))
+        });
+
+    is_shared.then_some(MaskDiagnostic::SharedMask)
+}
 
-                let mut new_blend_obj =
-                    LumenBlendObject::new(drawable_1, drawable_2, blend_mode.lumen_blend_mode);
+/// whether an entity is the mask of a mask component, then it's only drawn as part of the mask
+fn is_mask_entity(entities_and_components: &EntitiesAndComponents, entity: Entity) -> bool {
+    entities_and_components
+        .get_entities_with_component::<MaskComponent>()
+        .any(|mask_entity| {
+            entities_and_components
+                .try_get_component::<MaskComponent>(*mask_entity)
+                .is_some_and(|mask| mask.source == MaskSource::Entity(entity))
+        })
+}
 
-                new_blend_obj.set_transform(abc_transform_to_lumen_transform(**transform));
+/// the drawables of the children of a blend or mask, as layers ordered by z
+/// children that are the mask of a mask component are left out, they are only drawn by that mask
+fn get_child_layers(
+    entities_and_components: &mut EntitiesAndComponents,
+    children: Vec<Entity>,
+    total_time: f64,
+) -> Vec<OwnedOrMutableDrawable<'_>> {
+    // the layers are drawn in the same order as everything else, bottom to top
+    let mut ordered_children = children
+        .into_iter()
+        .filter(|child| !is_mask_entity(entities_and_components, *child))
+        .map(|child| EntityDepthItem {
+            entity: child,
+            transform: entities_and_components
+                .try_get_component::<ABC_Game_Engine::Transform>(child)
+                .map(|transform| **transform)
+                .unwrap_or_default(),
+        })
+        .collect::<Vec<_>>();
+    ordered_children.sort();
 
-                final_drawable = Some(Box::new(new_blend_obj));
+    let entities_and_components_ptr = entities_and_components as *mut EntitiesAndComponents;
+
+    let mut layers = vec![];
+    for child in ordered_children {
+        // SAFETY: every child is a different entity, so the drawables on them don't overlap
+        let (drawables, child_transform) = get_all_drawables_on_object_mut(
+            unsafe { &mut *entities_and_components_ptr },
+            child.entity,
+            total_time,
+            true,
+        );
+
+        // we set the position of the children rather than the parent
+        // it's a bit weird, but i tried the other way and it didn't work so this is fine
+        let child_transform =
+            child_transform.map(|transform| abc_transform_to_lumen_transform(*transform));
+
+        for mut drawable in drawables {
+            if let Some(child_transform) = child_transform {
+                drawable.set_transform(child_transform);
             }
+            layers.push(drawable);
+        }
+    }
+
+    layers
+}
+
+fn get_group_object(
+    entities_and_components: &mut EntitiesAndComponents,
+    entity: Entity,
+    total_time: f64,
+) -> Option<Box<LumenGroupObject<'_>>> {
+    let (group, transform, not_active) =
+        entities_and_components
+            .try_get_components::<(OpacityGroup, ABC_Game_Engine::Transform, NotActive)>(entity);
+
+    let (Some(group), Some(transform)) = (group, transform) else {
+        return None; // no group or no transform
+    };
+    if not_active.is_some() {
+        return None;
+    }
+    let group = *group;
+    let transform = *transform;
+
+    let depth = get_layer_depth(entities_and_components, entity);
+
+    // everything in the group is placed by where it is compared to the group
+    let group_transform = get_transform(entity, entities_and_components);
+    let mut members = vec![];
+    collect_group_members(
+        entities_and_components,
+        entity,
+        &group_transform,
+        &mut members,
+    );
+    members.sort();
+
+    let entities_and_components_ptr = entities_and_components as *mut EntitiesAndComponents;
+
+    let mut layers = vec![];
+    for member in members {
+        // SAFETY: every member is a different entity, and the members inside blends and masks aren't members themselves
+        let (drawables, _) = if member.entity == entity {
+            get_ungrouped_drawables_on_object_mut(
+                unsafe { &mut *entities_and_components_ptr },
+                entity,
+                total_time,
+            )
+        } else {
+            get_all_drawables_on_object_mut(
+                unsafe { &mut *entities_and_components_ptr },
+                member.entity,
+                total_time,
+                true,
+            )
+        };
+
+        let member_transform = abc_transform_to_lumen_transform(member.transform);
+        for mut drawable in drawables {
+            drawable.set_transform(member_transform);
+            layers.push(drawable);
+        }
+    }
+
+    if layers.is_empty() {
+        return None;
+    }
+
+    let mut group_object = LumenGroupObject::new(layers, group, depth);
+    group_object.set_transform(abc_transform_to_lumen_transform(transform));
+
+    Some(Box::new(group_object))
+}
+
+/// an entity and its descendants, with where they are compared to the group
+/// the children of blends, masks and other groups are left to them
+fn collect_group_members(
+    entities_and_components: &EntitiesAndComponents,
+    entity: Entity,
+    group_transform: &ABC_Game_Engine::Transform,
+    out_list: &mut Vec<EntityDepthItem>,
+) {
+    let mut transform = get_transform(entity, entities_and_components);
+    transform.x -= group_transform.x;
+    transform.y -= group_transform.y;
+    out_list.push(EntityDepthItem { entity, transform });
+
+    let (blend, mask) =
+        entities_and_components.try_get_components::<(BlendComponent, MaskComponent)>(entity);
+    if blend.is_some() || mask.is_some() {
+        return;
+    }
+
+    for child in entities_and_components.get_children(entity) {
+        let (group, not_active) =
+            entities_and_components.try_get_components::<(OpacityGroup, NotActive)>(child);
+        if not_active.is_some() || is_mask_entity(entities_and_components, child) {
+            continue;
+        }
+
+        if group.is_some() {
+            let mut transform = get_transform(child, entities_and_components);
+            transform.x -= group_transform.x;
+            transform.y -= group_transform.y;
+            out_list.push(EntityDepthItem {
+                entity: child,
+                transform,
+            });
+        } else {
+            collect_group_members(entities_and_components, child, group_transform, out_list);
+        }
+    }
+}
+
+/// whether an entity is a blend or mask that draws its children, without building the blend or mask
+/// this only looks at the components and the number of children, so it's cheap enough to check every parent
+fn draws_children(entities_and_components: &EntitiesAndComponents, entity: Entity) -> bool {
+    let (blend, mask, transform) =
+        entities_and_components
+            .try_get_components::<(BlendComponent, MaskComponent, ABC_Game_Engine::Transform)>(
+                entity,
+            );
+    if transform.is_none() {
+        return false;
+    }
+
+    let child_count = entities_and_components.get_children(entity).len();
+
+    (blend.is_some() && child_count >= 2) || (mask.is_some() && child_count >= 1)
+}
+
+/// how many of an entity's ancestors are blends, masks or opacity groups
+fn get_layer_depth(entities_and_components: &EntitiesAndComponents, entity: Entity) -> usize {
+    let mut depth = 0;
+    let mut current_entity = entity;
+
+    while let Some(parent) = entities_and_components.get_parent(current_entity) {
+        let (blend, mask, group) =
+            entities_and_components
+                .try_get_components::<(BlendComponent, MaskComponent, OpacityGroup)>(parent);
+        if blend.is_some() || mask.is_some() || group.is_some() {
+            depth += 1;
         }
-        _ => (), // no blend mode or no children
+        current_entity = parent;
     }
 
-    final_drawable
+    depth
 }
 
 fn get_all_drawables_on_object_mut<'a>(
@@ -779,24 +1559,55 @@ fn get_all_drawables_on_object_mut<'a>(
 ) {
     // needs to be benchmarked, but i can't think of a better way to do this...
     if !ignore_precheck {
+        if is_mask_entity(entities_and_components, entity) {
+            return (vec![], None);
+        }
+
         let mut current_entity = entity;
         while let Some(parent) = entities_and_components.get_parent(current_entity) {
             current_entity = parent;
 
-            let not_active = entities_and_components.try_get_component::<NotActive>(current_entity);
-            if not_active.is_some() {
+            let (not_active, group) = entities_and_components
+                .try_get_components::<(NotActive, OpacityGroup)>(current_entity);
+            // the group draws everything below it
+            if not_active.is_some() || group.is_some() {
                 return (vec![], None);
             }
-            let blend_object =
-                get_blend_object(entities_and_components, current_entity, total_time);
-
             // remember that we are looking at the parent so we don't draw that right now, when the blend object is the original entity it will be drawn
-            if blend_object.is_some() {
+            // the same goes for masks
+            if draws_children(entities_and_components, current_entity) {
                 return (vec![], None);
             }
         }
     }
 
+    let entities_and_components_ptr = entities_and_components as *mut EntitiesAndComponents;
+
+    // SAFETY: the group only borrows the drawables of this entity and its descendants, not the transform
+    let group_object = get_group_object(
+        unsafe { &mut *entities_and_components_ptr },
+        entity,
+        total_time,
+    );
+
+    if let Some(group_object) = group_object {
+        let (transform,) =
+            entities_and_components.try_get_components_mut::<(ABC_Game_Engine::Transform,)>(entity);
+        return (vec![OwnedOrMutableDrawable::Owned(group_object)], transform);
+    }
+
+    get_ungrouped_drawables_on_object_mut(entities_and_components, entity, total_time)
+}
+
+/// the drawables of an entity without its opacity group, if it has one
+fn get_ungrouped_drawables_on_object_mut(
+    entities_and_components: &mut EntitiesAndComponents,
+    entity: Entity,
+    total_time: f64,
+) -> (
+    Vec<OwnedOrMutableDrawable<'_>>,
+    Option<&mut ABC_Game_Engine::Transform>,
+) {
     let mut final_drawables = vec![];
 
     let entities_and_components_ptr = entities_and_components as *mut EntitiesAndComponents;
@@ -813,6 +1624,17 @@ fn get_all_drawables_on_object_mut<'a>(
         final_drawables.push(OwnedOrMutableDrawable::Owned(blend_object));
     }
 
+    // SAFETY: the same as the blend object, this only borrows the children and the mask entity
+    let mask_object = get_mask_object(
+        unsafe { &mut *entities_and_components_ptr },
+        entity,
+        total_time,
+    );
+
+    if let Some(mask_object) = mask_object {
+        final_drawables.push(OwnedOrMutableDrawable::Owned(mask_object));
+    }
+
     let (
         circle,
         rectangle,
@@ -822,6 +1644,16 @@ fn get_all_drawables_on_object_mut<'a>(
         cylinder,
         animation_state_machine,
         text_box,
+        line,
+        polyline,
+        polygon,
+        ellipse,
+        rounded_rectangle,
+        ring,
+        arc,
+        tilemap,
+        nine_slice_sprite,
+        atlas_sprite,
         transform,
         not_active,
     ) = entities_and_components.try_get_components_mut::<(
@@ -833,6 +1665,16 @@ fn get_all_drawables_on_object_mut<'a>(
         Cylinder,
         AnimationStateMachine,
         TextBox,
+        Line,
+        Polyline,
+        Polygon,
+        Ellipse,
+        RoundedRectangle,
+        Ring,
+        ArcShape,
+        Tilemap,
+        NineSliceSprite,
+        AtlasSprite,
         ABC_Game_Engine::Transform,
         NotActive,
     )>(entity);
@@ -843,39 +1685,59 @@ fn get_all_drawables_on_object_mut<'a>(
 
     // this is abhorrent, but I can't think of any better way to do this
     let mut mut_drawables = vec![];
-    match circle {
-        Some(circle) => mut_drawables.push(circle as &mut dyn Drawable),
-        None => (),
+    if let Some(circle) = circle {
+        mut_drawables.push(circle as &mut dyn Drawable);
     }
-    match rectangle {
-        Some(rectangle) => mut_drawables.push(rectangle as &mut dyn Drawable),
-        None => (),
+    if let Some(rectangle) = rectangle {
+        mut_drawables.push(rectangle as &mut dyn Drawable);
     }
-    match sprite {
-        Some(sprite) => mut_drawables.push(sprite as &mut dyn Drawable),
-        None => (),
+    if let Some(sprite) = sprite {
+        mut_drawables.push(sprite as &mut dyn Drawable);
     }
-    match sphere {
-        Some(sphere) => mut_drawables.push(sphere as &mut dyn Drawable),
-        None => (),
+    if let Some(sphere) = sphere {
+        mut_drawables.push(sphere as &mut dyn Drawable);
     }
-    match animation {
-        Some(animation) => mut_drawables.push(animation as &mut dyn Drawable),
-        None => (),
+    if let Some(animation) = animation {
+        mut_drawables.push(animation as &mut dyn Drawable);
     }
-    match cylinder {
-        Some(cylinder) => mut_drawables.push(cylinder as &mut dyn Drawable),
-        None => (),
+    if let Some(cylinder) = cylinder {
+        mut_drawables.push(cylinder as &mut dyn Drawable);
     }
-    match animation_state_machine {
-        Some(animation_state_machine) => {
-            mut_drawables.push(animation_state_machine as &mut dyn Drawable)
-        }
-        None => (),
+    if let Some(animation_state_machine) = animation_state_machine {
+        mut_drawables.push(animation_state_machine as &mut dyn Drawable);
     }
-    match text_box {
-        Some(text_box) => mut_drawables.push(text_box as &mut dyn Drawable),
-        None => (),
+    if let Some(text_box) = text_box {
+        mut_drawables.push(text_box as &mut dyn Drawable);
+    }
+    if let Some(line) = line {
+        mut_drawables.push(line as &mut dyn Drawable);
+    }
+    if let Some(polyline) = polyline {
+        mut_drawables.push(polyline as &mut dyn Drawable);
+    }
+    if let Some(polygon) = polygon {
+        mut_drawables.push(polygon as &mut dyn Drawable);
+    }
+    if let Some(ellipse) = ellipse {
+        mut_drawables.push(ellipse as &mut dyn Drawable);
+    }
+    if let Some(rounded_rectangle) = rounded_rectangle {
+        mut_drawables.push(rounded_rectangle as &mut dyn Drawable);
+    }
+    if let Some(ring) = ring {
+        mut_drawables.push(ring as &mut dyn Drawable);
+    }
+    if let Some(arc) = arc {
+        mut_drawables.push(arc as &mut dyn Drawable);
+    }
+    if let Some(tilemap) = tilemap {
+        mut_drawables.push(tilemap as &mut dyn Drawable);
+    }
+    if let Some(nine_slice_sprite) = nine_slice_sprite {
+        mut_drawables.push(nine_slice_sprite as &mut dyn Drawable);
+    }
+    if let Some(atlas_sprite) = atlas_sprite {
+        mut_drawables.push(atlas_sprite as &mut dyn Drawable);
     }
 
     for drawable in mut_drawables {
@@ -937,6 +1799,17 @@ fn get_all_entities_with_drawables(
         animation_state_machine.set_total_time_f64(total_time);
     }
 
+    let entities_with_tilemap = entities_and_components
+        .get_entities_with_component::<Tilemap>()
+        .cloned()
+        .collect::<Vec<Entity>>();
+
+    for entity in entities_with_tilemap.iter() {
+        let (tilemap,) = entities_and_components.get_components_mut::<(Tilemap,)>(*entity);
+
+        tilemap.set_total_time(total_time);
+    }
+
     let entities_with_cylinder = entities_and_components
         .get_entities_with_component::<Cylinder>()
         .cloned()
@@ -947,11 +1820,66 @@ fn get_all_entities_with_drawables(
         .cloned()
         .collect::<Vec<Entity>>();
 
+    let entities_with_mask_component = entities_and_components
+        .get_entities_with_component::<MaskComponent>()
+        .cloned()
+        .collect::<Vec<Entity>>();
+
+    let entities_with_opacity_group = entities_and_components
+        .get_entities_with_component::<OpacityGroup>()
+        .cloned()
+        .collect::<Vec<Entity>>();
+
     let entities_with_text_box = entities_and_components
         .get_entities_with_component::<TextBox>()
         .cloned()
         .collect::<Vec<Entity>>();
 
+    let entities_with_line = entities_and_components
+        .get_entities_with_component::<Line>()
+        .cloned()
+        .collect::<Vec<Entity>>();
+
+    let entities_with_polyline = entities_and_components
+        .get_entities_with_component::<Polyline>()
+        .cloned()
+        .collect::<Vec<Entity>>();
+
+    let entities_with_polygon = entities_and_components
+        .get_entities_with_component::<Polygon>()
+        .cloned()
+        .collect::<Vec<Entity>>();
+
+    let entities_with_ellipse = entities_and_components
+        .get_entities_with_component::<Ellipse>()
+        .cloned()
+        .collect::<Vec<Entity>>();
+
+    let entities_with_rounded_rectangle = entities_and_components
+        .get_entities_with_component::<RoundedRectangle>()
+        .cloned()
+        .collect::<Vec<Entity>>();
+
+    let entities_with_ring = entities_and_components
+        .get_entities_with_component::<Ring>()
+        .cloned()
+        .collect::<Vec<Entity>>();
+
+    let entities_with_arc = entities_and_components
+        .get_entities_with_component::<ArcShape>()
+        .cloned()
+        .collect::<Vec<Entity>>();
+
+    let entities_with_nine_slice_sprite = entities_and_components
+        .get_entities_with_component::<NineSliceSprite>()
+        .cloned()
+        .collect::<Vec<Entity>>();
+
+    let entities_with_atlas_sprite = entities_and_components
+        .get_entities_with_component::<AtlasSprite>()
+        .cloned()
+        .collect::<Vec<Entity>>();
+
     // lights are counted as drawables in this case
 
     let entities_with_point_light = entities_and_components
@@ -977,7 +1905,19 @@ fn get_all_entities_with_drawables(
     entities.extend(entities_with_cylinder);
     entities.extend(entities_with_animation_state_machine);
     entities.extend(entities_with_blend_component);
+    entities.extend(entities_with_mask_component);
+    entities.extend(entities_with_opacity_group);
     entities.extend(entities_with_text_box);
+    entities.extend(entities_with_line);
+    entities.extend(entities_with_polyline);
+    entities.extend(entities_with_polygon);
+    entities.extend(entities_with_ellipse);
+    entities.extend(entities_with_rounded_rectangle);
+    entities.extend(entities_with_ring);
+    entities.extend(entities_with_arc);
+    entities.extend(entities_with_tilemap);
+    entities.extend(entities_with_nine_slice_sprite);
+    entities.extend(entities_with_atlas_sprite);
     entities.extend(entities_with_point_light);
     entities.extend(entities_with_area_light);
     entities.extend(entities_with_directional_light);
@@ -993,11 +1933,17 @@ fn render_objects(entities_and_components: &mut EntitiesAndComponents, camera: &
     let mut entity_depth_array = vec![];
 
     let total_time = entities_and_components
-        .get_resource::<DeltaTime>()
-        .expect("failed to get delta time")
+        .get_resource::<GameTime>()
+        .expect("failed to get game time")
         .get_total_time();
 
     collect_renderable_entities(entities_and_components, &mut entity_depth_array, total_time);
+    let texture_camera_passes = get_texture_camera_passes(entities_and_components);
+    entities_and_components
+        .get_resource_mut::<LumenpyxProgram>()
+        .expect("failed to get lumen program")
+        .free_unused_texture_targets(&texture_camera_passes);
+    let window_camera_passes = get_camera_passes(entities_and_components);
 
     entity_depth_array.sort();
     let mut sprites = vec![];
@@ -1044,8 +1990,96 @@ fn render_objects(entities_and_components: &mut EntitiesAndComponents, camera: &
     let lumen_program = entities_and_components
         .get_resource_mut::<LumenpyxProgram>()
         .expect("failed to get lumen program");
+    lumen_program.blending_resolutions.clear();
+
+    // render textures first, so the sprites using them show this frame
+    for camera_pass in texture_camera_passes {
+        draw_scene(
+            lumen_program,
+            &lights_in_scene,
+            &sprite_borrows,
+            &camera_pass.camera,
+            &camera_pass.target,
+            camera_pass.resolution,
+        );
+
+        if let Some(output) = camera_pass.output {
+            copy_target_to_texture(lumen_program, &camera_pass.target, &output);
+        }
+    }
+
+    // only the middle of the render is shown, and it's never smaller than what is shown
+    let render_resolution = lumen_program.get_render_resolution();
+    draw_scene(
+        lumen_program,
+        &lights_in_scene,
+        &sprite_borrows,
+        camera,
+        MAIN_TARGET,
+        render_resolution,
+    );
+    apply_post_effects(lumen_program, MAIN_TARGET, total_time as f32);
+    present_target(lumen_program, MAIN_TARGET);
+
+    // the secondary windows are drawn after the main window so it isn't waiting on them
+    for camera_pass in window_camera_passes {
+        draw_scene(
+            lumen_program,
+            &lights_in_scene,
+            &sprite_borrows,
+            &camera_pass.camera,
+            &camera_pass.target,
+            camera_pass.resolution,
+        );
+    }
+}
+
+/// the active cameras that render into textures
+fn get_texture_camera_passes(scene: &mut EntitiesAndComponents) -> Vec<CameraPass> {
+    let camera_entities = scene
+        .get_entities_with_component::<Camera>()
+        .cloned()
+        .collect::<Vec<Entity>>();
+
+    let mut camera_passes = vec![];
+    for camera_entity in camera_entities {
+        let camera = **scene
+            .try_get_component::<Camera>(camera_entity)
+            .expect("renderer could not find a camera");
+
+        let CameraTarget::Texture(texture) = camera.target else {
+            continue;
+        };
+
+        if !camera.is_active {
+            continue;
+        }
+
+        let camera_transform = get_transform(camera_entity, scene);
+
+        let lumen_program = scene
+            .get_resource_mut::<LumenpyxProgram>()
+            .expect("failed to get lumen program");
 
-    draw_all(lights_in_scene, sprite_borrows, lumen_program, camera)
+        // the texture might have been removed
+        let Some(output) = lumen_program.get_texture_from_handle(&texture) else {
+            continue;
+        };
+        let resolution = [output.width(), output.height()];
+
+        camera_passes.push(CameraPass {
+            target: lumen_program.get_texture_target(texture),
+            resolution,
+            camera: lumenpyx::Camera::new([
+                camera_transform.x as f32,
+                camera_transform.y as f32,
+                camera_transform.z as f32,
+            ]),
+            output: Some(texture),
+        });
+    }
+
+    camera_passes
 }
 
 /// A recursive function that collects all renderable entities in the scene
@@ -1108,6 +2142,25 @@ fn abc_transform_to_lumen_transform(transform: ABC_Game_Engine::Transform) -> Tr
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_lights_skip_not_active() {
+        let mut entities_and_components = EntitiesAndComponents::new();
+        let light = || PointLight::new([1.0, 1.0, 1.0], 1.0, 0.1);
+
+        let active = entities_and_components.add_entity_with((light(),));
+        let hidden = entities_and_components.add_entity_with((light(), NotActive));
+        let hidden_parent = entities_and_components.add_entity_with((NotActive,));
+        let hidden_child = entities_and_components.add_entity_with((light(),));
+        entities_and_components.set_parent(hidden_child, hidden_parent);
+
+        let (lights, _) = get_all_lights_on_object_mut(&mut entities_and_components, active);
+        assert_eq!(lights.len(), 1);
+        let (lights, _) = get_all_lights_on_object_mut(&mut entities_and_components, hidden);
+        assert!(lights.is_empty());
+        let (lights, _) = get_all_lights_on_object_mut(&mut entities_and_components, hidden_child);
+        assert!(lights.is_empty());
+    }
+
     #[test]
     fn test_abc_transform_to_lumen_transform() {
         let abc_transform = ABC_Game_Engine::Transform {
@@ -0,0 +1,479 @@
+//! Nine slice sprites, a sprite cut into a 3x3 grid by its borders so it can be any size
+//! the corners are always drawn at their own size, the edges and center are stretched or tiled to fill the rest
+//! which is what buttons and dialog boxes need, scaling a sprite with the transform stretches its borders too
+
+use std::cell::RefCell;
+
+use glium::framebuffer::SimpleFrameBuffer;
+use glium::uniforms::Sampler;
+use glium::Surface;
+use lumenpyx::drawable_object::Drawable;
+use lumenpyx::primitives::{NormalInput, TextureInput};
+use lumenpyx::{LumenpyxProgram, TextureHandle, Transform};
+
+use crate::blending::ALPHA_BLEND;
+use crate::pipeline::BUFFER_BEHAVIOR;
+use crate::tilemap::{load_tilemap_shader, TileVertex};
+
+/// How far in from each side of the texture the borders are, in pixels
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Insets {
+    pub left: u32,
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+}
+
+impl Insets {
+    pub fn new(left: u32, top: u32, right: u32, bottom: u32) -> Self {
+        Self {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    /// the same border on every side
+    pub fn uniform(inset: u32) -> Self {
+        Self::new(inset, inset, inset, inset)
+    }
+}
+
+/// How the edges or the center fill the space between the corners
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SliceMode {
+    #[default]
+    Stretch,
+    /// repeated at the texture's size, the last copy is cut off
+    Tile,
+}
+
+/// A sprite that can be resized without distorting its corners, centered on the transform like a `Sprite`
+pub struct NineSliceSprite {
+    albedo: TextureHandle,
+    height: TextureHandle,
+    roughness: TextureHandle,
+    normal: TextureHandle,
+    texture_size: [u32; 2],
+    insets: Insets,
+    /// in pixels
+    size: [f32; 2],
+    edge_mode: SliceMode,
+    center_mode: SliceMode,
+    /// None when something changed since it was last drawn
+    vertex_buffer: RefCell<Option<glium::VertexBuffer<TileVertex>>>,
+    shadow_strength: f32,
+    transform: Transform,
+}
+
+impl NineSliceSprite {
+    /// the textures are loaded like a `Sprite`'s, size is the width and height it's drawn at in pixels
+    pub fn new(
+        albedo: TextureInput,
+        height: TextureInput,
+        roughness: TextureInput,
+        normal: NormalInput,
+        insets: Insets,
+        size: [f32; 2],
+        program: &mut LumenpyxProgram,
+    ) -> Self {
+        let (_, albedo, height, roughness, normal) = lumenpyx::primitives::Sprite::new(
+            albedo,
+            height,
+            roughness,
+            normal,
+            program,
+            Transform::default(),
+        );
+
+        let texture = program
+            .get_texture_from_handle(&albedo)
+            .expect("failed to get nine slice texture");
+        let texture_size = [
+            texture.get_width(),
+            texture
+                .get_height()
+                .expect("failed to get height of nine slice texture"),
+        ];
+
+        Self {
+            albedo,
+            height,
+            roughness,
+            normal,
+            texture_size,
+            insets,
+            size,
+            edge_mode: SliceMode::Stretch,
+            center_mode: SliceMode::Stretch,
+            vertex_buffer: RefCell::new(None),
+            shadow_strength: 0.5,
+            transform: Transform::default(),
+        }
+    }
+
+    pub fn with_edge_mode(mut self, edge_mode: SliceMode) -> Self {
+        self.set_edge_mode(edge_mode);
+        self
+    }
+
+    pub fn with_center_mode(mut self, center_mode: SliceMode) -> Self {
+        self.set_center_mode(center_mode);
+        self
+    }
+
+    pub fn set_size(&mut self, width: f32, height: f32) {
+        self.size = [width, height];
+        *self.vertex_buffer.get_mut() = None;
+    }
+
+    /// the width and height in pixels
+    pub fn get_size(&self) -> [f32; 2] {
+        self.size
+    }
+
+    pub fn set_insets(&mut self, insets: Insets) {
+        self.insets = insets;
+        *self.vertex_buffer.get_mut() = None;
+    }
+
+    pub fn get_insets(&self) -> Insets {
+        self.insets
+    }
+
+    pub fn set_edge_mode(&mut self, edge_mode: SliceMode) {
+        self.edge_mode = edge_mode;
+        *self.vertex_buffer.get_mut() = None;
+    }
+
+    pub fn get_edge_mode(&self) -> SliceMode {
+        self.edge_mode
+    }
+
+    pub fn set_center_mode(&mut self, center_mode: SliceMode) {
+        self.center_mode = center_mode;
+        *self.vertex_buffer.get_mut() = None;
+    }
+
+    pub fn get_center_mode(&self) -> SliceMode {
+        self.center_mode
+    }
+
+    pub fn set_shadow_strength(&mut self, shadow_strength: f32) {
+        self.shadow_strength = shadow_strength;
+    }
+
+    /// the albedo, height, roughness and normal textures in that order
+    pub fn get_textures(&self) -> [TextureHandle; 4] {
+        [self.albedo, self.height, self.roughness, self.normal]
+    }
+
+    pub fn free_all_textures(&self, program: &mut LumenpyxProgram) {
+        for texture in self.get_textures() {
+            program.remove_texture(&texture);
+        }
+    }
+
+    fn draw(
+        &self,
+        program: &LumenpyxProgram,
+        transform: &Transform,
+        framebuffer: &mut SimpleFrameBuffer,
+        texture: TextureHandle,
+    ) {
+        let texture = program
+            .get_texture_from_handle(&texture)
+            .expect("failed to get nine slice texture");
+        let shader = program
+            .get_shader("tilemap_shader")
+            .expect("failed to get tilemap shader");
+
+        let mut vertex_buffer = self.vertex_buffer.borrow_mut();
+        if vertex_buffer.is_none() {
+            let vertices = get_nine_slice_vertices(
+                self.texture_size,
+                self.insets,
+                self.size,
+                self.edge_mode,
+                self.center_mode,
+            );
+            *vertex_buffer = Some(
+                glium::VertexBuffer::new(&program.display, &vertices)
+                    .expect("failed to create vertex buffer for nine slice sprite"),
+            );
+        }
+
+        let vertex_buffer = vertex_buffer
+            .as_ref()
+            .expect("the vertices were just built");
+        if vertex_buffer.len() == 0 {
+            return;
+        }
+
+        let (width, height) = framebuffer.get_dimensions();
+        let uniforms = glium::uniform! {
+            matrix: transform.get_matrix(),
+            pixel_scale: 2.0 / width.min(height) as f32,
+            image: Sampler(texture, BUFFER_BEHAVIOR),
+            is_normal_map: false,
+        };
+
+        framebuffer
+            .draw(
+                vertex_buffer,
+                glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList),
+                shader,
+                &uniforms,
+                &glium::DrawParameters {
+                    blend: ALPHA_BLEND,
+                    ..Default::default()
+                },
+            )
+            .expect("failed to draw nine slice sprite");
+    }
+}
+
+/// one axis of the grid as (start, end) pairs, in pixels on screen and in the texture
+type Segment = ([f32; 2], [f32; 2]);
+
+/// the three columns or rows along one axis, each cut into copies of the texture if it's tiled
+/// the borders shrink to fit if they're bigger than the size together
+fn get_segments(
+    texture_size: f32,
+    borders: [f32; 2],
+    size: f32,
+    tile_middle: [bool; 3],
+) -> [Vec<Segment>; 3] {
+    let shrink = (size / (borders[0] + borders[1])).min(1.0);
+    let [start, end] = borders.map(|border| border * shrink);
+
+    let cells = [
+        ([0.0, start], [0.0, borders[0]]),
+        ([start, size - end], [borders[0], texture_size - borders[1]]),
+        (
+            [size - end, size],
+            [texture_size - borders[1], texture_size],
+        ),
+    ];
+
+    let mut segments: [Vec<Segment>; 3] = Default::default();
+    for (i, (screen, texture)) in cells.into_iter().enumerate() {
+        let texture_length = texture[1] - texture[0];
+        if screen[1] - screen[0] <= 0.0 || texture_length <= 0.0 {
+            continue;
+        }
+
+        if !tile_middle[i] {
+            segments[i].push((screen, texture));
+            continue;
+        }
+
+        let mut position = screen[0];
+        while position < screen[1] {
+            let length = (screen[1] - position).min(texture_length);
+            segments[i].push((
+                [position, position + length],
+                [texture[0], texture[0] + length],
+            ));
+            position += length;
+        }
+    }
+
+    segments
+}
+
+/// the triangles for the whole sprite centered on 0, 0
+fn get_nine_slice_vertices(
+    texture_size: [u32; 2],
+    insets: Insets,
+    size: [f32; 2],
+    edge_mode: SliceMode,
+    center_mode: SliceMode,
+) -> Vec<TileVertex> {
+    let [texture_width, texture_height] = texture_size.map(|size| size as f32);
+    let [width, height] = size.map(|size| size.max(0.0));
+
+    // only the middle column or row can be tiled, the corners never are
+    let is_tiled = |column: usize, row: usize| match (column == 1, row == 1) {
+        (true, true) => center_mode == SliceMode::Tile,
+        (false, false) => false,
+        _ => edge_mode == SliceMode::Tile,
+    };
+
+    let columns_by_mode = [false, true].map(|tile| {
+        get_segments(
+            texture_width,
+            [insets.left as f32, insets.right as f32],
+            width,
+            [false, tile, false],
+        )
+    });
+    let rows_by_mode = [false, true].map(|tile| {
+        get_segments(
+            texture_height,
+            [insets.top as f32, insets.bottom as f32],
+            height,
+            [false, tile, false],
+        )
+    });
+
+    let mut vertices = vec![];
+    for row in 0..3 {
+        for column in 0..3 {
+            let tiled = is_tiled(column, row);
+            let columns = &columns_by_mode[(tiled && column == 1) as usize][column];
+            let rows = &rows_by_mode[(tiled && row == 1) as usize][row];
+
+            for (screen_y, texture_y) in rows {
+                for (screen_x, texture_x) in columns {
+                    // y goes up on screen, and the texture is stored bottom row first
+                    let [left, right] = screen_x.map(|x| x - width / 2.0);
+                    let [top, bottom] = screen_y.map(|y| height / 2.0 - y);
+                    let [u_left, u_right] = texture_x.map(|x| x / texture_width);
+                    let [v_top, v_bottom] = texture_y.map(|y| 1.0 - y / texture_height);
+
+                    let corners = [
+                        ([left, top], [u_left, v_top]),
+                        ([right, top], [u_right, v_top]),
+                        ([right, bottom], [u_right, v_bottom]),
+                        ([left, bottom], [u_left, v_bottom]),
+                    ];
+
+                    for corner in [0, 1, 2, 0, 2, 3] {
+                        vertices.push(TileVertex::new(corners[corner].0, corners[corner].1));
+                    }
+                }
+            }
+        }
+    }
+
+    vertices
+}
+
+impl Drawable for NineSliceSprite {
+    fn draw_albedo(
+        &self,
+        program: &LumenpyxProgram,
+        transform: &Transform,
+        albedo_framebuffer: &mut SimpleFrameBuffer,
+    ) {
+        self.draw(program, transform, albedo_framebuffer, self.albedo);
+    }
+
+    fn draw_height(
+        &self,
+        program: &LumenpyxProgram,
+        transform: &Transform,
+        height_framebuffer: &mut SimpleFrameBuffer,
+    ) {
+        self.draw(program, transform, height_framebuffer, self.height);
+    }
+
+    fn draw_normal(
+        &self,
+        program: &LumenpyxProgram,
+        transform: &Transform,
+        normal_framebuffer: &mut SimpleFrameBuffer,
+    ) {
+        self.draw(program, transform, normal_framebuffer, self.normal);
+    }
+
+    fn draw_roughness(
+        &self,
+        program: &LumenpyxProgram,
+        transform: &Transform,
+        roughness_framebuffer: &mut SimpleFrameBuffer,
+    ) {
+        self.draw(program, transform, roughness_framebuffer, self.roughness);
+    }
+
+    fn try_load_shaders(&self, program: &mut LumenpyxProgram) {
+        load_tilemap_shader(program);
+    }
+
+    fn get_transform(&self) -> Transform {
+        self.transform
+    }
+
+    fn get_recieve_shadows_strength(&self) -> f32 {
+        self.shadow_strength
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the left, top, right and bottom of every quad on screen
+    fn get_quads(vertices: &[TileVertex]) -> Vec<[f32; 4]> {
+        vertices
+            .chunks(6)
+            .map(|quad| {
+                [
+                    quad[0].position[0],
+                    quad[0].position[1],
+                    quad[2].position[0],
+                    quad[2].position[1],
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_stretched_nine_slice() {
+        let vertices = get_nine_slice_vertices(
+            [12, 12],
+            Insets::uniform(4),
+            [100.0, 20.0],
+            SliceMode::Stretch,
+            SliceMode::Stretch,
+        );
+        let quads = get_quads(&vertices);
+        assert_eq!(quads.len(), 9);
+
+        // the top left corner keeps its size and samples only the corner of the texture
+        assert_eq!(quads[0], [-50.0, 10.0, -46.0, 6.0]);
+        assert_eq!(vertices[0].tex_coords, [0.0, 1.0]);
+        assert_eq!(vertices[2].tex_coords, [4.0 / 12.0, 1.0 - 4.0 / 12.0]);
+        // the center stretches between the borders
+        assert_eq!(quads[4], [-46.0, 6.0, 46.0, -6.0]);
+
+        // borders wider than the sprite shrink to fit
+        let vertices = get_nine_slice_vertices(
+            [12, 12],
+            Insets::uniform(4),
+            [4.0, 8.0],
+            SliceMode::Stretch,
+            SliceMode::Stretch,
+        );
+        let quads = get_quads(&vertices);
+        assert_eq!(quads.len(), 4);
+        assert_eq!(quads[0], [-2.0, 4.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_tiled_nine_slice() {
+        let vertices = get_nine_slice_vertices(
+            [12, 12],
+            Insets::uniform(4),
+            [18.0, 12.0],
+            SliceMode::Tile,
+            SliceMode::Stretch,
+        );
+        let quads = get_quads(&vertices);
+
+        // the top edge is 10 pixels wide and the texture's is 4, so two whole copies and half of one
+        assert_eq!(quads[1], [-5.0, 6.0, -1.0, 2.0]);
+        assert_eq!(quads[2], [-1.0, 6.0, 3.0, 2.0]);
+        assert_eq!(quads[3], [3.0, 6.0, 5.0, 2.0]);
+        assert_eq!(vertices[3 * 6 + 2].tex_coords[0], 6.0 / 12.0);
+
+        // the middle row is only 4 pixels high so its edges fit in one copy
+        assert_eq!(quads.len(), 5 + 3 + 5);
+    }
+}
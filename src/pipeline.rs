@@ -0,0 +1,755 @@
+//! The render pipeline, this does what lumenpyx's `draw_all` does, but into textures kept per target
+//! and without showing the result, so a scene can be drawn by more than one camera in a frame
+//! and the image can be used before (or instead of) being shown in the window
+
+use glium::framebuffer::SimpleFrameBuffer;
+use glium::uniforms::{MagnifySamplerFilter, Sampler, SamplerBehavior, SamplerWrapFunction};
+use glium::{Surface, Texture2d};
+use lumenpyx::drawable_object::Drawable;
+use lumenpyx::lights::LightDrawable;
+use lumenpyx::shaders::FULL_SCREEN_QUAD;
+use lumenpyx::{DebugOption, TextureHandle, Transform};
+
+use crate::blending::load_blending_textures;
+use crate::resolution::get_present_scale;
+use crate::LumenpyxProgram;
+
+/// the target the main camera draws to
+pub(crate) const MAIN_TARGET: &str = "main";
+
+/// the textures every target has, the same ones lumenpyx uses
+const TARGET_BUFFERS: [&str; 9] = [
+    "albedo",
+    "height",
+    "normal",
+    "roughness",
+    "shadow_strength",
+    "last_drawable",
+    "lit",
+    "reflection",
+    "reflected",
+];
+
+/// the same sampling lumenpyx uses for its buffers
+pub(crate) const BUFFER_BEHAVIOR: SamplerBehavior = SamplerBehavior {
+    minify_filter: glium::uniforms::MinifySamplerFilter::Nearest,
+    magnify_filter: MagnifySamplerFilter::Nearest,
+    max_anisotropy: 1,
+    wrap_function: (
+        SamplerWrapFunction::Mirror,
+        SamplerWrapFunction::Mirror,
+        SamplerWrapFunction::Mirror,
+    ),
+    depth_texture_comparison: None,
+};
+
+/// The settings for rendering, these work the same as lumenpyx's but can be read back
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RenderSettings {
+    shadows: bool,
+    reflections: bool,
+    /// the resolution the scene is rendered at, if it's bigger than the resolution
+    /// only the middle of the render is shown, this is for reflecting things that are off screen
+    render_resolution: Option<[u32; 2]>,
+    blur_reflections: bool,
+    blur_strength: f32,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            shadows: true,
+            reflections: true,
+            render_resolution: None,
+            blur_reflections: false,
+            blur_strength: 0.01,
+        }
+    }
+}
+
+impl RenderSettings {
+    pub fn with_shadows(mut self, shadows: bool) -> Self {
+        self.shadows = shadows;
+        self
+    }
+
+    pub fn with_reflections(mut self, reflections: bool) -> Self {
+        self.reflections = reflections;
+        self
+    }
+
+    pub fn with_render_resolution(mut self, resolution: [u32; 2]) -> Self {
+        self.render_resolution = Some(resolution);
+        self
+    }
+
+    pub fn with_blur_reflections(mut self, blur: bool) -> Self {
+        self.blur_reflections = blur;
+        self
+    }
+
+    /// default is 0.01
+    pub fn with_blur_strength(mut self, strength: f32) -> Self {
+        self.blur_strength = strength;
+        self
+    }
+
+    pub fn get_shadows(&self) -> bool {
+        self.shadows
+    }
+
+    pub fn get_reflections(&self) -> bool {
+        self.reflections
+    }
+
+    pub fn get_render_resolution(&self) -> Option<[u32; 2]> {
+        self.render_resolution
+    }
+
+    pub fn get_blur_reflections(&self) -> bool {
+        self.blur_reflections
+    }
+
+    pub fn get_blur_strength(&self) -> f32 {
+        self.blur_strength
+    }
+}
+
+impl From<RenderSettings> for lumenpyx::RenderSettings {
+    fn from(settings: RenderSettings) -> Self {
+        let mut lumen_settings = lumenpyx::RenderSettings::default()
+            .with_shadows(settings.shadows)
+            .with_reflections(settings.reflections)
+            .with_blur_reflections(settings.blur_reflections)
+            .with_blur_strength(settings.blur_strength);
+
+        if let Some(render_resolution) = settings.render_resolution {
+            lumen_settings = lumen_settings.with_render_resolution(render_resolution);
+        }
+
+        lumen_settings
+    }
+}
+
+/// One of the images a render makes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RenderBuffer {
+    /// the finished image, with lighting and reflections if they are turned on
+    #[default]
+    Final,
+    Albedo,
+    Height,
+    Roughness,
+    Normal,
+    ShadowStrength,
+}
+
+impl RenderBuffer {
+    pub(crate) fn from_debug_option(debug: &DebugOption) -> Self {
+        match debug {
+            DebugOption::None => RenderBuffer::Final,
+            DebugOption::Albedo => RenderBuffer::Albedo,
+            DebugOption::Height => RenderBuffer::Height,
+            DebugOption::Roughness => RenderBuffer::Roughness,
+            DebugOption::Normal => RenderBuffer::Normal,
+            DebugOption::ShadowStrength => RenderBuffer::ShadowStrength,
+        }
+    }
+}
+
+pub(crate) fn get_texture_name(target: &str, buffer: &str) -> String {
+    format!("{}_{}_texture", target, buffer)
+}
+
+/// A camera that draws to something other than the main window, collected before rendering
+pub(crate) struct CameraPass {
+    pub(crate) target: String,
+    pub(crate) resolution: [u32; 2],
+    pub(crate) camera: lumenpyx::Camera,
+    /// the texture the finished image is copied to, for cameras that render to a texture
+    pub(crate) output: Option<TextureHandle>,
+}
+
+impl LumenpyxProgram {
+    /// set the render settings, these are kept so the pipeline can read them
+    pub fn set_render_settings(&mut self, settings: RenderSettings) {
+        self.settings = settings;
+        self.internal_program.set_render_settings(settings.into());
+    }
+
+    pub fn get_render_settings(&self) -> RenderSettings {
+        self.settings
+    }
+
+    /// the resolution the main camera renders at, this can be bigger than the resolution shown
+    /// a render resolution from the settings that's smaller than the shown one, like after a resize, is grown to fit
+    pub fn get_render_resolution(&self) -> [u32; 2] {
+        let resolution = self.internal_program.get_resolution();
+        match self.settings.render_resolution {
+            Some(render_resolution) => {
+                [0, 1].map(|axis| render_resolution[axis].max(resolution[axis]))
+            }
+            None => resolution,
+        }
+    }
+
+    /// make a texture a camera can render into with `CameraTarget::Texture`
+    /// the handle can be used like any other texture, for example as the albedo of a sprite
+    pub fn add_render_texture(&mut self, resolution: [u32; 2]) -> TextureHandle {
+        let texture = Texture2d::empty_with_format(
+            &self.display,
+            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+            glium::texture::MipmapsOption::NoMipmap,
+            resolution[0],
+            resolution[1],
+        )
+        .expect("failed to create render texture");
+
+        self.add_not_named_texture(texture)
+    }
+
+    /// the name of the target a camera rendering into this texture draws to
+    pub(crate) fn get_texture_target(&mut self, texture: TextureHandle) -> String {
+        if let Some(target) = self.texture_targets.get(&texture) {
+            return target.clone();
+        }
+
+        // lumenpyx doesn't show the handle's id, so targets are numbered here instead
+        let target = self.free_texture_targets.pop().unwrap_or_else(|| {
+            self.next_texture_target += 1;
+            format!("texture_{}", self.next_texture_target)
+        });
+        self.texture_targets.insert(texture, target.clone());
+        target
+    }
+
+    /// frees the textures of every texture target that isn't drawn this frame and forgets which texture it was for
+    /// named textures can't be removed from lumenpyx, so the freed targets are kept to be given out again
+    pub(crate) fn free_unused_texture_targets(&mut self, camera_passes: &[CameraPass]) {
+        let internal_program = &mut self.internal_program;
+        let free_texture_targets = &mut self.free_texture_targets;
+
+        self.texture_targets.retain(|texture, target| {
+            let used = camera_passes
+                .iter()
+                .any(|camera_pass| camera_pass.output == Some(*texture));
+
+            if !used {
+                free_target_textures(internal_program, target);
+                free_texture_targets.push(std::mem::take(target));
+            }
+            used
+        });
+    }
+
+    /// get an image from the last time a target was drawn, None if it hasn't been drawn yet
+    pub(crate) fn get_target_texture(
+        &self,
+        target: &str,
+        buffer: RenderBuffer,
+    ) -> Option<&Texture2d> {
+        let buffer = match buffer {
+            RenderBuffer::Final if self.settings.reflections => "reflected",
+            RenderBuffer::Final if self.settings.shadows => "lit",
+            RenderBuffer::Final | RenderBuffer::Albedo => "albedo",
+            RenderBuffer::Height => "height",
+            RenderBuffer::Roughness => "roughness",
+            RenderBuffer::Normal => "normal",
+            RenderBuffer::ShadowStrength => "shadow_strength",
+        };
+
+        self.get_texture(&get_texture_name(target, buffer))
+    }
+}
+
+/// makes the textures of a target, or remakes them if the resolution changed
+fn load_target_textures(
+    program: &mut lumenpyx::LumenpyxProgram,
+    target: &str,
+    resolution: [u32; 2],
+) {
+    for buffer in TARGET_BUFFERS {
+        load_render_texture(program, &get_texture_name(target, buffer), resolution);
+    }
+}
+
+/// shrinks the textures of a target that isn't drawn anymore, they come back when it is drawn again
+/// lumenpyx can't remove named textures, so this is as close as it gets
+fn free_target_textures(program: &mut lumenpyx::LumenpyxProgram, target: &str) {
+    load_target_textures(program, target, [1, 1]);
+}
+
+/// makes a named texture to render into, or remakes it if the resolution changed
+pub(crate) fn load_render_texture(
+    program: &mut lumenpyx::LumenpyxProgram,
+    name: &str,
+    resolution: [u32; 2],
+) {
+    let up_to_date = program
+        .get_texture(name)
+        .is_some_and(|texture| texture.dimensions() == (resolution[0], resolution[1]));
+
+    if !up_to_date {
+        let texture = Texture2d::empty_with_format(
+            &program.display,
+            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+            glium::texture::MipmapsOption::NoMipmap,
+            resolution[0],
+            resolution[1],
+        )
+        .expect("failed to create render texture");
+
+        program.add_texture(texture, name);
+    }
+}
+
+/// draws the scene from a camera into the textures of a target
+pub(crate) fn draw_scene(
+    program: &mut LumenpyxProgram,
+    lights: &[&dyn LightDrawable],
+    drawables: &[&dyn Drawable],
+    camera: &lumenpyx::Camera,
+    target: &str,
+    resolution: [u32; 2],
+) {
+    let settings = program.settings;
+
+    // lumenpyx drawables that make their own textures, like its blend object, size them off the render resolution
+    program
+        .internal_program
+        .set_render_settings(settings.with_render_resolution(resolution).into());
+
+    // this is kind of inefficient, but lumenpyx does it this way too
+    for drawable in drawables {
+        drawable.try_load_shaders(&mut program.internal_program);
+    }
+    for light in lights {
+        light.try_load_shaders(&mut program.internal_program);
+    }
+    load_target_textures(&mut program.internal_program, target, resolution);
+    if !program.blending_resolutions.contains(&resolution) {
+        program.blending_resolutions.push(resolution);
+    }
+    load_blending_textures(
+        &mut program.internal_program,
+        resolution,
+        &program.blending_resolutions,
+    );
+
+    {
+        let lumen_program = &program.internal_program;
+
+        draw_buffers(
+            lumen_program,
+            drawables,
+            camera,
+            target,
+            resolution,
+            &settings,
+        );
+
+        if settings.shadows {
+            draw_lighting(lumen_program, lights, camera, target, resolution);
+        }
+
+        if settings.reflections {
+            draw_reflections(lumen_program, camera, target, &settings);
+        }
+    }
+
+    program
+        .internal_program
+        .set_render_settings(settings.into());
+}
+
+/// copies the finished image of a target into a texture, like a render texture
+pub(crate) fn copy_target_to_texture(
+    program: &LumenpyxProgram,
+    target: &str,
+    output: &TextureHandle,
+) {
+    let (Some(source), Some(output)) = (
+        program.get_target_texture(target, RenderBuffer::Final),
+        program.get_texture_from_handle(output),
+    ) else {
+        return;
+    };
+
+    let source_framebuffer = SimpleFrameBuffer::new(&program.display, source)
+        .expect("failed to create render framebuffer");
+    let output_framebuffer = SimpleFrameBuffer::new(&program.display, output)
+        .expect("failed to create render texture framebuffer");
+
+    source_framebuffer.blit_whole_color_to(
+        &output_framebuffer,
+        &glium::BlitTarget {
+            left: 0,
+            bottom: 0,
+            width: output.width() as i32,
+            height: output.height() as i32,
+        },
+        MagnifySamplerFilter::Nearest,
+    );
+}
+
+/// the same as lumenpyx's, but with the resolution of the target
+pub(crate) fn adjust_transform_for_drawable(
+    transform: &Transform,
+    camera: &lumenpyx::Camera,
+    resolution: [u32; 2],
+) -> Transform {
+    let mut new_transform = *transform;
+
+    let mut scale = transform.get_scale();
+    // scale off the resolution
+    if resolution[0] > resolution[1] {
+        scale[0] *= resolution[1] as f32 / resolution[0] as f32;
+    } else {
+        scale[1] *= resolution[0] as f32 / resolution[1] as f32;
+    }
+    new_transform.set_scale(scale[0], scale[1], scale[2]);
+
+    // the z isn't moved by the camera, it would mess up the height map's interaction with the light
+    let x = (transform.get_x() - camera.position[0]) / resolution[0] as f32 * 2.0;
+    let y = (transform.get_y() - camera.position[1]) / resolution[1] as f32 * 2.0;
+
+    new_transform.translate(x, y, transform.get_z());
+
+    new_transform
+}
+
+fn get_framebuffer<'a>(
+    program: &'a lumenpyx::LumenpyxProgram,
+    target: &str,
+    buffer: &str,
+) -> (&'a Texture2d, SimpleFrameBuffer<'a>) {
+    let texture = program
+        .get_texture(&get_texture_name(target, buffer))
+        .expect("failed to get render texture");
+
+    let framebuffer = SimpleFrameBuffer::new(&program.display, texture)
+        .expect("failed to create render framebuffer");
+
+    (texture, framebuffer)
+}
+
+/// draws a full screen shader into a framebuffer
+pub(crate) fn draw_full_screen<U: glium::uniforms::Uniforms>(
+    program: &lumenpyx::LumenpyxProgram,
+    framebuffer: &mut SimpleFrameBuffer,
+    shader: &str,
+    uniforms: &U,
+    draw_parameters: &glium::DrawParameters,
+) {
+    let shader = program
+        .get_shader(shader)
+        .expect("failed to get lumenpyx shader");
+
+    let vertex_buffer = glium::VertexBuffer::new(&program.display, &FULL_SCREEN_QUAD)
+        .expect("failed to create vertex buffer");
+
+    framebuffer
+        .draw(
+            &vertex_buffer,
+            program.indices,
+            shader,
+            uniforms,
+            draw_parameters,
+        )
+        .expect("failed to draw to the framebuffer");
+}
+
+/// draws the albedo, height, roughness, normal and shadow strength of every drawable
+fn draw_buffers(
+    program: &lumenpyx::LumenpyxProgram,
+    drawables: &[&dyn Drawable],
+    camera: &lumenpyx::Camera,
+    target: &str,
+    resolution: [u32; 2],
+    settings: &RenderSettings,
+) {
+    let (albedo_texture, mut albedo_framebuffer) = get_framebuffer(program, target, "albedo");
+    let (_, mut height_framebuffer) = get_framebuffer(program, target, "height");
+    let (_, mut roughness_framebuffer) = get_framebuffer(program, target, "roughness");
+    let (_, mut normal_framebuffer) = get_framebuffer(program, target, "normal");
+    let (_, mut shadow_strength_framebuffer) = get_framebuffer(program, target, "shadow_strength");
+    let (last_drawable_texture, mut last_drawable_framebuffer) =
+        get_framebuffer(program, target, "last_drawable");
+
+    albedo_framebuffer.clear_color(0.0, 0.0, 0.0, 0.0);
+    height_framebuffer.clear_color(0.0, 0.0, 0.0, 0.0);
+    roughness_framebuffer.clear_color(0.0, 0.0, 0.0, 0.0);
+    normal_framebuffer.clear_color(0.0, 0.0, 1.0, 0.0);
+    shadow_strength_framebuffer.clear_color(0.0, 0.0, 0.0, 0.0);
+    last_drawable_framebuffer.clear_color(0.0, 0.0, 0.0, 0.0);
+
+    let transforms = drawables
+        .iter()
+        .map(|drawable| {
+            adjust_transform_for_drawable(&drawable.get_transform(), camera, resolution)
+        })
+        .collect::<Vec<_>>();
+
+    for (drawable, transform) in drawables.iter().zip(&transforms) {
+        drawable.draw_albedo(program, transform, &mut albedo_framebuffer);
+
+        if settings.shadows {
+            // the pixels this drawable changed get its shadow strength
+            let uniforms = glium::uniform! {
+                last_draw: Sampler(last_drawable_texture, BUFFER_BEHAVIOR),
+                this_draw: Sampler(albedo_texture, BUFFER_BEHAVIOR),
+                shadow_strength: drawable.get_recieve_shadows_strength(),
+            };
+
+            draw_full_screen(
+                program,
+                &mut shadow_strength_framebuffer,
+                "recieve_shadows_shader",
+                &uniforms,
+                &Default::default(),
+            );
+
+            albedo_framebuffer.blit_whole_color_to(
+                &last_drawable_framebuffer,
+                &glium::BlitTarget {
+                    left: 0,
+                    bottom: 0,
+                    width: resolution[0] as i32,
+                    height: resolution[1] as i32,
+                },
+                MagnifySamplerFilter::Nearest,
+            );
+        }
+    }
+
+    if settings.shadows || settings.reflections {
+        for (drawable, transform) in drawables.iter().zip(&transforms) {
+            drawable.draw_height(program, transform, &mut height_framebuffer);
+        }
+    }
+
+    if settings.reflections {
+        for (drawable, transform) in drawables.iter().zip(&transforms) {
+            drawable.draw_roughness(program, transform, &mut roughness_framebuffer);
+        }
+
+        for (drawable, transform) in drawables.iter().zip(&transforms) {
+            drawable.draw_normal(program, transform, &mut normal_framebuffer);
+        }
+    }
+}
+
+fn draw_lighting(
+    program: &lumenpyx::LumenpyxProgram,
+    lights: &[&dyn LightDrawable],
+    camera: &lumenpyx::Camera,
+    target: &str,
+    resolution: [u32; 2],
+) {
+    let (albedo_texture, _) = get_framebuffer(program, target, "albedo");
+    let (height_texture, _) = get_framebuffer(program, target, "height");
+    let (roughness_texture, _) = get_framebuffer(program, target, "roughness");
+    let (shadow_strength_texture, _) = get_framebuffer(program, target, "shadow_strength");
+    let (_, mut lit_framebuffer) = get_framebuffer(program, target, "lit");
+
+    lit_framebuffer.clear_color(0.0, 0.0, 0.0, 0.0);
+
+    for light in lights {
+        let transform = adjust_transform_for_drawable(&light.get_transform(), camera, resolution);
+
+        light.draw(
+            program,
+            transform.get_matrix(),
+            &mut lit_framebuffer,
+            Sampler(height_texture, BUFFER_BEHAVIOR),
+            Sampler(albedo_texture, BUFFER_BEHAVIOR),
+            Sampler(roughness_texture, BUFFER_BEHAVIOR),
+            Sampler(shadow_strength_texture, BUFFER_BEHAVIOR),
+        );
+    }
+}
+
+/// screen space reflections, lerped with the lit image by the roughness
+fn draw_reflections(
+    program: &lumenpyx::LumenpyxProgram,
+    camera: &lumenpyx::Camera,
+    target: &str,
+    settings: &RenderSettings,
+) {
+    let lit_buffer = if settings.shadows { "lit" } else { "albedo" };
+
+    let (lit_texture, _) = get_framebuffer(program, target, lit_buffer);
+    let (height_texture, _) = get_framebuffer(program, target, "height");
+    let (roughness_texture, _) = get_framebuffer(program, target, "roughness");
+    let (normal_texture, _) = get_framebuffer(program, target, "normal");
+    let (reflection_texture, mut reflection_framebuffer) =
+        get_framebuffer(program, target, "reflection");
+    let (_, mut reflected_framebuffer) = get_framebuffer(program, target, "reflected");
+
+    let replace = glium::DrawParameters {
+        blend: glium::Blend {
+            color: glium::BlendingFunction::AlwaysReplace,
+            alpha: glium::BlendingFunction::AlwaysReplace,
+            constant_value: (0.0, 0.0, 0.0, 0.0),
+        },
+        ..Default::default()
+    };
+
+    reflection_framebuffer.clear_color(0.0, 0.0, 0.0, 0.0);
+
+    // the intersection distance is stored in the alpha channel
+    let uniforms = glium::uniform! {
+        albedomap: Sampler(lit_texture, BUFFER_BEHAVIOR),
+        heightmap: Sampler(height_texture, BUFFER_BEHAVIOR),
+        roughnessmap: Sampler(roughness_texture, BUFFER_BEHAVIOR),
+        normalmap: Sampler(normal_texture, BUFFER_BEHAVIOR),
+        camera_z: camera.position[2],
+        blur_reflections: settings.blur_reflections,
+    };
+    draw_full_screen(
+        program,
+        &mut reflection_framebuffer,
+        "reflection_shader",
+        &uniforms,
+        &replace,
+    );
+
+    let reflection_sampler = Sampler(reflection_texture, BUFFER_BEHAVIOR);
+
+    if settings.blur_reflections {
+        let uniforms = glium::uniform! {
+            tex: reflection_sampler,
+            blur_scale: settings.blur_strength,
+        };
+        draw_full_screen(
+            program,
+            &mut reflection_framebuffer,
+            "gaussian_blur",
+            &uniforms,
+            &replace,
+        );
+    }
+
+    let uniforms = glium::uniform! {
+        top_tex: reflection_sampler,
+        bottom_tex: Sampler(lit_texture, BUFFER_BEHAVIOR),
+    };
+    draw_full_screen(
+        program,
+        &mut reflected_framebuffer,
+        "overlay_shader",
+        &uniforms,
+        &Default::default(),
+    );
+}
+
+/// where an image goes in a target so it is as big as possible without changing its aspect ratio
+/// with `integer_scale` each image pixel is a whole number of target pixels
+/// returned as [left, bottom, width, height]
+pub(crate) fn get_letterbox_rect(
+    image_size: [u32; 2],
+    target_size: [u32; 2],
+    integer_scale: bool,
+) -> [u32; 4] {
+    let scale = get_present_scale(image_size, target_size, integer_scale);
+
+    let width = ((image_size[0] as f64 * scale).round() as u32).min(target_size[0]);
+    let height = ((image_size[1] as f64 * scale).round() as u32).min(target_size[1]);
+
+    [
+        (target_size[0] - width) / 2,
+        (target_size[1] - height) / 2,
+        width,
+        height,
+    ]
+}
+
+/// shows the middle `visible_size` pixels of a texture in a window, scaled to fit with bars around it
+pub(crate) fn present_texture(
+    display: &glium::Display<glium::glutin::surface::WindowSurface>,
+    texture: &Texture2d,
+    visible_size: [u32; 2],
+    integer_scale: bool,
+) {
+    let (texture_width, texture_height) = texture.dimensions();
+    let visible_width = visible_size[0].min(texture_width);
+    let visible_height = visible_size[1].min(texture_height);
+
+    let framebuffer =
+        SimpleFrameBuffer::new(display, texture).expect("failed to create present framebuffer");
+
+    let mut frame = display.draw();
+    frame.clear_color(0.0, 0.0, 0.0, 0.0);
+
+    let (frame_width, frame_height) = frame.get_dimensions();
+    let [left, bottom, width, height] = get_letterbox_rect(
+        [visible_width, visible_height],
+        [frame_width, frame_height],
+        integer_scale,
+    );
+
+    framebuffer.blit_color(
+        &glium::Rect {
+            left: (texture_width - visible_width) / 2,
+            bottom: (texture_height - visible_height) / 2,
+            width: visible_width,
+            height: visible_height,
+        },
+        &frame,
+        &glium::BlitTarget {
+            left,
+            bottom,
+            width: width as i32,
+            height: height as i32,
+        },
+        MagnifySamplerFilter::Nearest,
+    );
+
+    frame
+        .finish()
+        .expect("failed to finish drawing to the window");
+}
+
+/// shows a target in the main window, the way lumenpyx would at the end of `draw_all`
+pub(crate) fn present_target(program: &LumenpyxProgram, target: &str) {
+    let buffer = RenderBuffer::from_debug_option(&program.debug);
+
+    let texture = program
+        .get_target_texture(target, buffer)
+        .expect("the target has to be drawn before it can be shown");
+
+    present_texture(
+        &program.display,
+        texture,
+        program.get_resolution(),
+        program.resolution_policy.is_integer_scaled(),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_letterbox_rect() {
+        // a wide window has bars on the sides
+        assert_eq!(
+            get_letterbox_rect([160, 90], [1920, 720], false),
+            [320, 0, 1280, 720]
+        );
+        // a tall window has bars above and below
+        assert_eq!(
+            get_letterbox_rect([160, 90], [800, 900], false),
+            [0, 225, 800, 450]
+        );
+        assert_eq!(
+            get_letterbox_rect([160, 90], [1920, 1080], false),
+            [0, 0, 1920, 1080]
+        );
+        // the pixels left over after scaling by 11 are split between the bars
+        assert_eq!(
+            get_letterbox_rect([174, 98], [1920, 1080], true),
+            [3, 1, 1914, 1078]
+        );
+    }
+}
@@ -0,0 +1,575 @@
+//! Effects drawn over the finished image, after the lighting and reflections
+//! they run in the order they were added and can be changed between frames like any other setting
+//! custom effects are GLSL fragment shaders that get the same inputs as the built in ones
+//!
+//! the chain only runs on the main camera's image, so the main window, mirror windows and captures have the effects
+//! but cameras that render into a texture or their own window don't, which keeps the effects from
+//! being applied twice when a render texture is shown in the main scene
+
+pub mod palette;
+
+use std::fmt;
+use std::path::Path;
+
+use glium::framebuffer::SimpleFrameBuffer;
+use glium::texture::RawImage2d;
+use glium::uniforms::{
+    AsUniformValue, DynamicUniforms, MagnifySamplerFilter, MinifySamplerFilter, Sampler,
+    SamplerBehavior, SamplerWrapFunction,
+};
+use glium::{Surface, Texture2d};
+use lumenpyx::TextureHandle;
+
+use crate::pipeline::{
+    draw_full_screen, get_texture_name, load_render_texture, RenderBuffer, BUFFER_BEHAVIOR,
+};
+use crate::LumenpyxProgram;
+use palette::{diffuse_error, Dithering, Palette};
+
+const POST_VERTEX_SHADER: &str = include_str!("../shaders/full_screen.vert");
+
+/// the built in effects, by the name their shader is stored under
+const BUILT_IN_SHADERS: [(&str, &str); 7] = [
+    (
+        "post_bloom",
+        include_str!("../shaders/post_processing/bloom.frag"),
+    ),
+    (
+        "post_vignette",
+        include_str!("../shaders/post_processing/vignette.frag"),
+    ),
+    (
+        "post_color_grading",
+        include_str!("../shaders/post_processing/color_grading.frag"),
+    ),
+    (
+        "post_chromatic_aberration",
+        include_str!("../shaders/post_processing/chromatic_aberration.frag"),
+    ),
+    (
+        "post_crt",
+        include_str!("../shaders/post_processing/crt.frag"),
+    ),
+    (
+        "post_film_grain",
+        include_str!("../shaders/post_processing/film_grain.frag"),
+    ),
+    (
+        "post_palette",
+        include_str!("../shaders/post_processing/palette.frag"),
+    ),
+];
+
+/// custom shaders are stored with this in front of their name, so they can't replace lumenpyx's
+const CUSTOM_SHADER_PREFIX: &str = "post_custom_";
+
+/// the two textures every effect takes turns drawing into
+const POST_BUFFERS: [&str; 2] = ["post_a", "post_b"];
+
+/// colors in a lut are blended between, unlike the pixels of the image
+const LUT_BEHAVIOR: SamplerBehavior = SamplerBehavior {
+    minify_filter: MinifySamplerFilter::Linear,
+    magnify_filter: MagnifySamplerFilter::Linear,
+    max_anisotropy: 1,
+    wrap_function: (
+        SamplerWrapFunction::Clamp,
+        SamplerWrapFunction::Clamp,
+        SamplerWrapFunction::Clamp,
+    ),
+    depth_texture_comparison: None,
+};
+
+#[derive(Debug)]
+pub enum PostProcessError {
+    /// the shader didn't compile, the error has the log from the driver
+    Shader(glium::ProgramCreationError),
+    Image(image::ImageError),
+    Texture(glium::texture::TextureCreationError),
+    /// a lut has to be a row of squares, so its width is its height squared
+    InvalidLutSize {
+        width: u32,
+        height: u32,
+    },
+}
+
+impl fmt::Display for PostProcessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PostProcessError::Shader(error) => {
+                write!(f, "failed to compile post processing shader: {}", error)
+            }
+            PostProcessError::Image(error) => write!(f, "failed to load lut: {}", error),
+            PostProcessError::Texture(error) => {
+                write!(f, "failed to create lut or palette texture: {}", error)
+            }
+            PostProcessError::InvalidLutSize { width, height } => write!(
+                f,
+                "a {}x{} image isn't a lut, the width has to be the height squared",
+                width, height
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PostProcessError {}
+
+impl From<glium::ProgramCreationError> for PostProcessError {
+    fn from(error: glium::ProgramCreationError) -> Self {
+        PostProcessError::Shader(error)
+    }
+}
+
+impl From<image::ImageError> for PostProcessError {
+    fn from(error: image::ImageError) -> Self {
+        PostProcessError::Image(error)
+    }
+}
+
+impl From<glium::texture::TextureCreationError> for PostProcessError {
+    fn from(error: glium::texture::TextureCreationError) -> Self {
+        PostProcessError::Texture(error)
+    }
+}
+
+/// A value given to a custom shader as a uniform
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PostParameter {
+    Float(f32),
+    Vec2([f32; 2]),
+    Vec3([f32; 3]),
+    Vec4([f32; 4]),
+}
+
+impl PostParameter {
+    fn as_uniform_value(&self) -> &dyn AsUniformValue {
+        match self {
+            PostParameter::Float(value) => value,
+            PostParameter::Vec2(value) => value,
+            PostParameter::Vec3(value) => value,
+            PostParameter::Vec4(value) => value,
+        }
+    }
+}
+
+impl From<f32> for PostParameter {
+    fn from(value: f32) -> Self {
+        PostParameter::Float(value)
+    }
+}
+
+impl From<[f32; 2]> for PostParameter {
+    fn from(value: [f32; 2]) -> Self {
+        PostParameter::Vec2(value)
+    }
+}
+
+impl From<[f32; 3]> for PostParameter {
+    fn from(value: [f32; 3]) -> Self {
+        PostParameter::Vec3(value)
+    }
+}
+
+impl From<[f32; 4]> for PostParameter {
+    fn from(value: [f32; 4]) -> Self {
+        PostParameter::Vec4(value)
+    }
+}
+
+/// An effect drawn over the finished image
+/// distances are in pixels of the render resolution, so effects look the same at any window size
+#[derive(Clone, Debug, PartialEq)]
+pub enum PostEffect {
+    /// light brighter than the threshold (0 to 1) glows onto the pixels around it
+    Bloom {
+        threshold: f32,
+        intensity: f32,
+        radius: f32,
+    },
+    /// darkens the corners, the radius is where it starts, from 0 (the middle) to 1 (the corners)
+    Vignette {
+        strength: f32,
+        radius: f32,
+        softness: f32,
+    },
+    /// changes the colors with a lut from `LumenpyxProgram::load_lut`, strength 1 is fully graded
+    ColorGrading { lut: TextureHandle, strength: f32 },
+    /// the red and blue channels split apart towards the edges, by offset pixels at the edge
+    ChromaticAberration { offset: f32 },
+    /// a curved screen with darker scanlines, curvature 0 is flat
+    Crt {
+        curvature: f32,
+        scanline_strength: f32,
+    },
+    /// noise that changes every frame
+    FilmGrain { strength: f32 },
+    /// every pixel becomes a color from the palette, best near the end of the chain
+    Palette {
+        palette: Palette,
+        dithering: Dithering,
+    },
+    /// a shader added with `LumenpyxProgram::add_post_shader`, the parameters are set as uniforms
+    Custom {
+        shader: String,
+        parameters: Vec<(String, PostParameter)>,
+    },
+}
+
+impl PostEffect {
+    /// the source of the shader this effect uses, None for custom effects
+    fn get_built_in_shader(&self) -> Option<&'static str> {
+        let shader_name = self.get_shader_name();
+
+        BUILT_IN_SHADERS
+            .iter()
+            .find(|(name, _)| *name == shader_name)
+            .map(|(_, fragment_shader)| *fragment_shader)
+    }
+
+    fn get_shader_name(&self) -> String {
+        match self {
+            PostEffect::Bloom { .. } => "post_bloom".to_string(),
+            PostEffect::Vignette { .. } => "post_vignette".to_string(),
+            PostEffect::ColorGrading { .. } => "post_color_grading".to_string(),
+            PostEffect::ChromaticAberration { .. } => "post_chromatic_aberration".to_string(),
+            PostEffect::Crt { .. } => "post_crt".to_string(),
+            PostEffect::FilmGrain { .. } => "post_film_grain".to_string(),
+            PostEffect::Palette { .. } => "post_palette".to_string(),
+            PostEffect::Custom { shader, .. } => format!("{}{}", CUSTOM_SHADER_PREFIX, shader),
+        }
+    }
+}
+
+impl LumenpyxProgram {
+    /// add an effect to the end of the post processing chain, the effects only apply to the main camera
+    /// this compiles the effect's shader if it's built in and hasn't been compiled yet, and makes its palette texture
+    pub fn add_post_effect(&mut self, effect: PostEffect) -> Result<(), PostProcessError> {
+        self.load_built_in_shader(&effect)?;
+        self.load_palette_texture(&effect)?;
+        self.post_effects.push(effect);
+        Ok(())
+    }
+
+    pub fn get_post_effects(&self) -> &[PostEffect] {
+        &self.post_effects
+    }
+
+    /// the post processing chain, to change the parameters, order or which effects are in it
+    /// built in effects added here are skipped until their shader is compiled with `load_post_shaders`,
+    /// and so are palettes that are added or changed here, until `load_post_shaders` makes their texture
+    pub fn get_post_effects_mut(&mut self) -> &mut Vec<PostEffect> {
+        &mut self.post_effects
+    }
+
+    /// compile the shaders of every built in effect that hasn't been compiled yet,
+    /// and make the textures of the palettes in the chain, forgetting the ones that aren't in it anymore
+    pub fn load_post_shaders(&mut self) -> Result<(), PostProcessError> {
+        for (name, fragment_shader) in BUILT_IN_SHADERS {
+            self.load_post_shader(name, fragment_shader)?;
+        }
+
+        let post_effects = std::mem::take(&mut self.post_effects);
+        self.palette_textures.retain(|palette, _| {
+            post_effects.iter().any(|effect| {
+                matches!(effect, PostEffect::Palette { palette: used, .. } if used == palette)
+            })
+        });
+        let result = post_effects
+            .iter()
+            .try_for_each(|effect| self.load_palette_texture(effect));
+        self.post_effects = post_effects;
+
+        result
+    }
+
+    /// the palette as a texture one pixel high, made once for every palette
+    fn load_palette_texture(&mut self, effect: &PostEffect) -> Result<(), PostProcessError> {
+        let PostEffect::Palette { palette, .. } = effect else {
+            return Ok(());
+        };
+        if palette.is_empty() || self.palette_textures.contains_key(palette) {
+            return Ok(());
+        }
+
+        let colors = palette.get_colors().concat();
+        let texture = Texture2d::new(
+            &self.display,
+            RawImage2d::from_raw_rgb(colors, (palette.len() as u32, 1)),
+        )?;
+        self.palette_textures.insert(palette.clone(), texture);
+        Ok(())
+    }
+
+    fn load_built_in_shader(&mut self, effect: &PostEffect) -> Result<(), PostProcessError> {
+        match effect.get_built_in_shader() {
+            Some(fragment_shader) => {
+                self.load_post_shader(&effect.get_shader_name(), fragment_shader)
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn load_post_shader(
+        &mut self,
+        name: &str,
+        fragment_shader: &str,
+    ) -> Result<(), PostProcessError> {
+        if self.get_shader(name).is_none() {
+            let shader = glium::Program::from_source(
+                &self.display,
+                POST_VERTEX_SHADER,
+                fragment_shader,
+                None,
+            )?;
+            self.add_shader(shader, name);
+        }
+        Ok(())
+    }
+
+    pub fn clear_post_effects(&mut self) {
+        self.post_effects.clear();
+        self.palette_textures.clear();
+    }
+
+    /// compile a fragment shader for `PostEffect::Custom`, a shader with the same name is replaced
+    /// the shader gets `in vec2 v_tex_coords` and the uniforms `sampler2D image`,
+    /// `vec2 resolution` and `float time`, and the version has to be 140 or lower
+    pub fn add_post_shader(
+        &mut self,
+        name: &str,
+        fragment_shader: &str,
+    ) -> Result<(), PostProcessError> {
+        let shader =
+            glium::Program::from_source(&self.display, POST_VERTEX_SHADER, fragment_shader, None)?;
+
+        self.add_shader(shader, &format!("{}{}", CUSTOM_SHADER_PREFIX, name));
+        Ok(())
+    }
+
+    /// load a color grading lut, a strip of squares like the ones Unreal and Unity use
+    /// a 256x16 image has 16 squares, one for each blue value, with red going right and green going down
+    pub fn load_lut(&mut self, path: impl AsRef<Path>) -> Result<TextureHandle, PostProcessError> {
+        let image = image::open(path)?.to_rgba8();
+        let (width, height) = image.dimensions();
+
+        if get_lut_size([width, height]).is_none() {
+            return Err(PostProcessError::InvalidLutSize { width, height });
+        }
+
+        let image = RawImage2d::from_raw_rgba_reversed(&image.into_raw(), (width, height));
+        let texture = Texture2d::new(&self.display, image)?;
+
+        Ok(self.add_not_named_texture(texture))
+    }
+}
+
+/// the number of squares in a lut, None if the image isn't a lut
+fn get_lut_size(dimensions: [u32; 2]) -> Option<u32> {
+    let [width, height] = dimensions;
+
+    if height > 1 && height.checked_mul(height) == Some(width) {
+        Some(height)
+    } else {
+        None
+    }
+}
+
+/// draws the post processing chain over the finished image of a target
+pub(crate) fn apply_post_effects(program: &mut LumenpyxProgram, target: &str, time: f32) {
+    if program.post_effects.is_empty() {
+        return;
+    }
+
+    let Some(resolution) = program
+        .get_target_texture(target, RenderBuffer::Final)
+        .map(|texture| [texture.width(), texture.height()])
+    else {
+        return;
+    };
+
+    for buffer in POST_BUFFERS {
+        load_render_texture(
+            &mut program.internal_program,
+            &get_texture_name(target, buffer),
+            resolution,
+        );
+    }
+
+    let program = &*program;
+    let final_texture = program
+        .get_target_texture(target, RenderBuffer::Final)
+        .expect("the target has to be drawn before post processing");
+    let post_textures = POST_BUFFERS.map(|buffer| {
+        program
+            .get_texture(&get_texture_name(target, buffer))
+            .expect("failed to get post processing texture")
+    });
+
+    let resolution = [resolution[0] as f32, resolution[1] as f32];
+    let mut source = final_texture;
+    let mut passes = 0;
+
+    for effect in &program.post_effects {
+        let shader_name = effect.get_shader_name();
+
+        // an effect whose shader hasn't been compiled yet is skipped
+        if program.get_shader(&shader_name).is_none() {
+            continue;
+        }
+
+        let output = post_textures[passes % 2];
+
+        if let PostEffect::Palette {
+            palette,
+            dithering: Dithering::ErrorDiffusion,
+        } = effect
+        {
+            // every pixel depends on the ones before it, so this can't be a shader
+            let image: RawImage2d<u8> = source.read();
+            let (width, height) = (image.width, image.height);
+            let mut pixels = image.data.into_owned();
+            diffuse_error(&mut pixels, [width, height], palette);
+
+            output.write(
+                glium::Rect {
+                    left: 0,
+                    bottom: 0,
+                    width,
+                    height,
+                },
+                RawImage2d::from_raw_rgba(pixels, (width, height)),
+            );
+
+            source = output;
+            passes += 1;
+            continue;
+        }
+
+        let mut framebuffer = SimpleFrameBuffer::new(&program.display, output)
+            .expect("failed to create post processing framebuffer");
+
+        let image = Sampler(source, BUFFER_BEHAVIOR);
+        let lut;
+        let lut_size;
+        let palette_size;
+        let palette_sampler;
+        let dither_strength;
+        let mut uniforms = DynamicUniforms::new();
+        uniforms.add("image", &image);
+        uniforms.add("resolution", &resolution);
+        uniforms.add("time", &time);
+
+        match effect {
+            PostEffect::Bloom {
+                threshold,
+                intensity,
+                radius,
+            } => {
+                uniforms.add("threshold", threshold);
+                uniforms.add("intensity", intensity);
+                uniforms.add("radius", radius);
+            }
+            PostEffect::Vignette {
+                strength,
+                radius,
+                softness,
+            } => {
+                uniforms.add("strength", strength);
+                uniforms.add("radius", radius);
+                uniforms.add("softness", softness);
+            }
+            PostEffect::ColorGrading {
+                lut: handle,
+                strength,
+            } => {
+                let Some(texture) = program.get_texture_from_handle(handle) else {
+                    continue;
+                };
+
+                lut = Sampler(texture, LUT_BEHAVIOR);
+                lut_size = texture.height() as f32;
+                uniforms.add("lut", &lut);
+                uniforms.add("lut_size", &lut_size);
+                uniforms.add("strength", strength);
+            }
+            PostEffect::ChromaticAberration { offset } => {
+                uniforms.add("offset", offset);
+            }
+            PostEffect::Crt {
+                curvature,
+                scanline_strength,
+            } => {
+                uniforms.add("curvature", curvature);
+                uniforms.add("scanline_strength", scanline_strength);
+            }
+            PostEffect::FilmGrain { strength } => {
+                uniforms.add("strength", strength);
+            }
+            PostEffect::Palette { palette, dithering } => {
+                // empty palettes and ones changed since `load_post_shaders` don't have a texture
+                let Some(palette_texture) = program.palette_textures.get(palette) else {
+                    continue;
+                };
+
+                palette_sampler = Sampler(palette_texture, BUFFER_BEHAVIOR);
+                palette_size = palette.len() as i32;
+                dither_strength = match dithering {
+                    Dithering::Ordered { strength } => *strength,
+                    Dithering::None | Dithering::ErrorDiffusion => 0.0,
+                };
+                uniforms.add("palette", &palette_sampler);
+                uniforms.add("palette_size", &palette_size);
+                uniforms.add("dither_strength", &dither_strength);
+            }
+            PostEffect::Custom { parameters, .. } => {
+                for (name, value) in parameters {
+                    uniforms.add(name, value.as_uniform_value());
+                }
+            }
+        }
+
+        draw_full_screen(
+            &program.internal_program,
+            &mut framebuffer,
+            &shader_name,
+            &uniforms,
+            &Default::default(),
+        );
+
+        source = output;
+        passes += 1;
+    }
+
+    if passes == 0 {
+        return;
+    }
+
+    // the result goes back into the final image, so everything that shows or copies it gets the effects
+    let source_framebuffer = SimpleFrameBuffer::new(&program.display, source)
+        .expect("failed to create post processing framebuffer");
+    let final_framebuffer = SimpleFrameBuffer::new(&program.display, final_texture)
+        .expect("failed to create render framebuffer");
+
+    source_framebuffer.blit_whole_color_to(
+        &final_framebuffer,
+        &glium::BlitTarget {
+            left: 0,
+            bottom: 0,
+            width: final_texture.width() as i32,
+            height: final_texture.height() as i32,
+        },
+        MagnifySamplerFilter::Nearest,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lut_size() {
+        assert_eq!(get_lut_size([256, 16]), Some(16));
+        assert_eq!(get_lut_size([1024, 32]), Some(32));
+        assert_eq!(get_lut_size([256, 256]), None);
+        assert_eq!(get_lut_size([1, 1]), None);
+    }
+}
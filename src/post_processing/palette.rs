@@ -0,0 +1,287 @@
+//! Fixed color palettes for `PostEffect::Palette`, so lighting can't make colors the art doesn't use
+//! palettes are loaded from swatch files: .hex (one color per line, like Lospec's),
+//! .gpl (GIMP palettes) or .png (every different color in the image, in reading order)
+
+use std::fmt;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum PaletteError {
+    Io(std::io::Error),
+    Image(image::ImageError),
+    /// a line of a .hex or .gpl file that isn't a color, lines start at 1
+    InvalidLine {
+        line: usize,
+        text: String,
+    },
+    /// the file isn't a .hex, .gpl or .png
+    UnsupportedFormat(String),
+    /// the file has no colors in it
+    Empty,
+}
+
+impl fmt::Display for PaletteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaletteError::Io(error) => write!(f, "failed to read palette: {}", error),
+            PaletteError::Image(error) => write!(f, "failed to load palette image: {}", error),
+            PaletteError::InvalidLine { line, text } => {
+                write!(f, "line {} of the palette isn't a color: {:?}", line, text)
+            }
+            PaletteError::UnsupportedFormat(extension) => write!(
+                f,
+                "palettes can't be loaded from .{} files, only .hex, .gpl and .png",
+                extension
+            ),
+            PaletteError::Empty => write!(f, "the palette has no colors"),
+        }
+    }
+}
+
+impl std::error::Error for PaletteError {}
+
+impl From<std::io::Error> for PaletteError {
+    fn from(error: std::io::Error) -> Self {
+        PaletteError::Io(error)
+    }
+}
+
+impl From<image::ImageError> for PaletteError {
+    fn from(error: image::ImageError) -> Self {
+        PaletteError::Image(error)
+    }
+}
+
+/// How the colors between the palette's colors are shown
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Dithering {
+    /// every pixel becomes the closest color, gradients turn into bands
+    #[default]
+    None,
+    /// a repeating 4x4 Bayer pattern, stable when the camera moves
+    /// strength is how far (0 to 1) a color can be pushed towards its neighbours, around 0.1 to 0.25 works well
+    Ordered { strength: f32 },
+    /// Floyd-Steinberg, the error of every pixel is spread to the pixels after it
+    /// smoother than ordered but the pattern moves with the image, and it runs on the cpu
+    ErrorDiffusion,
+}
+
+/// A list of colors
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Palette {
+    colors: Vec<[u8; 3]>,
+}
+
+impl Palette {
+    pub fn new(colors: Vec<[u8; 3]>) -> Self {
+        Self { colors }
+    }
+
+    /// load a palette from a .hex, .gpl or .png file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PaletteError> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        let palette = match extension.as_str() {
+            "hex" => Self::from_hex(&std::fs::read_to_string(path)?)?,
+            "gpl" => Self::from_gpl(&std::fs::read_to_string(path)?)?,
+            "png" => Self::from_image(&image::open(path)?.to_rgba8()),
+            _ => return Err(PaletteError::UnsupportedFormat(extension)),
+        };
+
+        if palette.colors.is_empty() {
+            return Err(PaletteError::Empty);
+        }
+
+        Ok(palette)
+    }
+
+    /// one RRGGBB color per line, the # is optional and empty lines are skipped
+    pub fn from_hex(text: &str) -> Result<Self, PaletteError> {
+        let mut colors = vec![];
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let color = parse_hex_color(line.trim_start_matches('#')).ok_or_else(|| {
+                PaletteError::InvalidLine {
+                    line: index + 1,
+                    text: line.to_string(),
+                }
+            })?;
+            colors.push(color);
+        }
+
+        Ok(Self::new(colors))
+    }
+
+    /// a GIMP palette, the header, comments and color names are skipped
+    pub fn from_gpl(text: &str) -> Result<Self, PaletteError> {
+        let mut colors = vec![];
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            let is_header =
+                line == "GIMP Palette" || line.starts_with("Name:") || line.starts_with("Columns:");
+
+            if line.is_empty() || line.starts_with('#') || is_header {
+                continue;
+            }
+
+            let invalid_line = || PaletteError::InvalidLine {
+                line: index + 1,
+                text: line.to_string(),
+            };
+
+            let mut channels = line.split_whitespace().map(|channel| channel.parse::<u8>());
+            let mut color = [0; 3];
+            for channel in &mut color {
+                *channel = channels
+                    .next()
+                    .and_then(|channel| channel.ok())
+                    .ok_or_else(invalid_line)?;
+            }
+            colors.push(color);
+        }
+
+        Ok(Self::new(colors))
+    }
+
+    /// every different color in an image, in reading order, transparent pixels are skipped
+    pub fn from_image(image: &image::RgbaImage) -> Self {
+        let mut colors = vec![];
+
+        for pixel in image.pixels() {
+            let color = [pixel[0], pixel[1], pixel[2]];
+            if pixel[3] > 0 && !colors.contains(&color) {
+                colors.push(color);
+            }
+        }
+
+        Self::new(colors)
+    }
+
+    pub fn get_colors(&self) -> &[[u8; 3]] {
+        &self.colors
+    }
+
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+
+    /// the color in the palette closest to a color, with channels from 0 to 255
+    /// None if the palette is empty
+    pub fn get_nearest_color(&self, color: [f32; 3]) -> Option<[u8; 3]> {
+        self.colors.iter().copied().min_by(|a, b| {
+            get_distance_squared(color, *a).total_cmp(&get_distance_squared(color, *b))
+        })
+    }
+}
+
+fn parse_hex_color(text: &str) -> Option<[u8; 3]> {
+    if text.len() != 6 || !text.is_ascii() {
+        return None;
+    }
+
+    let channel = |index: usize| u8::from_str_radix(&text[index..index + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// the same distance the palette shader uses
+fn get_distance_squared(color: [f32; 3], palette_color: [u8; 3]) -> f32 {
+    color
+        .iter()
+        .zip(palette_color)
+        .map(|(channel, palette_channel)| (channel - palette_channel as f32).powi(2))
+        .sum()
+}
+
+/// Floyd-Steinberg dithering of rgba pixels, row by row
+/// pixels that are fully transparent are left alone, and don't spread error
+pub(crate) fn diffuse_error(pixels: &mut [u8], size: [u32; 2], palette: &Palette) {
+    let [width, height] = [size[0] as usize, size[1] as usize];
+
+    // the error of the current row and the next one
+    let mut error = vec![[0.0_f32; 3]; width];
+    let mut next_error = vec![[0.0_f32; 3]; width];
+
+    for y in 0..height {
+        for x in 0..width {
+            let index = (y * width + x) * 4;
+            if pixels[index + 3] == 0 {
+                continue;
+            }
+
+            let wanted = [0, 1, 2].map(|channel| {
+                (pixels[index + channel] as f32 + error[x][channel]).clamp(0.0, 255.0)
+            });
+            let Some(nearest) = palette.get_nearest_color(wanted) else {
+                return;
+            };
+
+            for channel in 0..3 {
+                pixels[index + channel] = nearest[channel];
+
+                let difference = wanted[channel] - nearest[channel] as f32;
+                if x + 1 < width {
+                    error[x + 1][channel] += difference * 7.0 / 16.0;
+                    next_error[x + 1][channel] += difference * 1.0 / 16.0;
+                }
+                if x > 0 {
+                    next_error[x - 1][channel] += difference * 3.0 / 16.0;
+                }
+                next_error[x][channel] += difference * 5.0 / 16.0;
+            }
+        }
+
+        std::mem::swap(&mut error, &mut next_error);
+        next_error.fill([0.0; 3]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_palettes() {
+        let hex = Palette::from_hex("ff0000\n#00FF00\n\n0000ff\n").unwrap();
+        assert_eq!(hex.get_colors(), &[[255, 0, 0], [0, 255, 0], [0, 0, 255]]);
+
+        let gpl = "GIMP Palette\nName: test\nColumns: 2\n# a comment\n255   0   0\tred\n  0 128 255 blue\n";
+        assert_eq!(
+            Palette::from_gpl(gpl).unwrap().get_colors(),
+            &[[255, 0, 0], [0, 128, 255]]
+        );
+
+        assert!(matches!(
+            Palette::from_hex("ff0000\nnope"),
+            Err(PaletteError::InvalidLine { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_diffuse_error() {
+        let palette = Palette::new(vec![[0, 0, 0], [255, 255, 255]]);
+
+        // a flat 50% gray becomes a mix of black and white, about half each
+        let mut pixels = [128, 128, 128, 255].repeat(16);
+        diffuse_error(&mut pixels, [4, 4], &palette);
+
+        let white = pixels.chunks(4).filter(|pixel| pixel[0] == 255).count();
+        assert!(pixels
+            .chunks(4)
+            .all(|pixel| pixel[0] == 0 || pixel[0] == 255));
+        assert!((6..=10).contains(&white));
+    }
+}
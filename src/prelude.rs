@@ -1,6 +1,16 @@
+pub use crate::assets::{
+    AssetError, AssetHandle, Assets, NormalSource, SpriteSource, TextureSource,
+};
+pub use crate::atlas::{AtlasError, AtlasManifest, AtlasRegion, AtlasSprite, TextureAtlas};
 pub use crate::lights::*;
+pub use crate::nine_slice::{Insets, NineSliceSprite, SliceMode};
+pub use crate::post_processing::palette::{Dithering, Palette};
+pub use crate::post_processing::PostEffect;
 pub use crate::primitives::*;
 pub use crate::render;
+pub use crate::tilemap::tiled::{PropertyValue, TiledError, TiledMap, TiledProperties};
+pub use crate::tilemap::{Tile, TileAnimation, TileRotation, Tilemap, Tileset};
 pub use crate::Camera;
+pub use crate::CameraTarget;
 pub use crate::LumenpyxEventLoop;
 pub use crate::RenderSettings;
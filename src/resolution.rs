@@ -0,0 +1,210 @@
+//! How the render resolution follows the size of the window
+//! lumenpyx scales the render to fit inside the window, keeping the aspect ratio,
+//! so the policies here pick a render resolution that makes that scaling look the way you want
+
+use crate::LumenpyxProgram;
+
+/// What happens to the render resolution when the window is resized
+/// the virtual resolution is the one given to `LumenpyxEventLoop::new` or `set_virtual_resolution`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResolutionPolicy {
+    #[default]
+    /// always render at the virtual resolution, bars fill the space when the aspect ratio doesn't match
+    Letterbox,
+    /// render at least the virtual resolution and grow it along the wider axis,
+    /// so wider (or taller) windows see more of the world instead of bars
+    Expand,
+    /// the render resolution follows the window, each world pixel is `pixel_scale` logical window pixels
+    /// so things are the same size on high DPI screens
+    MatchWindow { pixel_scale: u32 },
+    /// each world pixel is drawn as a whole number of window pixels, as big as possible while
+    /// the virtual resolution still fits, the space around it shows more of the world
+    /// and the few window pixels left over that can't fit a whole world pixel are bars
+    IntegerScaled,
+}
+
+impl ResolutionPolicy {
+    /// the render resolution this policy uses for a window size in physical pixels
+    pub fn get_render_resolution(
+        &self,
+        virtual_resolution: [u32; 2],
+        window_size: [u32; 2],
+        scale_factor: f64,
+    ) -> [u32; 2] {
+        // a minimized window has no size, so keep what we have
+        if window_size[0] == 0 || window_size[1] == 0 {
+            return virtual_resolution;
+        }
+
+        let width_scale = window_size[0] as f64 / virtual_resolution[0] as f64;
+        let height_scale = window_size[1] as f64 / virtual_resolution[1] as f64;
+
+        match self {
+            ResolutionPolicy::Letterbox => virtual_resolution,
+            ResolutionPolicy::Expand => {
+                let scale = width_scale.min(height_scale);
+                [
+                    ((window_size[0] as f64 / scale).round() as u32).max(virtual_resolution[0]),
+                    ((window_size[1] as f64 / scale).round() as u32).max(virtual_resolution[1]),
+                ]
+            }
+            ResolutionPolicy::MatchWindow { pixel_scale } => {
+                let pixel_scale = (*pixel_scale).max(1) as f64 * scale_factor;
+                [
+                    ((window_size[0] as f64 / pixel_scale).ceil() as u32).max(1),
+                    ((window_size[1] as f64 / pixel_scale).ceil() as u32).max(1),
+                ]
+            }
+            ResolutionPolicy::IntegerScaled => {
+                let scale = (width_scale.min(height_scale).floor() as u32).max(1);
+                [window_size[0] / scale, window_size[1] / scale]
+            }
+        }
+    }
+
+    /// returns true if the render is shown at a whole number scale
+    pub(crate) fn is_integer_scaled(&self) -> bool {
+        matches!(self, ResolutionPolicy::IntegerScaled)
+    }
+}
+
+/// how many window pixels one render pixel is shown as, so the render fits in the window
+/// with `integer_scale` the scale is rounded down to a whole number, unless the render is too big to fit at 1
+pub(crate) fn get_present_scale(
+    resolution: [u32; 2],
+    window_size: [u32; 2],
+    integer_scale: bool,
+) -> f64 {
+    let scale = (window_size[0] as f64 / resolution[0] as f64)
+        .min(window_size[1] as f64 / resolution[1] as f64);
+
+    if integer_scale && scale >= 1.0 {
+        scale.floor()
+    } else {
+        scale
+    }
+}
+
+/// turn a position in the window into a position in the render, both relative to the center
+/// the window position goes from -0.5 to 0.5 on each axis, the render position is in render pixels
+/// this matches how the render is shown in the window, so it works for every policy
+pub(crate) fn window_to_render_position(
+    window_position: [f64; 2],
+    window_size: [u32; 2],
+    resolution: [u32; 2],
+    integer_scale: bool,
+) -> [f64; 2] {
+    let scale = get_present_scale(resolution, window_size, integer_scale);
+
+    [
+        window_position[0] * window_size[0] as f64 / scale,
+        window_position[1] * window_size[1] as f64 / scale,
+    ]
+}
+
+impl LumenpyxProgram {
+    /// set what happens to the render resolution when the window is resized
+    pub fn set_resolution_policy(&mut self, policy: ResolutionPolicy) {
+        self.resolution_policy = policy;
+        self.apply_resolution_policy();
+    }
+
+    pub fn get_resolution_policy(&self) -> ResolutionPolicy {
+        self.resolution_policy
+    }
+
+    /// set the resolution the resolution policy is based on
+    pub fn set_virtual_resolution(&mut self, resolution: [u32; 2]) {
+        self.virtual_resolution = resolution;
+        self.apply_resolution_policy();
+    }
+
+    pub fn get_virtual_resolution(&self) -> [u32; 2] {
+        self.virtual_resolution
+    }
+
+    /// sets the render resolution from the policy and the current window size
+    pub(crate) fn apply_resolution_policy(&mut self) {
+        let window_size = self.window.inner_size();
+
+        let resolution = self.resolution_policy.get_render_resolution(
+            self.virtual_resolution,
+            window_size.into(),
+            self.window.scale_factor(),
+        );
+
+        if resolution != self.internal_program.get_resolution() {
+            self.internal_program.set_resolution(resolution);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolution_policies() {
+        let virtual_resolution = [128, 96];
+        let window_size = [1920, 1080];
+
+        assert_eq!(
+            ResolutionPolicy::Letterbox.get_render_resolution(virtual_resolution, window_size, 1.0),
+            [128, 96]
+        );
+        // the height limits the scale to 11.25, so the width grows
+        assert_eq!(
+            ResolutionPolicy::Expand.get_render_resolution(virtual_resolution, window_size, 1.0),
+            [171, 96]
+        );
+        assert_eq!(
+            ResolutionPolicy::MatchWindow { pixel_scale: 4 }.get_render_resolution(
+                virtual_resolution,
+                window_size,
+                1.0
+            ),
+            [480, 270]
+        );
+        // a 4k screen at 200% has the same logical size
+        assert_eq!(
+            ResolutionPolicy::MatchWindow { pixel_scale: 4 }.get_render_resolution(
+                virtual_resolution,
+                [3840, 2160],
+                2.0
+            ),
+            [480, 270]
+        );
+        // a scale of 11 fits, so the render is the window divided by 11, and the rest is bars
+        assert_eq!(
+            ResolutionPolicy::IntegerScaled.get_render_resolution(
+                virtual_resolution,
+                window_size,
+                1.0
+            ),
+            [174, 98]
+        );
+        assert_eq!(
+            ResolutionPolicy::Expand.get_render_resolution(virtual_resolution, [0, 0], 1.0),
+            virtual_resolution
+        );
+    }
+
+    #[test]
+    fn test_window_to_render_position() {
+        // the render fills the window
+        assert_eq!(
+            window_to_render_position([0.5, -0.5], [200, 100], [100, 50], false),
+            [50.0, -25.0]
+        );
+        // the window is wider than the render, so the edge of the window is past the edge of the render
+        assert_eq!(
+            window_to_render_position([0.5, 0.5], [200, 50], [100, 50], false),
+            [100.0, 25.0]
+        );
+        // shown at a scale of 2 instead of 2.5, so the edge of the window is past the edge of the render
+        assert_eq!(
+            window_to_render_position([0.5, 0.5], [250, 125], [100, 50], true),
+            [62.5, 31.25]
+        );
+    }
+}
@@ -0,0 +1,723 @@
+//! Flat shapes made of triangles, for the primitives lumenpyx doesn't have
+//! outlines are turned into triangles on the cpu when the shape changes, then drawn with one flat color per buffer
+//! points are in pixels around the transform, with y going up
+
+use std::cell::RefCell;
+
+use glium::framebuffer::SimpleFrameBuffer;
+use glium::Surface;
+use lumenpyx::drawable_object::Drawable;
+use lumenpyx::{LumenpyxProgram, Transform};
+
+use crate::blending::ALPHA_BLEND;
+use crate::primitives::LineCap;
+
+const SHAPE_VERTEX_SHADER: &str = include_str!("../shaders/primitives/shape.vert");
+const SHAPE_FRAGMENT_SHADER: &str = include_str!("../shaders/primitives/shape.frag");
+
+/// a join sharper than this many half thicknesses is cut off instead of pointing out forever
+const MITER_LIMIT: f32 = 4.0;
+/// how many triangles make half a circle, for round caps
+const ROUND_CAP_SEGMENTS: usize = 8;
+/// about how long each straight piece of a curve is, in pixels
+const CURVE_SEGMENT_LENGTH: f32 = 4.0;
+const MAX_CURVE_SEGMENTS: usize = 256;
+
+#[derive(Clone, Copy)]
+struct ShapeVertex {
+    position: [f32; 2],
+}
+
+glium::implement_vertex!(ShapeVertex, position);
+
+/// triangles with one color, height and roughness
+pub(crate) struct Shape {
+    /// every three points is a triangle
+    triangles: Vec<[f32; 2]>,
+    /// None when the triangles changed since they were last drawn
+    vertex_buffer: RefCell<Option<glium::VertexBuffer<ShapeVertex>>>,
+    pub(crate) color: [f32; 4],
+    /// in pixels, the top of the shape is flat
+    pub(crate) height: f32,
+    pub(crate) roughness: f32,
+    pub(crate) shadow_strength: f32,
+    transform: Transform,
+}
+
+impl Shape {
+    pub(crate) fn new(color: [f32; 4]) -> Self {
+        Self {
+            triangles: vec![],
+            vertex_buffer: RefCell::new(None),
+            color,
+            height: 0.0,
+            roughness: 0.0,
+            shadow_strength: 0.5,
+            transform: Transform::default(),
+        }
+    }
+
+    pub(crate) fn set_triangles(&mut self, triangles: Vec<[f32; 2]>) {
+        self.triangles = triangles;
+        *self.vertex_buffer.get_mut() = None;
+    }
+
+    fn draw(
+        &self,
+        program: &LumenpyxProgram,
+        transform: &Transform,
+        framebuffer: &mut SimpleFrameBuffer,
+        color: [f32; 4],
+    ) {
+        if self.triangles.is_empty() {
+            return;
+        }
+
+        let (width, height) = framebuffer.get_dimensions();
+        let smallest_dim = width.min(height) as f32;
+
+        let mut vertex_buffer = self.vertex_buffer.borrow_mut();
+        if vertex_buffer.is_none() {
+            let vertices = self
+                .triangles
+                .iter()
+                .map(|position| ShapeVertex {
+                    position: *position,
+                })
+                .collect::<Vec<_>>();
+            *vertex_buffer = Some(
+                glium::VertexBuffer::new(&program.display, &vertices)
+                    .expect("failed to create vertex buffer for shape"),
+            );
+        }
+        let vertex_buffer = vertex_buffer
+            .as_ref()
+            .expect("the vertices were just built");
+
+        let shader = program
+            .get_shader("shape_shader")
+            .expect("failed to get shape shader");
+
+        let uniforms = glium::uniform! {
+            matrix: transform.get_matrix(),
+            pixel_scale: 2.0 / smallest_dim,
+            shape_color: color,
+        };
+
+        framebuffer
+            .draw(
+                vertex_buffer,
+                glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList),
+                shader,
+                &uniforms,
+                &glium::DrawParameters {
+                    blend: ALPHA_BLEND,
+                    ..Default::default()
+                },
+            )
+            .expect("failed to draw shape");
+    }
+}
+
+impl Drawable for Shape {
+    fn draw_albedo(
+        &self,
+        program: &LumenpyxProgram,
+        transform: &Transform,
+        albedo_framebuffer: &mut SimpleFrameBuffer,
+    ) {
+        self.draw(program, transform, albedo_framebuffer, self.color);
+    }
+
+    fn draw_height(
+        &self,
+        program: &LumenpyxProgram,
+        transform: &Transform,
+        height_framebuffer: &mut SimpleFrameBuffer,
+    ) {
+        // heights are stored as a fraction of the smallest side of the render, like lumenpyx's spheres
+        let (width, height) = height_framebuffer.get_dimensions();
+        let shape_height = self.height / width.min(height) as f32;
+
+        self.draw(
+            program,
+            transform,
+            height_framebuffer,
+            [shape_height, shape_height, shape_height, 1.0],
+        );
+    }
+
+    fn draw_normal(
+        &self,
+        program: &LumenpyxProgram,
+        transform: &Transform,
+        normal_framebuffer: &mut SimpleFrameBuffer,
+    ) {
+        // the top is flat so it faces the camera
+        self.draw(program, transform, normal_framebuffer, [0.0, 0.0, 1.0, 1.0]);
+    }
+
+    fn draw_roughness(
+        &self,
+        program: &LumenpyxProgram,
+        transform: &Transform,
+        roughness_framebuffer: &mut SimpleFrameBuffer,
+    ) {
+        let roughness = self.roughness;
+        self.draw(
+            program,
+            transform,
+            roughness_framebuffer,
+            [roughness, roughness, roughness, 1.0],
+        );
+    }
+
+    fn try_load_shaders(&self, program: &mut LumenpyxProgram) {
+        if program.get_shader("shape_shader").is_some() {
+            return;
+        }
+
+        let shader = glium::Program::from_source(
+            &program.display,
+            SHAPE_VERTEX_SHADER,
+            SHAPE_FRAGMENT_SHADER,
+            None,
+        )
+        .expect("failed to create shape shader");
+
+        program.add_shader(shader, "shape_shader");
+    }
+
+    fn get_transform(&self) -> Transform {
+        self.transform
+    }
+
+    fn get_recieve_shadows_strength(&self) -> f32 {
+        self.shadow_strength
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+}
+
+fn subtract(a: [f32; 2], b: [f32; 2]) -> [f32; 2] {
+    [a[0] - b[0], a[1] - b[1]]
+}
+
+fn add(a: [f32; 2], b: [f32; 2]) -> [f32; 2] {
+    [a[0] + b[0], a[1] + b[1]]
+}
+
+fn scale(a: [f32; 2], amount: f32) -> [f32; 2] {
+    [a[0] * amount, a[1] * amount]
+}
+
+fn cross(a: [f32; 2], b: [f32; 2]) -> f32 {
+    a[0] * b[1] - a[1] * b[0]
+}
+
+fn dot(a: [f32; 2], b: [f32; 2]) -> f32 {
+    a[0] * b[0] + a[1] * b[1]
+}
+
+fn normalize(a: [f32; 2]) -> [f32; 2] {
+    let length = dot(a, a).sqrt();
+    if length == 0.0 {
+        return [0.0, 0.0];
+    }
+    scale(a, 1.0 / length)
+}
+
+/// the direction to the left of a segment, one pixel long
+fn get_left_normal(from: [f32; 2], to: [f32; 2]) -> [f32; 2] {
+    let direction = normalize(subtract(to, from));
+    [-direction[1], direction[0]]
+}
+
+/// the points without repeats next to each other, including the last and first if it's closed
+fn remove_repeated_points(points: &[[f32; 2]], closed: bool) -> Vec<[f32; 2]> {
+    let mut unique_points: Vec<[f32; 2]> = vec![];
+    for point in points {
+        if unique_points.last() != Some(point) {
+            unique_points.push(*point);
+        }
+    }
+
+    if closed && unique_points.len() > 1 && unique_points.first() == unique_points.last() {
+        unique_points.pop();
+    }
+
+    unique_points
+}
+
+/// twice the area of a polygon, positive if the points go counterclockwise
+fn get_signed_area(points: &[[f32; 2]]) -> f32 {
+    (0..points.len())
+        .map(|i| cross(points[i], points[(i + 1) % points.len()]))
+        .sum()
+}
+
+fn is_in_triangle(point: [f32; 2], a: [f32; 2], b: [f32; 2], c: [f32; 2]) -> bool {
+    // counterclockwise triangles, points on the edges count as inside
+    cross(subtract(b, a), subtract(point, a)) >= 0.0
+        && cross(subtract(c, b), subtract(point, b)) >= 0.0
+        && cross(subtract(a, c), subtract(point, c)) >= 0.0
+}
+
+/// the triangles filling a polygon, which can be concave but shouldn't cross itself
+/// ear clipping, so it's fine for the few hundred points a shape usually has
+pub(crate) fn fill_polygon(points: &[[f32; 2]]) -> Vec<[f32; 2]> {
+    let points = remove_repeated_points(points, true);
+    if points.len() < 3 {
+        return vec![];
+    }
+
+    // the ears are found going counterclockwise
+    let mut remaining = (0..points.len()).collect::<Vec<_>>();
+    if get_signed_area(&points) < 0.0 {
+        remaining.reverse();
+    }
+
+    let mut triangles = vec![];
+    while remaining.len() > 3 {
+        let count = remaining.len();
+        let mut clipped = false;
+
+        for i in 0..count {
+            let previous = points[remaining[(i + count - 1) % count]];
+            let current = points[remaining[i]];
+            let next = points[remaining[(i + 1) % count]];
+
+            let turn = cross(subtract(current, previous), subtract(next, current));
+            if turn < 0.0 {
+                continue; // a reflex corner can't be an ear
+            }
+
+            // points in a straight line are removed without a triangle
+            let is_ear = turn == 0.0
+                || !remaining.iter().any(|index| {
+                    let point = points[*index];
+                    point != previous
+                        && point != current
+                        && point != next
+                        && is_in_triangle(point, previous, current, next)
+                });
+
+            if is_ear {
+                if turn > 0.0 {
+                    triangles.extend([previous, current, next]);
+                }
+                remaining.remove(i);
+                clipped = true;
+                break;
+            }
+        }
+
+        // the polygon crosses itself, so there's no ear left, a fan is better than nothing
+        if !clipped {
+            for i in 1..remaining.len() - 1 {
+                triangles.extend([
+                    points[remaining[0]],
+                    points[remaining[i]],
+                    points[remaining[i + 1]],
+                ]);
+            }
+            return triangles;
+        }
+    }
+
+    triangles.extend(remaining.iter().map(|index| points[*index]));
+    triangles
+}
+
+/// the triangles of a line through points, thickness pixels wide
+/// closed lines join the last point back to the first and have no caps
+pub(crate) fn stroke_polyline(
+    points: &[[f32; 2]],
+    thickness: f32,
+    cap: LineCap,
+    closed: bool,
+) -> Vec<[f32; 2]> {
+    let mut points = remove_repeated_points(points, closed);
+    let half_thickness = thickness / 2.0;
+    if points.len() < 2 || half_thickness <= 0.0 {
+        return vec![];
+    }
+
+    let closed = closed && points.len() > 2;
+    let count = points.len();
+
+    // square caps are the line made longer by half its thickness at both ends
+    if !closed && cap == LineCap::Square {
+        let start_direction = normalize(subtract(points[0], points[1]));
+        let end_direction = normalize(subtract(points[count - 1], points[count - 2]));
+        points[0] = add(points[0], scale(start_direction, half_thickness));
+        points[count - 1] = add(points[count - 1], scale(end_direction, half_thickness));
+    }
+
+    let mut triangles = vec![];
+
+    // where the edges are at each point, on the segment coming in and the one going out
+    let mut joints = vec![];
+    for i in 0..count {
+        let previous = (i > 0 || closed).then(|| points[(i + count - 1) % count]);
+        let next = (i + 1 < count || closed).then(|| points[(i + 1) % count]);
+
+        let joint = match (previous, next) {
+            (Some(previous), Some(next)) => {
+                let normal_in = get_left_normal(previous, points[i]);
+                let normal_out = get_left_normal(points[i], next);
+                let miter = normalize(add(normal_in, normal_out));
+                let miter_cosine = dot(miter, normal_in);
+
+                if miter_cosine > 1.0 / MITER_LIMIT {
+                    let miter = scale(miter, half_thickness / miter_cosine);
+                    StrokeJoint::shared(add(points[i], miter), subtract(points[i], miter))
+                } else {
+                    get_bevel_joint(previous, points[i], next, half_thickness)
+                }
+            }
+            (None, Some(next)) => {
+                let offset = scale(get_left_normal(points[i], next), half_thickness);
+                StrokeJoint::shared(add(points[i], offset), subtract(points[i], offset))
+            }
+            (Some(previous), None) => {
+                let offset = scale(get_left_normal(previous, points[i]), half_thickness);
+                StrokeJoint::shared(add(points[i], offset), subtract(points[i], offset))
+            }
+            (None, None) => unreachable!("there are at least two points"),
+        };
+
+        if let Some(bevel) = joint.bevel {
+            triangles.extend(bevel);
+        }
+        joints.push(joint);
+    }
+
+    let segment_count = if closed { count } else { count - 1 };
+    for i in 0..segment_count {
+        let next = (i + 1) % count;
+        let [start_left, start_right] = joints[i].edges_out;
+        let [end_left, end_right] = joints[next].edges_in;
+
+        triangles.extend([start_left, start_right, end_left]);
+        triangles.extend([end_left, start_right, end_right]);
+    }
+
+    if !closed && cap == LineCap::Round {
+        add_round_cap(
+            &mut triangles,
+            points[0],
+            subtract(joints[0].edges_out[0], points[0]),
+            half_thickness,
+        );
+        add_round_cap(
+            &mut triangles,
+            points[count - 1],
+            subtract(joints[count - 1].edges_in[1], points[count - 1]),
+            half_thickness,
+        );
+    }
+
+    triangles
+}
+
+/// where the left and right edges of a line are at one of its points
+struct StrokeJoint {
+    /// the end of the segment coming in
+    edges_in: [[f32; 2]; 2],
+    /// the start of the segment going out
+    edges_out: [[f32; 2]; 2],
+    /// the triangle filling the outside of a cut off corner
+    bevel: Option<[[f32; 2]; 3]>,
+}
+
+impl StrokeJoint {
+    fn shared(left: [f32; 2], right: [f32; 2]) -> Self {
+        Self {
+            edges_in: [left, right],
+            edges_out: [left, right],
+            bevel: None,
+        }
+    }
+}
+
+/// a corner too sharp to miter, the outside is cut off and the inside edges meet
+/// the segments share the inner point so nothing is drawn twice, which would show on see through lines
+fn get_bevel_joint(
+    previous: [f32; 2],
+    point: [f32; 2],
+    next: [f32; 2],
+    half_thickness: f32,
+) -> StrokeJoint {
+    let normal_in = get_left_normal(previous, point);
+    let normal_out = get_left_normal(point, next);
+    let offset_in = scale(normal_in, half_thickness);
+    let offset_out = scale(normal_out, half_thickness);
+
+    // the inner edges meet this far from the point, but not past the end of either segment
+    // if they would, the inside of the corner is left a little thin instead of folding over itself
+    let miter = normalize(add(normal_in, normal_out));
+    let miter_cosine = dot(miter, normal_in);
+    let shortest_segment = dot(subtract(point, previous), subtract(point, previous))
+        .min(dot(subtract(next, point), subtract(next, point)))
+        .sqrt();
+    let inner_length = if miter_cosine > 0.0 {
+        (half_thickness / miter_cosine).min(shortest_segment)
+    } else {
+        0.0
+    };
+
+    let turn = cross(subtract(point, previous), subtract(next, point));
+    if turn > 0.0 {
+        // turning left, so the left side is the inside
+        let inner = add(point, scale(miter, inner_length));
+        let outer_in = subtract(point, offset_in);
+        let outer_out = subtract(point, offset_out);
+        StrokeJoint {
+            edges_in: [inner, outer_in],
+            edges_out: [inner, outer_out],
+            bevel: Some([inner, outer_in, outer_out]),
+        }
+    } else {
+        let inner = subtract(point, scale(miter, inner_length));
+        let outer_in = add(point, offset_in);
+        let outer_out = add(point, offset_out);
+        StrokeJoint {
+            edges_in: [outer_in, inner],
+            edges_out: [outer_out, inner],
+            bevel: Some([inner, outer_in, outer_out]),
+        }
+    }
+}
+
+/// half a circle on the outside of the end of a line, going from the offset to its opposite
+fn add_round_cap(triangles: &mut Vec<[f32; 2]>, center: [f32; 2], offset: [f32; 2], radius: f32) {
+    let start_angle = offset[1].atan2(offset[0]);
+
+    for segment in 0..ROUND_CAP_SEGMENTS {
+        let angle = |segment: usize| {
+            start_angle + std::f32::consts::PI * segment as f32 / ROUND_CAP_SEGMENTS as f32
+        };
+        let (sin_a, cos_a) = angle(segment).sin_cos();
+        let (sin_b, cos_b) = angle(segment + 1).sin_cos();
+
+        triangles.extend([
+            center,
+            add(center, [cos_a * radius, sin_a * radius]),
+            add(center, [cos_b * radius, sin_b * radius]),
+        ]);
+    }
+}
+
+/// how many straight pieces a curve is made of, so big curves still look round
+fn get_curve_segment_count(radius: f32, sweep: f32) -> usize {
+    let length = radius.abs() * sweep.abs();
+    ((length / CURVE_SEGMENT_LENGTH).ceil() as usize).clamp(4, MAX_CURVE_SEGMENTS)
+}
+
+/// points along an elliptical arc from the start angle to the end angle, in radians counterclockwise from the right
+fn get_arc_points(
+    center: [f32; 2],
+    radius: [f32; 2],
+    start_angle: f32,
+    end_angle: f32,
+) -> Vec<[f32; 2]> {
+    let sweep = end_angle - start_angle;
+    let segments = get_curve_segment_count(radius[0].max(radius[1]), sweep);
+
+    (0..=segments)
+        .map(|segment| {
+            let angle = start_angle + sweep * segment as f32 / segments as f32;
+            let (sin, cos) = angle.sin_cos();
+            [center[0] + cos * radius[0], center[1] + sin * radius[1]]
+        })
+        .collect()
+}
+
+pub(crate) fn fill_ellipse(radius_x: f32, radius_y: f32) -> Vec<[f32; 2]> {
+    let mut points = get_arc_points([0.0, 0.0], [radius_x, radius_y], 0.0, std::f32::consts::TAU);
+    points.pop(); // the last point is the first one again
+
+    fill_polygon(&points)
+}
+
+/// a rectangle centered on the transform with quarter circles for corners
+/// the corner radius is made smaller if it doesn't fit
+pub(crate) fn fill_rounded_rectangle(width: f32, height: f32, corner_radius: f32) -> Vec<[f32; 2]> {
+    let [half_width, half_height] = [width.abs() / 2.0, height.abs() / 2.0];
+    let radius = corner_radius.clamp(0.0, half_width.min(half_height));
+
+    if radius == 0.0 {
+        return fill_polygon(&[
+            [-half_width, -half_height],
+            [half_width, -half_height],
+            [half_width, half_height],
+            [-half_width, half_height],
+        ]);
+    }
+
+    let quarter_turn = std::f32::consts::FRAC_PI_2;
+    let corners = [
+        [half_width - radius, half_height - radius],
+        [-half_width + radius, half_height - radius],
+        [-half_width + radius, -half_height + radius],
+        [half_width - radius, -half_height + radius],
+    ];
+
+    let mut points = vec![];
+    for (i, corner) in corners.into_iter().enumerate() {
+        let start_angle = quarter_turn * i as f32;
+        points.extend(get_arc_points(
+            corner,
+            [radius, radius],
+            start_angle,
+            start_angle + quarter_turn,
+        ));
+    }
+
+    fill_polygon(&points)
+}
+
+/// a slice of a circle between two angles, in radians counterclockwise from the right
+/// with an inner radius it's a piece of a ring, without one it's a pie slice
+pub(crate) fn fill_arc(
+    radius: f32,
+    inner_radius: f32,
+    start_angle: f32,
+    end_angle: f32,
+) -> Vec<[f32; 2]> {
+    let sweep = (end_angle - start_angle).clamp(-std::f32::consts::TAU, std::f32::consts::TAU);
+    let inner_radius = inner_radius.clamp(0.0, radius);
+    if radius <= 0.0 || sweep == 0.0 || inner_radius == radius {
+        return vec![];
+    }
+
+    let outer = get_arc_points([0.0, 0.0], [radius; 2], start_angle, start_angle + sweep);
+    let mut triangles = vec![];
+
+    if inner_radius == 0.0 {
+        for pair in outer.windows(2) {
+            triangles.extend([[0.0, 0.0], pair[0], pair[1]]);
+        }
+        return triangles;
+    }
+
+    // the inner edge has as many points as the outer one so they can be paired up
+    let inner = outer
+        .iter()
+        .map(|point| scale(*point, inner_radius / radius))
+        .collect::<Vec<_>>();
+
+    for i in 0..outer.len() - 1 {
+        triangles.extend([inner[i], outer[i], outer[i + 1]]);
+        triangles.extend([inner[i], outer[i + 1], inner[i + 1]]);
+    }
+
+    triangles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the total area of a list of triangles, however they're wound
+    fn get_area(triangles: &[[f32; 2]]) -> f32 {
+        triangles
+            .chunks(3)
+            .map(|triangle| get_signed_area(triangle).abs() / 2.0)
+            .sum()
+    }
+
+    #[test]
+    fn test_fill_polygon() {
+        // an L shape, clockwise and with the first point repeated at the end
+        let l_shape = [
+            [0.0, 0.0],
+            [0.0, 2.0],
+            [1.0, 2.0],
+            [1.0, 1.0],
+            [2.0, 1.0],
+            [2.0, 0.0],
+            [0.0, 0.0],
+        ];
+        let triangles = fill_polygon(&l_shape);
+
+        assert_eq!(triangles.len(), 4 * 3);
+        assert!((get_area(&triangles) - 3.0).abs() < 0.001);
+        assert!(fill_polygon(&[[0.0, 0.0], [1.0, 1.0]]).is_empty());
+    }
+
+    #[test]
+    fn test_stroke_polyline() {
+        let line = [[0.0, 0.0], [10.0, 0.0]];
+
+        let butt = stroke_polyline(&line, 2.0, LineCap::Butt, false);
+        assert!((get_area(&butt) - 20.0).abs() < 0.001);
+
+        let square = stroke_polyline(&line, 2.0, LineCap::Square, false);
+        assert!((get_area(&square) - 24.0).abs() < 0.001);
+
+        // a closed square outline, the miters meet exactly at the corners
+        let outline = [[0.0, 0.0], [10.0, 0.0], [10.0, 10.0], [0.0, 10.0]];
+        let closed = stroke_polyline(&outline, 2.0, LineCap::Butt, true);
+        assert!((get_area(&closed) - (12.0 * 12.0 - 8.0 * 8.0)).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_stroke_polyline_no_overlap() {
+        // a corner sharp enough to be cut off, see through lines would be darker where triangles overlap
+        let open = [[0.0, 0.0], [20.0, 0.0], [0.0, 4.0], [-20.0, 20.0]];
+        let closed = [[0.0, 0.0], [40.0, 0.0], [0.0, 6.0]];
+
+        for (points, closed) in [(&open[..], false), (&closed[..], true)] {
+            let triangles = stroke_polyline(points, 3.0, LineCap::Round, closed);
+
+            for x in -250..450 {
+                for y in -100..250 {
+                    // off the grid a little so the samples don't land on edges
+                    let point = [x as f32 * 0.1 + 0.013, y as f32 * 0.1 + 0.007];
+                    let covered = triangles
+                        .chunks(3)
+                        .filter(|triangle| {
+                            let [a, b, c] = [triangle[0], triangle[1], triangle[2]];
+                            is_in_triangle(point, a, b, c) || is_in_triangle(point, a, c, b)
+                        })
+                        .count();
+                    assert!(covered <= 1, "{:?} is covered {} times", point, covered);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_curved_shapes() {
+        use std::f32::consts::PI;
+
+        // the curves are made of straight pieces, so the areas are a little smaller
+        let close_to = |area: f32, expected: f32| area <= expected && area > expected * 0.98;
+
+        assert!(close_to(
+            get_area(&fill_ellipse(40.0, 20.0)),
+            PI * 40.0 * 20.0
+        ));
+
+        let rounded = fill_rounded_rectangle(100.0, 50.0, 10.0);
+        assert!(close_to(
+            get_area(&rounded),
+            100.0 * 50.0 - (4.0 - PI) * 100.0
+        ));
+        // the corners can't be rounder than half the shortest side
+        assert_eq!(
+            fill_rounded_rectangle(10.0, 10.0, 50.0),
+            fill_rounded_rectangle(10.0, 10.0, 5.0)
+        );
+
+        let ring = fill_arc(30.0, 20.0, 0.0, 2.0 * PI);
+        assert!(close_to(get_area(&ring), PI * (30.0 * 30.0 - 20.0 * 20.0)));
+
+        let quarter = fill_arc(30.0, 0.0, 0.0, PI / 2.0);
+        assert!(close_to(get_area(&quarter), PI * 30.0 * 30.0 / 4.0));
+    }
+}
@@ -0,0 +1,787 @@
+//! Tilemaps, a grid of tiles cut from one tileset and drawn as a single drawable
+//! the grid is split into chunks, each chunk is built once and only drawn when it's on screen
+//! so a whole level is one entity instead of thousands of sprites
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+use glium::framebuffer::SimpleFrameBuffer;
+use glium::uniforms::Sampler;
+use glium::Surface;
+use lumenpyx::drawable_object::Drawable;
+use lumenpyx::primitives::{NormalInput, TextureInput};
+use lumenpyx::{LumenpyxProgram, TextureHandle, Transform};
+
+use crate::blending::ALPHA_BLEND;
+use crate::pipeline::BUFFER_BEHAVIOR;
+
+pub mod tiled;
+
+const TILEMAP_VERTEX_SHADER: &str = include_str!("../shaders/primitives/tilemap.vert");
+const TILEMAP_FRAGMENT_SHADER: &str = include_str!("../shaders/primitives/tilemap.frag");
+
+/// how many tiles wide and high a chunk is
+pub const CHUNK_SIZE: u32 = 16;
+
+/// a corner of a textured triangle, in pixels, also used by nine slice sprites
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct TileVertex {
+    pub(crate) position: [f32; 2],
+    pub(crate) tex_coords: [f32; 2],
+    /// a 2x2 matrix, column by column, that turns and flips the sampled normal the same way as the texture
+    pub(crate) normal_transform: [f32; 4],
+}
+
+glium::implement_vertex!(TileVertex, position, tex_coords, normal_transform);
+
+impl TileVertex {
+    /// a corner that isn't turned or flipped
+    pub(crate) fn new(position: [f32; 2], tex_coords: [f32; 2]) -> Self {
+        Self {
+            position,
+            tex_coords,
+            normal_transform: [1.0, 0.0, 0.0, 1.0],
+        }
+    }
+}
+
+/// A texture set cut into a grid of tiles, numbered left to right then top to bottom starting at 0
+/// the albedo, height, roughness and normal textures are loaded like a `Sprite`'s and cut the same way
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tileset {
+    albedo: TextureHandle,
+    height: TextureHandle,
+    roughness: TextureHandle,
+    normal: TextureHandle,
+    texture_size: [u32; 2],
+    tile_size: [u32; 2],
+    /// pixels around the edge of the texture
+    margin: u32,
+    /// pixels between tiles
+    spacing: u32,
+}
+
+impl Tileset {
+    pub fn new(
+        albedo: TextureInput,
+        height: TextureInput,
+        roughness: TextureInput,
+        normal: NormalInput,
+        tile_size: [u32; 2],
+        program: &mut LumenpyxProgram,
+    ) -> Self {
+        let (_, albedo, height, roughness, normal) = lumenpyx::primitives::Sprite::new(
+            albedo,
+            height,
+            roughness,
+            normal,
+            program,
+            Transform::default(),
+        );
+
+        let texture = program
+            .get_texture_from_handle(&albedo)
+            .expect("failed to get tileset texture");
+        let texture_size = [
+            texture.get_width(),
+            texture
+                .get_height()
+                .expect("failed to get height of tileset texture"),
+        ];
+
+        Self {
+            albedo,
+            height,
+            roughness,
+            normal,
+            texture_size,
+            tile_size,
+            margin: 0,
+            spacing: 0,
+        }
+    }
+
+    /// for tilesets with empty pixels around the edge and between the tiles
+    pub fn with_spacing(mut self, margin: u32, spacing: u32) -> Self {
+        self.margin = margin;
+        self.spacing = spacing;
+        self
+    }
+
+    pub fn get_tile_size(&self) -> [u32; 2] {
+        self.tile_size
+    }
+
+    pub fn get_columns(&self) -> u32 {
+        self.get_tiles_along(0)
+    }
+
+    pub fn get_rows(&self) -> u32 {
+        self.get_tiles_along(1)
+    }
+
+    pub fn get_tile_count(&self) -> u32 {
+        self.get_columns() * self.get_rows()
+    }
+
+    /// the albedo, height, roughness and normal textures in that order
+    pub fn get_textures(&self) -> [TextureHandle; 4] {
+        [self.albedo, self.height, self.roughness, self.normal]
+    }
+
+    pub fn free_all_textures(&self, program: &mut LumenpyxProgram) {
+        for texture in self.get_textures() {
+            program.remove_texture(&texture);
+        }
+    }
+
+    fn get_tiles_along(&self, axis: usize) -> u32 {
+        let usable = self.texture_size[axis].saturating_sub(self.margin * 2) + self.spacing;
+        usable / (self.tile_size[axis] + self.spacing).max(1)
+    }
+
+    /// the texture coordinates of a tile's corners: top left, top right, bottom right, bottom left
+    /// None if the tileset doesn't have that many tiles
+    fn get_tile_corners(&self, index: u32) -> Option<[[f32; 2]; 4]> {
+        let columns = self.get_columns();
+        if columns == 0 || index >= self.get_tile_count() {
+            return None;
+        }
+
+        let [width, height] = self.texture_size.map(|size| size as f32);
+        let left = (self.margin + (index % columns) * (self.tile_size[0] + self.spacing)) as f32;
+        let top = (self.margin + (index / columns) * (self.tile_size[1] + self.spacing)) as f32;
+        let right = left + self.tile_size[0] as f32;
+        let bottom = top + self.tile_size[1] as f32;
+
+        // textures are stored bottom row first
+        let [left, right] = [left / width, right / width];
+        let [top, bottom] = [1.0 - top / height, 1.0 - bottom / height];
+
+        Some([[left, top], [right, top], [right, bottom], [left, bottom]])
+    }
+}
+
+/// How far a tile is turned, clockwise
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TileRotation {
+    #[default]
+    None,
+    Clockwise90,
+    Clockwise180,
+    Clockwise270,
+}
+
+impl TileRotation {
+    fn get_quarter_turns(self) -> usize {
+        match self {
+            TileRotation::None => 0,
+            TileRotation::Clockwise90 => 1,
+            TileRotation::Clockwise180 => 2,
+            TileRotation::Clockwise270 => 3,
+        }
+    }
+}
+
+/// One cell of a tilemap, the tile is turned first and then flipped
+/// the normals are turned and flipped with the tile, so light still comes from the right side
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Tile {
+    pub index: u32,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    pub rotation: TileRotation,
+}
+
+impl Tile {
+    pub fn new(index: u32) -> Self {
+        Self {
+            index,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_flip(mut self, flip_x: bool, flip_y: bool) -> Self {
+        self.flip_x = flip_x;
+        self.flip_y = flip_y;
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: TileRotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// which corner of the tileset tile is shown at each corner of the cell
+    fn get_corners(&self, tile_corners: [[f32; 2]; 4]) -> [[f32; 2]; 4] {
+        // the corners go around clockwise, so turning the tile moves them along
+        let turns = self.rotation.get_quarter_turns();
+        let mut corners = [0, 1, 2, 3].map(|corner| tile_corners[(corner + 4 - turns) % 4]);
+
+        if self.flip_x {
+            corners.swap(0, 1);
+            corners.swap(2, 3);
+        }
+        if self.flip_y {
+            corners.swap(0, 3);
+            corners.swap(1, 2);
+        }
+
+        corners
+    }
+
+    /// where the tile's normals point once it's turned and flipped, as a 2x2 matrix column by column
+    fn get_normal_transform(&self) -> [f32; 4] {
+        // where the right and up directions of the tile end up, y goes up
+        let mut columns = [[1.0, 0.0], [0.0, 1.0]];
+        for _ in 0..self.rotation.get_quarter_turns() {
+            columns = columns.map(|[x, y]| [y, -x]);
+        }
+
+        if self.flip_x {
+            columns = columns.map(|[x, y]| [-x, y]);
+        }
+        if self.flip_y {
+            columns = columns.map(|[x, y]| [x, -y]);
+        }
+
+        [columns[0][0], columns[0][1], columns[1][0], columns[1][1]]
+    }
+}
+
+/// The tiles an animated tile cycles through, each with how long it's shown in seconds
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileAnimation {
+    frames: Vec<(u32, f32)>,
+}
+
+impl TileAnimation {
+    pub fn new(frames: Vec<(u32, f32)>) -> Self {
+        Self { frames }
+    }
+
+    /// every frame shown for the same time
+    pub fn from_indices(indices: Vec<u32>, frame_duration: f32) -> Self {
+        Self::new(
+            indices
+                .into_iter()
+                .map(|index| (index, frame_duration))
+                .collect(),
+        )
+    }
+
+    pub fn get_frames(&self) -> &[(u32, f32)] {
+        &self.frames
+    }
+
+    /// the tile shown at a time since the start, the animation loops
+    fn get_index_at(&self, time: f64) -> Option<u32> {
+        let length: f64 = self
+            .frames
+            .iter()
+            .map(|(_, duration)| *duration as f64)
+            .sum();
+        if length <= 0.0 {
+            return self.frames.first().map(|(index, _)| *index);
+        }
+
+        let mut time = time.rem_euclid(length);
+        for (index, duration) in &self.frames {
+            if time < *duration as f64 {
+                return Some(*index);
+            }
+            time -= *duration as f64;
+        }
+
+        self.frames.last().map(|(index, _)| *index)
+    }
+}
+
+#[derive(Default)]
+struct Chunk {
+    /// None when the tiles changed since it was last drawn
+    vertex_buffer: RefCell<Option<glium::VertexBuffer<TileVertex>>>,
+    has_animated_tiles: Cell<bool>,
+}
+
+/// A grid of tiles from one tileset, the top left corner is at the transform and rows go down
+/// tiles bigger than a cell stick out of its top and right, like in Tiled
+pub struct Tilemap {
+    tileset: Tileset,
+    /// in tiles
+    width: u32,
+    height: u32,
+    /// in pixels, the tileset's tile size unless it's set
+    cell_size: [u32; 2],
+    /// row by row, starting at the top
+    tiles: Vec<Option<Tile>>,
+    animations: HashMap<u32, TileAnimation>,
+    /// what every animated tile shows right now, so chunks are only rebuilt when a frame changes
+    animation_frames: HashMap<u32, u32>,
+    chunks: Vec<Chunk>,
+    shadow_strength: f32,
+    transform: Transform,
+}
+
+impl Tilemap {
+    /// an empty tilemap, width and height are in tiles
+    pub fn new(tileset: Tileset, width: u32, height: u32) -> Self {
+        let chunk_count =
+            width.div_ceil(CHUNK_SIZE) as usize * height.div_ceil(CHUNK_SIZE) as usize;
+        let tile_count = (width as usize)
+            .checked_mul(height as usize)
+            .expect("failed to make tilemap, it has too many tiles");
+
+        Self {
+            tileset,
+            width,
+            height,
+            cell_size: tileset.tile_size,
+            tiles: vec![None; tile_count],
+            animations: HashMap::new(),
+            animation_frames: HashMap::new(),
+            chunks: (0..chunk_count).map(|_| Chunk::default()).collect(),
+            shadow_strength: 0.5,
+            transform: Transform::default(),
+        }
+    }
+
+    /// a tilemap from tile indices row by row starting at the top, None is an empty cell
+    /// a last row that isn't full is filled with empty cells
+    pub fn from_indices(tileset: Tileset, width: u32, indices: &[Option<u32>]) -> Self {
+        let width = width.max(1);
+        let height = (indices.len() as u32).div_ceil(width);
+        let mut tilemap = Self::new(tileset, width, height);
+
+        for (i, index) in indices.iter().enumerate() {
+            tilemap.tiles[i] = index.map(Tile::new);
+        }
+
+        tilemap
+    }
+
+    /// how far apart the cells are in pixels, tiles are drawn at the bottom left of their cell
+    pub fn with_cell_size(mut self, cell_size: [u32; 2]) -> Self {
+        self.cell_size = cell_size;
+        self.rebuild_all_chunks();
+        self
+    }
+
+    pub fn get_cell_size(&self) -> [u32; 2] {
+        self.cell_size
+    }
+
+    pub fn get_tileset(&self) -> &Tileset {
+        &self.tileset
+    }
+
+    /// the width and height in tiles
+    pub fn get_size(&self) -> [u32; 2] {
+        [self.width, self.height]
+    }
+
+    /// None if the cell is empty or outside the map
+    pub fn get_tile(&self, x: u32, y: u32) -> Option<Tile> {
+        self.get_tile_index(x, y).and_then(|i| self.tiles[i])
+    }
+
+    /// cells outside the map are ignored
+    pub fn set_tile(&mut self, x: u32, y: u32, tile: Option<Tile>) {
+        let Some(i) = self.get_tile_index(x, y) else {
+            return;
+        };
+
+        if self.tiles[i] != tile {
+            self.tiles[i] = tile;
+            let chunk = self.get_chunk_index(x, y);
+            *self.chunks[chunk].vertex_buffer.get_mut() = None;
+        }
+    }
+
+    pub fn fill(&mut self, tile: Option<Tile>) {
+        self.tiles.fill(tile);
+        self.rebuild_all_chunks();
+    }
+
+    /// make every cell with this tile cycle through the animation
+    pub fn set_animation(&mut self, index: u32, animation: TileAnimation) {
+        self.animations.insert(index, animation);
+        self.animation_frames.clear();
+        self.rebuild_all_chunks();
+    }
+
+    pub fn remove_animation(&mut self, index: u32) {
+        self.animations.remove(&index);
+        self.animation_frames.remove(&index);
+        self.rebuild_all_chunks();
+    }
+
+    pub fn set_shadow_strength(&mut self, shadow_strength: f32) {
+        self.shadow_strength = shadow_strength;
+    }
+
+    /// moves the animated tiles to the frame for this time, in seconds since the game started
+    pub(crate) fn set_total_time(&mut self, total_time: f64) {
+        let mut changed = false;
+        for (index, animation) in &self.animations {
+            let frame = animation.get_index_at(total_time).unwrap_or(*index);
+            if self.animation_frames.insert(*index, frame) != Some(frame) {
+                changed = true;
+            }
+        }
+
+        if changed {
+            for chunk in &mut self.chunks {
+                if chunk.has_animated_tiles.get() {
+                    *chunk.vertex_buffer.get_mut() = None;
+                }
+            }
+        }
+    }
+
+    fn get_tile_index(&self, x: u32, y: u32) -> Option<usize> {
+        (x < self.width && y < self.height).then(|| (y * self.width + x) as usize)
+    }
+
+    fn get_chunk_index(&self, x: u32, y: u32) -> usize {
+        ((y / CHUNK_SIZE) * self.width.div_ceil(CHUNK_SIZE) + x / CHUNK_SIZE) as usize
+    }
+
+    fn rebuild_all_chunks(&mut self) {
+        for chunk in &mut self.chunks {
+            *chunk.vertex_buffer.get_mut() = None;
+        }
+    }
+
+    /// the cells a chunk covers, as left, top, right and bottom in tiles, the right and bottom aren't included
+    fn get_chunk_cells(&self, chunk: usize) -> [u32; 4] {
+        let chunk_columns = self.width.div_ceil(CHUNK_SIZE);
+        let left = (chunk as u32 % chunk_columns) * CHUNK_SIZE;
+        let top = (chunk as u32 / chunk_columns) * CHUNK_SIZE;
+
+        [
+            left,
+            top,
+            (left + CHUNK_SIZE).min(self.width),
+            (top + CHUNK_SIZE).min(self.height),
+        ]
+    }
+
+    /// the two triangles of every tile in a chunk, and whether any of them are animated
+    fn get_chunk_vertices(&self, chunk: usize) -> (Vec<TileVertex>, bool) {
+        let [tile_width, tile_height] = self.tileset.tile_size.map(|size| size as f32);
+        let [cell_width, cell_height] = self.cell_size.map(|size| size as f32);
+        let [left, top, right, bottom] = self.get_chunk_cells(chunk);
+
+        let mut vertices = vec![];
+        let mut has_animated_tiles = false;
+
+        for y in top..bottom {
+            for x in left..right {
+                let Some(tile) = self.get_tile(x, y) else {
+                    continue;
+                };
+
+                let mut index = tile.index;
+                if self.animations.contains_key(&tile.index) {
+                    has_animated_tiles = true;
+                    index = self
+                        .animation_frames
+                        .get(&tile.index)
+                        .copied()
+                        .unwrap_or(tile.index);
+                }
+
+                let Some(tile_corners) = self.tileset.get_tile_corners(index) else {
+                    continue; // not in the tileset
+                };
+                let tex_coords = tile.get_corners(tile_corners);
+                let normal_transform = tile.get_normal_transform();
+
+                // y goes up on screen, and rows go down
+                let cell_left = x as f32 * cell_width;
+                let cell_bottom = -(y as f32 + 1.0) * cell_height;
+                let positions = [
+                    [cell_left, cell_bottom + tile_height],
+                    [cell_left + tile_width, cell_bottom + tile_height],
+                    [cell_left + tile_width, cell_bottom],
+                    [cell_left, cell_bottom],
+                ];
+
+                for corner in [0, 1, 2, 0, 2, 3] {
+                    vertices.push(TileVertex {
+                        position: positions[corner],
+                        tex_coords: tex_coords[corner],
+                        normal_transform,
+                    });
+                }
+            }
+        }
+
+        (vertices, has_animated_tiles)
+    }
+
+    /// whether any of a chunk is inside the framebuffer
+    fn is_chunk_visible(&self, chunk: usize, matrix: &[[f32; 4]; 4], pixel_scale: f32) -> bool {
+        let [cell_width, cell_height] = self.cell_size.map(|size| size as f32);
+        let [left, top, right, bottom] = self.get_chunk_cells(chunk);
+
+        // tiles bigger than their cells stick out of the top and right of the chunk
+        let [overhang_x, overhang_y] = [0, 1]
+            .map(|axis| self.tileset.tile_size[axis].saturating_sub(self.cell_size[axis]) as f32);
+        let [left, right] = [
+            left as f32 * cell_width,
+            right as f32 * cell_width + overhang_x,
+        ];
+        let [top, bottom] = [
+            -(top as f32) * cell_height + overhang_y,
+            -(bottom as f32) * cell_height,
+        ];
+
+        let corners = [[left, top], [right, top], [right, bottom], [left, bottom]];
+
+        // where the corners end up on screen, from -1 to 1
+        let screen_corners = corners.map(|[x, y]| {
+            let [x, y] = [x * pixel_scale, y * pixel_scale];
+            let w = matrix[0][3] * x + matrix[1][3] * y + matrix[3][3];
+            [
+                (matrix[0][0] * x + matrix[1][0] * y + matrix[3][0]) / w,
+                (matrix[0][1] * x + matrix[1][1] * y + matrix[3][1]) / w,
+            ]
+        });
+
+        (0..2).all(|axis| {
+            let min = screen_corners
+                .iter()
+                .map(|corner| corner[axis])
+                .fold(f32::INFINITY, f32::min);
+            let max = screen_corners
+                .iter()
+                .map(|corner| corner[axis])
+                .fold(f32::NEG_INFINITY, f32::max);
+            max >= -1.0 && min <= 1.0
+        })
+    }
+
+    fn draw(
+        &self,
+        program: &LumenpyxProgram,
+        transform: &Transform,
+        framebuffer: &mut SimpleFrameBuffer,
+        texture: TextureHandle,
+        is_normal_map: bool,
+    ) {
+        let texture = program
+            .get_texture_from_handle(&texture)
+            .expect("failed to get tileset texture");
+        let shader = program
+            .get_shader("tilemap_shader")
+            .expect("failed to get tilemap shader");
+
+        let (width, height) = framebuffer.get_dimensions();
+        let pixel_scale = 2.0 / width.min(height) as f32;
+        let matrix = transform.get_matrix();
+
+        let uniforms = glium::uniform! {
+            matrix: matrix,
+            pixel_scale: pixel_scale,
+            image: Sampler(texture, BUFFER_BEHAVIOR),
+            is_normal_map: is_normal_map,
+        };
+
+        for (i, chunk) in self.chunks.iter().enumerate() {
+            if !self.is_chunk_visible(i, &matrix, pixel_scale) {
+                continue;
+            }
+
+            let mut vertex_buffer = chunk.vertex_buffer.borrow_mut();
+            if vertex_buffer.is_none() {
+                let (vertices, has_animated_tiles) = self.get_chunk_vertices(i);
+                chunk.has_animated_tiles.set(has_animated_tiles);
+                *vertex_buffer = Some(
+                    glium::VertexBuffer::new(&program.display, &vertices)
+                        .expect("failed to create vertex buffer for tilemap"),
+                );
+            }
+
+            let vertex_buffer = vertex_buffer.as_ref().expect("the chunk was just built");
+            if vertex_buffer.len() == 0 {
+                continue;
+            }
+
+            framebuffer
+                .draw(
+                    vertex_buffer,
+                    glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList),
+                    shader,
+                    &uniforms,
+                    &glium::DrawParameters {
+                        blend: ALPHA_BLEND,
+                        ..Default::default()
+                    },
+                )
+                .expect("failed to draw tilemap");
+        }
+    }
+}
+
+/// the shader for drawing textured triangles, shared with nine slice sprites
+pub(crate) fn load_tilemap_shader(program: &mut LumenpyxProgram) {
+    if program.get_shader("tilemap_shader").is_some() {
+        return;
+    }
+
+    let shader = glium::Program::from_source(
+        &program.display,
+        TILEMAP_VERTEX_SHADER,
+        TILEMAP_FRAGMENT_SHADER,
+        None,
+    )
+    .expect("failed to create tilemap shader");
+
+    program.add_shader(shader, "tilemap_shader");
+}
+
+impl Drawable for Tilemap {
+    fn draw_albedo(
+        &self,
+        program: &LumenpyxProgram,
+        transform: &Transform,
+        albedo_framebuffer: &mut SimpleFrameBuffer,
+    ) {
+        self.draw(
+            program,
+            transform,
+            albedo_framebuffer,
+            self.tileset.albedo,
+            false,
+        );
+    }
+
+    fn draw_height(
+        &self,
+        program: &LumenpyxProgram,
+        transform: &Transform,
+        height_framebuffer: &mut SimpleFrameBuffer,
+    ) {
+        self.draw(
+            program,
+            transform,
+            height_framebuffer,
+            self.tileset.height,
+            false,
+        );
+    }
+
+    fn draw_normal(
+        &self,
+        program: &LumenpyxProgram,
+        transform: &Transform,
+        normal_framebuffer: &mut SimpleFrameBuffer,
+    ) {
+        self.draw(
+            program,
+            transform,
+            normal_framebuffer,
+            self.tileset.normal,
+            true,
+        );
+    }
+
+    fn draw_roughness(
+        &self,
+        program: &LumenpyxProgram,
+        transform: &Transform,
+        roughness_framebuffer: &mut SimpleFrameBuffer,
+    ) {
+        self.draw(
+            program,
+            transform,
+            roughness_framebuffer,
+            self.tileset.roughness,
+            false,
+        );
+    }
+
+    fn try_load_shaders(&self, program: &mut LumenpyxProgram) {
+        load_tilemap_shader(program);
+    }
+
+    fn get_transform(&self) -> Transform {
+        self.transform
+    }
+
+    fn get_recieve_shadows_strength(&self) -> f32 {
+        self.shadow_strength
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tile_corners() {
+        let corners = [[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]];
+        let [top_left, top_right, bottom_right, bottom_left] = corners;
+
+        // turned clockwise, the bottom left of the tile is now at the top left
+        let turned = Tile::new(0).with_rotation(TileRotation::Clockwise90);
+        assert_eq!(turned.get_corners(corners)[0], bottom_left);
+
+        let flipped = Tile::new(0).with_flip(true, false);
+        assert_eq!(
+            flipped.get_corners(corners),
+            [top_right, top_left, bottom_left, bottom_right]
+        );
+
+        let both = Tile::new(0).with_flip(true, true);
+        assert_eq!(
+            both.get_corners(corners),
+            Tile::new(0)
+                .with_rotation(TileRotation::Clockwise180)
+                .get_corners(corners)
+        );
+    }
+
+    #[test]
+    fn test_tile_normal_transform() {
+        let turn = |tile: Tile, [x, y]: [f32; 2]| {
+            let [a, b, c, d] = tile.get_normal_transform();
+            [a * x + c * y, b * x + d * y]
+        };
+
+        assert_eq!(turn(Tile::new(0), [1.0, 0.0]), [1.0, 0.0]);
+        // a normal pointing right points down once the tile is turned clockwise
+        let turned = Tile::new(0).with_rotation(TileRotation::Clockwise90);
+        assert_eq!(turn(turned, [1.0, 0.0]), [0.0, -1.0]);
+        assert_eq!(turn(turned, [0.0, 1.0]), [1.0, 0.0]);
+
+        let flipped = Tile::new(0).with_flip(true, false);
+        assert_eq!(turn(flipped, [1.0, 0.0]), [-1.0, 0.0]);
+        assert_eq!(turn(flipped, [0.0, 1.0]), [0.0, 1.0]);
+
+        // flipping both ways is the same as half a turn
+        assert_eq!(
+            Tile::new(0).with_flip(true, true).get_normal_transform(),
+            Tile::new(0)
+                .with_rotation(TileRotation::Clockwise180)
+                .get_normal_transform()
+        );
+    }
+
+    #[test]
+    fn test_tile_animation() {
+        let animation = TileAnimation::new(vec![(4, 0.5), (5, 0.25)]);
+
+        assert_eq!(animation.get_index_at(0.0), Some(4));
+        assert_eq!(animation.get_index_at(0.6), Some(5));
+        // it loops
+        assert_eq!(animation.get_index_at(0.8), Some(4));
+        assert_eq!(TileAnimation::new(vec![]).get_index_at(1.0), None);
+    }
+}
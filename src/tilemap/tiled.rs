@@ -0,0 +1,1308 @@
+//! Loading maps made in Tiled (.tmx or .tmj) and turning them into entities
+//! tile layers become tilemaps, object layers become entities with a transform and optionally a light or rectangle,
+//! and every layer and object gets its custom properties as a `TiledProperties` component
+//! only orthogonal, finite maps are supported, with csv or uncompressed base64 tile data
+//!
+//! objects choose what they become with a `component` string property, or their class if they don't have one:
+//! - `point_light`: a `PointLight` using the `color`, `intensity` and `falloff` properties
+//! - `area_light`: an `AreaLight` the size of the object, using the same properties
+//! - `rectangle`: a `Rectangle` the size of the object, using the `color` property
+//!
+//! tilesets can have `height_image`, `roughness_image` and `normal_image` file properties,
+//! otherwise tiles are flat and the normals are generated
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use lumenpyx::primitives::{Normal, NormalInput, Texture, TextureInput};
+use ABC_Game_Engine::{EntitiesAndComponents, Entity};
+
+use super::{Tile, TileAnimation, TileRotation, Tilemap, Tileset};
+use crate::lights::{AreaLight, PointLight};
+use crate::primitives::Rectangle;
+use crate::{LumenpyxProgram, NotActive};
+
+const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+/// the flags, including the one for rotated hexagons which isn't supported
+const GID_FLAGS: u32 = 0xF000_0000;
+
+const DEFAULT_LIGHT_COLOR: [f32; 3] = [1.0, 1.0, 1.0];
+const DEFAULT_LIGHT_INTENSITY: f32 = 1.0;
+const DEFAULT_LIGHT_FALLOFF: f32 = 0.01;
+
+#[derive(Debug)]
+pub enum TiledError {
+    Io(std::io::Error),
+    Xml(roxmltree::Error),
+    Json(serde_json::Error),
+    /// the file isn't a .tmx or .tmj
+    UnsupportedFormat(String),
+    /// something the importer can't load yet, like isometric maps or compressed tile data
+    Unsupported(String),
+    /// the file is missing something or has a value of the wrong type
+    Invalid(String),
+    /// a tileset image that doesn't exist
+    MissingImage(PathBuf),
+    /// a tileset image that exists but couldn't be decoded
+    Image(PathBuf, image::ImageError),
+    /// the tilesets are loaded into the lumenpyx program, so it has to be in the scene first
+    MissingProgram,
+}
+
+impl fmt::Display for TiledError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TiledError::Io(error) => write!(f, "failed to read map: {}", error),
+            TiledError::Xml(error) => write!(f, "failed to parse map: {}", error),
+            TiledError::Json(error) => write!(f, "failed to parse map: {}", error),
+            TiledError::UnsupportedFormat(extension) => write!(
+                f,
+                "maps can't be loaded from .{} files, only .tmx and .tmj",
+                extension
+            ),
+            TiledError::Unsupported(message) => write!(f, "unsupported map: {}", message),
+            TiledError::Invalid(message) => write!(f, "invalid map: {}", message),
+            TiledError::MissingImage(path) => {
+                write!(f, "tileset image {} doesn't exist", path.display())
+            }
+            TiledError::Image(path, error) => {
+                write!(
+                    f,
+                    "failed to load tileset image {}: {}",
+                    path.display(),
+                    error
+                )
+            }
+            TiledError::MissingProgram => write!(
+                f,
+                "the lumenpyx program has to be added before a map is spawned"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TiledError {}
+
+impl From<std::io::Error> for TiledError {
+    fn from(error: std::io::Error) -> Self {
+        TiledError::Io(error)
+    }
+}
+
+impl From<roxmltree::Error> for TiledError {
+    fn from(error: roxmltree::Error) -> Self {
+        TiledError::Xml(error)
+    }
+}
+
+impl From<serde_json::Error> for TiledError {
+    fn from(error: serde_json::Error) -> Self {
+        TiledError::Json(error)
+    }
+}
+
+/// A custom property, file paths are relative to the file they're in like Tiled saves them
+#[derive(Clone, Debug, PartialEq)]
+pub enum PropertyValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    /// rgba from 0 to 1
+    Color([f32; 4]),
+    File(String),
+    /// the id of another object
+    Object(u32),
+}
+
+impl PropertyValue {
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            PropertyValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// ints are turned into floats too
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            PropertyValue::Float(value) => Some(*value),
+            PropertyValue::Int(value) => Some(*value as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            PropertyValue::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// strings and file paths
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            PropertyValue::String(value) | PropertyValue::File(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_color(&self) -> Option<[f32; 4]> {
+        match self {
+            PropertyValue::Color(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+pub type Properties = HashMap<String, PropertyValue>;
+
+/// The name, class and custom properties of the layer or object an entity was made from
+/// the entity for the whole map has the map's properties
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TiledProperties {
+    pub name: String,
+    pub class: String,
+    pub properties: Properties,
+}
+
+impl TiledProperties {
+    pub fn get(&self, name: &str) -> Option<&PropertyValue> {
+        self.properties.get(name)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TiledTileset {
+    /// the global id of the first tile, layers refer to tiles by global id
+    pub first_gid: u32,
+    pub name: String,
+    /// relative to the current directory, like the map's path
+    pub image: PathBuf,
+    pub tile_size: [u32; 2],
+    pub margin: u32,
+    pub spacing: u32,
+    /// by the id of the first tile in the tileset, not the global id
+    pub animations: HashMap<u32, TileAnimation>,
+    /// the directory the tileset's own file paths are relative to
+    pub directory: PathBuf,
+    pub properties: Properties,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TiledTileLayer {
+    pub name: String,
+    pub class: String,
+    pub width: u32,
+    pub height: u32,
+    /// global ids row by row from the top, with Tiled's flip flags, 0 is an empty cell
+    pub tiles: Vec<u32>,
+    pub offset: [f32; 2],
+    pub visible: bool,
+    pub properties: Properties,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TiledObject {
+    pub id: u32,
+    pub name: String,
+    pub class: String,
+    /// in pixels from the top left of the map, y goes down like in Tiled
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// clockwise, in degrees
+    pub rotation: f32,
+    pub properties: Properties,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TiledObjectLayer {
+    pub name: String,
+    pub class: String,
+    pub objects: Vec<TiledObject>,
+    pub offset: [f32; 2],
+    pub visible: bool,
+    pub properties: Properties,
+}
+
+/// Layers from bottom to top, layers in groups are flattened with the group's offset added to theirs
+#[derive(Clone, Debug, PartialEq)]
+pub enum TiledLayer {
+    Tiles(TiledTileLayer),
+    Objects(TiledObjectLayer),
+}
+
+/// A Tiled map, read but not loaded into the game yet
+#[derive(Clone, Debug, PartialEq)]
+pub struct TiledMap {
+    /// in tiles
+    pub width: u32,
+    pub height: u32,
+    /// the size of a cell in pixels, tiles from tilesets with bigger tiles stick out of the top right of their cell
+    pub tile_size: [u32; 2],
+    pub tilesets: Vec<TiledTileset>,
+    pub layers: Vec<TiledLayer>,
+    pub properties: Properties,
+}
+
+impl TiledMap {
+    /// reads a .tmx or .tmj file, and the tilesets it uses from other files
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, TiledError> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let directory = path.parent().unwrap_or(Path::new(""));
+        let text = std::fs::read_to_string(path)?;
+
+        match extension.as_str() {
+            "tmx" => Self::from_tmx(&text, directory),
+            "tmj" | "json" => Self::from_tmj(&text, directory),
+            _ => Err(TiledError::UnsupportedFormat(extension)),
+        }
+    }
+
+    /// a map from the text of a .tmx file, paths in it are relative to the directory
+    pub fn from_tmx(text: &str, directory: &Path) -> Result<Self, TiledError> {
+        let document = roxmltree::Document::parse(text)?;
+        xml::parse_map(document.root_element(), directory)
+    }
+
+    /// a map from the text of a .tmj file, paths in it are relative to the directory
+    pub fn from_tmj(text: &str, directory: &Path) -> Result<Self, TiledError> {
+        let value = serde_json::from_str(text)?;
+        json::parse_map(&value, directory)
+    }
+
+    /// the tileset a global id is in, and the id within it
+    fn get_tileset(&self, gid: u32) -> Option<(usize, u32)> {
+        let gid = gid & !GID_FLAGS;
+        self.tilesets
+            .iter()
+            .enumerate()
+            .filter(|(_, tileset)| tileset.first_gid <= gid)
+            .max_by_key(|(_, tileset)| tileset.first_gid)
+            .map(|(i, tileset)| (i, gid - tileset.first_gid))
+    }
+
+    /// makes an entity for the map, with a child for every layer and object
+    /// the top left of the map is at the map entity and each layer is 1 higher in z than the one below
+    /// loads the tileset textures into the lumenpyx program, so it has to be in the scene
+    pub fn spawn(
+        &self,
+        entities_and_components: &mut EntitiesAndComponents,
+    ) -> Result<Entity, TiledError> {
+        let tilesets = self.load_tilesets(entities_and_components)?;
+
+        let map_entity = entities_and_components.add_entity_with((
+            ABC_Game_Engine::Transform::default(),
+            TiledProperties {
+                properties: self.properties.clone(),
+                ..Default::default()
+            },
+        ));
+
+        for (z, layer) in self.layers.iter().enumerate() {
+            let layer_entity = match layer {
+                TiledLayer::Tiles(layer) => {
+                    self.spawn_tile_layer(entities_and_components, layer, &tilesets, z as f64)
+                }
+                TiledLayer::Objects(layer) => {
+                    spawn_object_layer(entities_and_components, layer, z as f64)
+                }
+            };
+            entities_and_components.set_parent(layer_entity, map_entity);
+        }
+
+        Ok(map_entity)
+    }
+
+    fn load_tilesets(
+        &self,
+        entities_and_components: &mut EntitiesAndComponents,
+    ) -> Result<Vec<Tileset>, TiledError> {
+        let program = entities_and_components
+            .get_resource_mut::<LumenpyxProgram>()
+            .ok_or(TiledError::MissingProgram)?;
+
+        let mut tilesets = vec![];
+        for tileset in &self.tilesets {
+            // decoded here instead of by lumenpyx, which panics on an image it can't read
+            let image = |name: &str| -> Result<Option<glium::Texture2d>, TiledError> {
+                let path = match name {
+                    "image" => tileset.image.clone(),
+                    _ => match tileset
+                        .properties
+                        .get(name)
+                        .and_then(|value| value.as_str())
+                    {
+                        Some(path) => tileset.directory.join(path),
+                        None => return Ok(None),
+                    },
+                };
+
+                if !path.exists() {
+                    return Err(TiledError::MissingImage(path));
+                }
+                let pixels = image::open(&path)
+                    .map_err(|error| TiledError::Image(path, error))?
+                    .to_rgba32f();
+                let dimensions = pixels.dimensions();
+                // textures are stored bottom row first
+                let raw = glium::texture::RawImage2d::from_raw_rgba_reversed(&pixels, dimensions);
+                let texture = glium::Texture2d::new(&program.display, raw)
+                    .expect("failed to create tileset texture");
+                Ok(Some(texture))
+            };
+
+            let albedo = image("image")?.expect("tilesets always have an image");
+            let height = image("height_image")?
+                .map(Texture::Texture)
+                .unwrap_or(Texture::Solid([0.0, 0.0, 0.0, 1.0]));
+            let roughness = image("roughness_image")?
+                .map(Texture::Texture)
+                .unwrap_or(Texture::Solid([1.0, 1.0, 1.0, 1.0]));
+            let normal = image("normal_image")?
+                .map(Normal::Texture)
+                .unwrap_or(Normal::AutoGenerated);
+
+            let loaded = Tileset::new(
+                TextureInput::from(Texture::Texture(albedo)),
+                TextureInput::from(height),
+                TextureInput::from(roughness),
+                NormalInput::from(normal),
+                tileset.tile_size,
+                program,
+            )
+            .with_spacing(tileset.margin, tileset.spacing);
+
+            tilesets.push(loaded);
+        }
+
+        Ok(tilesets)
+    }
+
+    /// an entity for the layer, with a tilemap child for every tileset the layer uses
+    fn spawn_tile_layer(
+        &self,
+        entities_and_components: &mut EntitiesAndComponents,
+        layer: &TiledTileLayer,
+        tilesets: &[Tileset],
+        z: f64,
+    ) -> Entity {
+        let layer_entity = entities_and_components.add_entity_with((
+            ABC_Game_Engine::Transform {
+                x: layer.offset[0] as f64,
+                y: -layer.offset[1] as f64,
+                z,
+                ..Default::default()
+            },
+            TiledProperties {
+                name: layer.name.clone(),
+                class: layer.class.clone(),
+                properties: layer.properties.clone(),
+            },
+        ));
+
+        if !layer.visible {
+            entities_and_components.add_component_to(layer_entity, NotActive);
+        }
+
+        for (tileset_index, tileset) in tilesets.iter().enumerate() {
+            let mut tilemap =
+                Tilemap::new(*tileset, layer.width, layer.height).with_cell_size(self.tile_size);
+            let mut is_used = false;
+
+            for (i, gid) in layer.tiles.iter().enumerate() {
+                match self.get_tileset(*gid) {
+                    Some((index, id)) if index == tileset_index && *gid != 0 => {
+                        let [x, y] = [i as u32 % layer.width, i as u32 / layer.width];
+                        tilemap.set_tile(x, y, Some(gid_to_tile(*gid, id)));
+                        is_used = true;
+                    }
+                    _ => (),
+                }
+            }
+
+            if !is_used {
+                continue;
+            }
+
+            for (id, animation) in &self.tilesets[tileset_index].animations {
+                tilemap.set_animation(*id, animation.clone());
+            }
+
+            // the transform is relative to the layer, which already has the z
+            let tilemap_entity = entities_and_components
+                .add_entity_with((tilemap, ABC_Game_Engine::Transform::default()));
+            entities_and_components.set_parent(tilemap_entity, layer_entity);
+        }
+
+        layer_entity
+    }
+}
+
+fn spawn_object_layer(
+    entities_and_components: &mut EntitiesAndComponents,
+    layer: &TiledObjectLayer,
+    z: f64,
+) -> Entity {
+    let layer_entity = entities_and_components.add_entity_with((
+        ABC_Game_Engine::Transform {
+            x: layer.offset[0] as f64,
+            y: -layer.offset[1] as f64,
+            z,
+            ..Default::default()
+        },
+        TiledProperties {
+            name: layer.name.clone(),
+            class: layer.class.clone(),
+            properties: layer.properties.clone(),
+        },
+    ));
+
+    if !layer.visible {
+        entities_and_components.add_component_to(layer_entity, NotActive);
+    }
+
+    for object in &layer.objects {
+        let object_entity = spawn_object(entities_and_components, object);
+        entities_and_components.set_parent(object_entity, layer_entity);
+    }
+
+    layer_entity
+}
+
+/// the transform is relative to the object layer, so it's at z 0
+fn spawn_object(
+    entities_and_components: &mut EntitiesAndComponents,
+    object: &TiledObject,
+) -> Entity {
+    let [x, y] = get_object_center(object);
+    let object_entity = entities_and_components.add_entity_with((
+        ABC_Game_Engine::Transform {
+            x: x as f64,
+            y: -y as f64,
+            // tiled turns clockwise and y goes down, here y goes up
+            rotation: -(object.rotation as f64).to_radians(),
+            ..Default::default()
+        },
+        TiledProperties {
+            name: object.name.clone(),
+            class: object.class.clone(),
+            properties: object.properties.clone(),
+        },
+    ));
+
+    let property = |name: &str| object.properties.get(name);
+    let color = property("color").and_then(|value| value.as_color());
+    let light_color = color
+        .map(|[r, g, b, _]| [r, g, b])
+        .unwrap_or(DEFAULT_LIGHT_COLOR);
+    let intensity = property("intensity")
+        .and_then(|value| value.as_float())
+        .map_or(DEFAULT_LIGHT_INTENSITY, |value| value as f32);
+    let falloff = property("falloff")
+        .and_then(|value| value.as_float())
+        .map_or(DEFAULT_LIGHT_FALLOFF, |value| value as f32);
+
+    let component = property("component")
+        .and_then(|value| value.as_str())
+        .unwrap_or(&object.class);
+
+    match component {
+        "point_light" => entities_and_components.add_component_to(
+            object_entity,
+            PointLight::new(light_color, intensity, falloff),
+        ),
+        "area_light" => entities_and_components.add_component_to(
+            object_entity,
+            AreaLight::new(light_color, intensity, falloff, object.width, object.height),
+        ),
+        "rectangle" => entities_and_components.add_component_to(
+            object_entity,
+            Rectangle::new(
+                color.unwrap_or([1.0, 1.0, 1.0, 1.0]),
+                object.width,
+                object.height,
+            ),
+        ),
+        _ => (),
+    }
+
+    object_entity
+}
+
+/// the middle of an object in Tiled's coordinates, its position is the top left corner before it's turned
+fn get_object_center(object: &TiledObject) -> [f32; 2] {
+    let (sin, cos) = object.rotation.to_radians().sin_cos();
+    let [half_width, half_height] = [object.width / 2.0, object.height / 2.0];
+
+    [
+        object.x + half_width * cos - half_height * sin,
+        object.y + half_width * sin + half_height * cos,
+    ]
+}
+
+/// a tile from a global id with Tiled's flip flags, Tiled flips diagonally then horizontally then vertically
+fn gid_to_tile(gid: u32, id: u32) -> Tile {
+    let horizontal = gid & FLIPPED_HORIZONTALLY != 0;
+    let vertical = gid & FLIPPED_VERTICALLY != 0;
+
+    // a diagonal flip is the same as turning clockwise and then flipping horizontally
+    match gid & FLIPPED_DIAGONALLY != 0 {
+        true => Tile::new(id)
+            .with_rotation(TileRotation::Clockwise90)
+            .with_flip(!horizontal, vertical),
+        false => Tile::new(id).with_flip(horizontal, vertical),
+    }
+}
+
+/// #RRGGBB or #AARRGGBB
+fn parse_color(text: &str) -> Option<[f32; 4]> {
+    let hex = text.trim_start_matches('#');
+    if !hex.is_ascii() {
+        return None;
+    }
+
+    let channel = |index: usize| {
+        u8::from_str_radix(hex.get(index..index + 2)?, 16)
+            .ok()
+            .map(|channel| channel as f32 / 255.0)
+    };
+
+    match hex.len() {
+        6 => Some([channel(0)?, channel(2)?, channel(4)?, 1.0]),
+        8 => Some([channel(2)?, channel(4)?, channel(6)?, channel(0)?]),
+        _ => None,
+    }
+}
+
+fn parse_property(kind: &str, value: &str) -> Option<PropertyValue> {
+    Some(match kind {
+        "bool" => PropertyValue::Bool(value.parse().ok()?),
+        "int" => PropertyValue::Int(value.parse().ok()?),
+        "float" => PropertyValue::Float(value.parse().ok()?),
+        "color" => PropertyValue::Color(parse_color(value)?),
+        "file" => PropertyValue::File(value.to_string()),
+        "object" => PropertyValue::Object(value.parse().ok()?),
+        _ => PropertyValue::String(value.to_string()),
+    })
+}
+
+/// the little-endian ids in base64 tile data
+fn decode_base64_tiles(text: &str) -> Result<Vec<u32>, TiledError> {
+    let invalid = || TiledError::Invalid("tile data isn't valid base64".to_string());
+
+    let mut bytes = vec![];
+    let mut buffer = 0_u32;
+    let mut bits = 0;
+    for character in text.bytes().filter(|byte| !byte.is_ascii_whitespace()) {
+        let value = match character {
+            b'A'..=b'Z' => character - b'A',
+            b'a'..=b'z' => character - b'a' + 26,
+            b'0'..=b'9' => character - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            _ => return Err(invalid()),
+        };
+
+        buffer = (buffer << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+
+    if bytes.len() % 4 != 0 {
+        return Err(invalid());
+    }
+
+    Ok(bytes
+        .chunks(4)
+        .map(|id| u32::from_le_bytes([id[0], id[1], id[2], id[3]]))
+        .collect())
+}
+
+fn parse_csv_tiles(text: &str) -> Result<Vec<u32>, TiledError> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+            id.parse::<u32>()
+                .map_err(|_| TiledError::Invalid(format!("{:?} isn't a tile id", id)))
+        })
+        .collect()
+}
+
+fn check_tile_count(layer: &TiledTileLayer) -> Result<(), TiledError> {
+    let tile_count = (layer.width as usize)
+        .checked_mul(layer.height as usize)
+        .ok_or_else(|| {
+            TiledError::Invalid(format!(
+                "layer {:?} is too big at {}x{}",
+                layer.name, layer.width, layer.height
+            ))
+        })?;
+    if layer.tiles.len() != tile_count {
+        return Err(TiledError::Invalid(format!(
+            "layer {:?} has {} tiles but is {}x{}",
+            layer.name,
+            layer.tiles.len(),
+            layer.width,
+            layer.height
+        )));
+    }
+    Ok(())
+}
+
+fn check_map_kind(orientation: &str, infinite: bool) -> Result<(), TiledError> {
+    if orientation != "orthogonal" {
+        return Err(TiledError::Unsupported(format!(
+            "{} maps, only orthogonal maps can be loaded",
+            orientation
+        )));
+    }
+    if infinite {
+        return Err(TiledError::Unsupported("infinite maps".to_string()));
+    }
+    Ok(())
+}
+
+mod xml {
+    use super::*;
+    use roxmltree::Node;
+
+    fn get_attribute<T: std::str::FromStr>(
+        node: Node,
+        name: &str,
+    ) -> Result<Option<T>, TiledError> {
+        node.attribute(name)
+            .map(|value| {
+                value.parse().map_err(|_| {
+                    TiledError::Invalid(format!(
+                        "{} of <{}> isn't valid: {:?}",
+                        name,
+                        node.tag_name().name(),
+                        value
+                    ))
+                })
+            })
+            .transpose()
+    }
+
+    fn get_required<T: std::str::FromStr>(node: Node, name: &str) -> Result<T, TiledError> {
+        get_attribute(node, name)?.ok_or_else(|| {
+            TiledError::Invalid(format!("<{}> has no {}", node.tag_name().name(), name))
+        })
+    }
+
+    fn get_child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+        node.children().find(|child| child.has_tag_name(name))
+    }
+
+    fn parse_properties(node: Node) -> Result<Properties, TiledError> {
+        let mut properties = Properties::new();
+        let Some(properties_node) = get_child(node, "properties") else {
+            return Ok(properties);
+        };
+
+        for property in properties_node
+            .children()
+            .filter(|child| child.has_tag_name("property"))
+        {
+            let name: String = get_required(property, "name")?;
+            let kind = property.attribute("type").unwrap_or("string");
+            // multiline strings are saved as text instead of an attribute
+            let value = property
+                .attribute("value")
+                .or_else(|| property.text())
+                .unwrap_or_default();
+
+            let value = parse_property(kind, value).ok_or_else(|| {
+                TiledError::Invalid(format!("property {:?} isn't a valid {}", name, kind))
+            })?;
+            properties.insert(name, value);
+        }
+
+        Ok(properties)
+    }
+
+    fn get_class(node: Node) -> String {
+        // tiled called it type before 1.9
+        node.attribute("class")
+            .or_else(|| node.attribute("type"))
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    pub(super) fn parse_map(map: Node, directory: &Path) -> Result<TiledMap, TiledError> {
+        check_map_kind(
+            map.attribute("orientation").unwrap_or("orthogonal"),
+            map.attribute("infinite") == Some("1"),
+        )?;
+
+        let mut tilesets = vec![];
+        for tileset in map.children().filter(|child| child.has_tag_name("tileset")) {
+            let first_gid = get_required(tileset, "firstgid")?;
+
+            let tileset = match tileset.attribute("source") {
+                Some(source) => {
+                    let path = directory.join(source);
+                    let text = std::fs::read_to_string(&path)?;
+                    let tileset_directory = path.parent().unwrap_or(Path::new(""));
+
+                    if source.ends_with(".tsj") || source.ends_with(".json") {
+                        let value = serde_json::from_str(&text)?;
+                        json::parse_tileset(&value, first_gid, tileset_directory)?
+                    } else {
+                        let document = roxmltree::Document::parse(&text)?;
+                        parse_tileset(document.root_element(), first_gid, tileset_directory)?
+                    }
+                }
+                None => parse_tileset(tileset, first_gid, directory)?,
+            };
+            tilesets.push(tileset);
+        }
+
+        let mut layers = vec![];
+        parse_layers(map, [0.0, 0.0], true, &mut layers)?;
+
+        Ok(TiledMap {
+            width: get_required(map, "width")?,
+            height: get_required(map, "height")?,
+            tile_size: [
+                get_required(map, "tilewidth")?,
+                get_required(map, "tileheight")?,
+            ],
+            tilesets,
+            layers,
+            properties: parse_properties(map)?,
+        })
+    }
+
+    pub(super) fn parse_tileset(
+        tileset: Node,
+        first_gid: u32,
+        directory: &Path,
+    ) -> Result<TiledTileset, TiledError> {
+        let image = get_child(tileset, "image").ok_or_else(|| {
+            TiledError::Unsupported("tilesets made of separate images".to_string())
+        })?;
+
+        let mut animations = HashMap::new();
+        for tile in tileset
+            .children()
+            .filter(|child| child.has_tag_name("tile"))
+        {
+            let Some(animation) = get_child(tile, "animation") else {
+                continue;
+            };
+
+            let mut frames = vec![];
+            for frame in animation
+                .children()
+                .filter(|child| child.has_tag_name("frame"))
+            {
+                let id = get_required(frame, "tileid")?;
+                let duration: u32 = get_required(frame, "duration")?;
+                frames.push((id, duration as f32 / 1000.0));
+            }
+            animations.insert(get_required(tile, "id")?, TileAnimation::new(frames));
+        }
+
+        Ok(TiledTileset {
+            first_gid,
+            name: tileset.attribute("name").unwrap_or_default().to_string(),
+            image: directory.join(get_required::<String>(image, "source")?),
+            tile_size: [
+                get_required(tileset, "tilewidth")?,
+                get_required(tileset, "tileheight")?,
+            ],
+            margin: get_attribute(tileset, "margin")?.unwrap_or(0),
+            spacing: get_attribute(tileset, "spacing")?.unwrap_or(0),
+            animations,
+            directory: directory.to_path_buf(),
+            properties: parse_properties(tileset)?,
+        })
+    }
+
+    fn get_offset(node: Node, parent_offset: [f32; 2]) -> Result<[f32; 2], TiledError> {
+        Ok([
+            parent_offset[0] + get_attribute(node, "offsetx")?.unwrap_or(0.0),
+            parent_offset[1] + get_attribute(node, "offsety")?.unwrap_or(0.0),
+        ])
+    }
+
+    fn parse_layers(
+        parent: Node,
+        parent_offset: [f32; 2],
+        parent_visible: bool,
+        layers: &mut Vec<TiledLayer>,
+    ) -> Result<(), TiledError> {
+        for node in parent.children().filter(|child| child.is_element()) {
+            let offset = get_offset(node, parent_offset)?;
+            let visible = parent_visible && node.attribute("visible") != Some("0");
+            let name = node.attribute("name").unwrap_or_default().to_string();
+
+            match node.tag_name().name() {
+                "layer" => {
+                    let data = get_child(node, "data").ok_or_else(|| {
+                        TiledError::Invalid(format!("layer {:?} has no data", name))
+                    })?;
+
+                    if let Some(compression) = data.attribute("compression") {
+                        return Err(TiledError::Unsupported(format!(
+                            "{} compressed tile data, save the map with csv or uncompressed base64",
+                            compression
+                        )));
+                    }
+
+                    let text = data.text().unwrap_or_default();
+                    let tiles = match data.attribute("encoding") {
+                        Some("csv") => parse_csv_tiles(text)?,
+                        Some("base64") => decode_base64_tiles(text)?,
+                        _ => {
+                            return Err(TiledError::Unsupported(
+                                "xml tile data, save the map with csv or base64".to_string(),
+                            ))
+                        }
+                    };
+
+                    let layer = TiledTileLayer {
+                        name,
+                        class: get_class(node),
+                        width: get_required(node, "width")?,
+                        height: get_required(node, "height")?,
+                        tiles,
+                        offset,
+                        visible,
+                        properties: parse_properties(node)?,
+                    };
+                    check_tile_count(&layer)?;
+                    layers.push(TiledLayer::Tiles(layer));
+                }
+                "objectgroup" => {
+                    let mut objects = vec![];
+                    for object in node.children().filter(|child| child.has_tag_name("object")) {
+                        objects.push(TiledObject {
+                            id: get_attribute(object, "id")?.unwrap_or(0),
+                            name: object.attribute("name").unwrap_or_default().to_string(),
+                            class: get_class(object),
+                            x: get_attribute(object, "x")?.unwrap_or(0.0),
+                            y: get_attribute(object, "y")?.unwrap_or(0.0),
+                            width: get_attribute(object, "width")?.unwrap_or(0.0),
+                            height: get_attribute(object, "height")?.unwrap_or(0.0),
+                            rotation: get_attribute(object, "rotation")?.unwrap_or(0.0),
+                            properties: parse_properties(object)?,
+                        });
+                    }
+
+                    layers.push(TiledLayer::Objects(TiledObjectLayer {
+                        name,
+                        class: get_class(node),
+                        objects,
+                        offset,
+                        visible,
+                        properties: parse_properties(node)?,
+                    }));
+                }
+                "group" => parse_layers(node, offset, visible, layers)?,
+                // image layers aren't supported, and the rest isn't a layer
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+mod json {
+    use super::*;
+    use serde_json::Value;
+
+    fn get_field<'a>(value: &'a Value, name: &str) -> Result<&'a Value, TiledError> {
+        value
+            .get(name)
+            .ok_or_else(|| TiledError::Invalid(format!("missing {}", name)))
+    }
+
+    fn get_u32(value: &Value, name: &str) -> Result<u32, TiledError> {
+        get_field(value, name)?
+            .as_u64()
+            .and_then(|number| u32::try_from(number).ok())
+            .ok_or_else(|| TiledError::Invalid(format!("{} isn't a positive whole number", name)))
+    }
+
+    fn get_f32_or_zero(value: &Value, name: &str) -> f32 {
+        value.get(name).and_then(Value::as_f64).unwrap_or(0.0) as f32
+    }
+
+    fn get_str<'a>(value: &'a Value, name: &str) -> &'a str {
+        value.get(name).and_then(Value::as_str).unwrap_or_default()
+    }
+
+    fn get_array<'a>(value: &'a Value, name: &str) -> &'a [Value] {
+        value
+            .get(name)
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    fn get_class(value: &Value) -> String {
+        // tiled called it type before 1.9
+        match value.get("class").and_then(Value::as_str) {
+            Some(class) => class.to_string(),
+            None => get_str(value, "type").to_string(),
+        }
+    }
+
+    fn parse_properties(value: &Value) -> Result<Properties, TiledError> {
+        let mut properties = Properties::new();
+
+        for property in get_array(value, "properties") {
+            let name = get_str(property, "name").to_string();
+            let kind = property
+                .get("type")
+                .and_then(Value::as_str)
+                .unwrap_or("string");
+            let value = match get_field(property, "value")? {
+                Value::String(text) => text.clone(),
+                other => other.to_string(),
+            };
+
+            let value = parse_property(kind, &value).ok_or_else(|| {
+                TiledError::Invalid(format!("property {:?} isn't a valid {}", name, kind))
+            })?;
+            properties.insert(name, value);
+        }
+
+        Ok(properties)
+    }
+
+    pub(super) fn parse_map(map: &Value, directory: &Path) -> Result<TiledMap, TiledError> {
+        check_map_kind(
+            map.get("orientation")
+                .and_then(Value::as_str)
+                .unwrap_or("orthogonal"),
+            map.get("infinite").and_then(Value::as_bool) == Some(true),
+        )?;
+
+        let mut tilesets = vec![];
+        for tileset in get_array(map, "tilesets") {
+            let first_gid = get_u32(tileset, "firstgid")?;
+
+            let tileset = match tileset.get("source").and_then(Value::as_str) {
+                Some(source) => {
+                    let path = directory.join(source);
+                    let text = std::fs::read_to_string(&path)?;
+                    let tileset_directory = path.parent().unwrap_or(Path::new(""));
+
+                    if source.ends_with(".tsx") {
+                        let document = roxmltree::Document::parse(&text)?;
+                        xml::parse_tileset(document.root_element(), first_gid, tileset_directory)?
+                    } else {
+                        let value = serde_json::from_str(&text)?;
+                        parse_tileset(&value, first_gid, tileset_directory)?
+                    }
+                }
+                None => parse_tileset(tileset, first_gid, directory)?,
+            };
+            tilesets.push(tileset);
+        }
+
+        let mut layers = vec![];
+        parse_layers(get_array(map, "layers"), [0.0, 0.0], true, &mut layers)?;
+
+        Ok(TiledMap {
+            width: get_u32(map, "width")?,
+            height: get_u32(map, "height")?,
+            tile_size: [get_u32(map, "tilewidth")?, get_u32(map, "tileheight")?],
+            tilesets,
+            layers,
+            properties: parse_properties(map)?,
+        })
+    }
+
+    pub(super) fn parse_tileset(
+        tileset: &Value,
+        first_gid: u32,
+        directory: &Path,
+    ) -> Result<TiledTileset, TiledError> {
+        let image = tileset
+            .get("image")
+            .and_then(Value::as_str)
+            .ok_or_else(|| {
+                TiledError::Unsupported("tilesets made of separate images".to_string())
+            })?;
+
+        let mut animations = HashMap::new();
+        for tile in get_array(tileset, "tiles") {
+            let frames = get_array(tile, "animation");
+            if frames.is_empty() {
+                continue;
+            }
+
+            let frames = frames
+                .iter()
+                .map(|frame| {
+                    Ok((
+                        get_u32(frame, "tileid")?,
+                        get_u32(frame, "duration")? as f32 / 1000.0,
+                    ))
+                })
+                .collect::<Result<Vec<_>, TiledError>>()?;
+            animations.insert(get_u32(tile, "id")?, TileAnimation::new(frames));
+        }
+
+        Ok(TiledTileset {
+            first_gid,
+            name: get_str(tileset, "name").to_string(),
+            image: directory.join(image),
+            tile_size: [
+                get_u32(tileset, "tilewidth")?,
+                get_u32(tileset, "tileheight")?,
+            ],
+            margin: get_u32(tileset, "margin").unwrap_or(0),
+            spacing: get_u32(tileset, "spacing").unwrap_or(0),
+            animations,
+            directory: directory.to_path_buf(),
+            properties: parse_properties(tileset)?,
+        })
+    }
+
+    fn parse_layers(
+        values: &[Value],
+        parent_offset: [f32; 2],
+        parent_visible: bool,
+        layers: &mut Vec<TiledLayer>,
+    ) -> Result<(), TiledError> {
+        for layer in values {
+            let offset = [
+                parent_offset[0] + get_f32_or_zero(layer, "offsetx"),
+                parent_offset[1] + get_f32_or_zero(layer, "offsety"),
+            ];
+            let visible =
+                parent_visible && layer.get("visible").and_then(Value::as_bool) != Some(false);
+            let name = get_str(layer, "name").to_string();
+
+            match get_str(layer, "type") {
+                "tilelayer" => {
+                    if let Some(compression) = layer
+                        .get("compression")
+                        .and_then(Value::as_str)
+                        .filter(|compression| !compression.is_empty())
+                    {
+                        return Err(TiledError::Unsupported(format!(
+                            "{} compressed tile data, save the map with csv or uncompressed base64",
+                            compression
+                        )));
+                    }
+
+                    let tiles = match get_field(layer, "data")? {
+                        Value::String(data) => decode_base64_tiles(data)?,
+                        Value::Array(ids) => ids
+                            .iter()
+                            .map(|id| {
+                                id.as_u64()
+                                    .and_then(|id| u32::try_from(id).ok())
+                                    .ok_or_else(|| {
+                                        TiledError::Invalid(format!("{} isn't a tile id", id))
+                                    })
+                            })
+                            .collect::<Result<Vec<_>, _>>()?,
+                        _ => {
+                            return Err(TiledError::Invalid(format!(
+                                "layer {:?} has no tile data",
+                                name
+                            )))
+                        }
+                    };
+
+                    let layer = TiledTileLayer {
+                        name,
+                        class: get_class(layer),
+                        width: get_u32(layer, "width")?,
+                        height: get_u32(layer, "height")?,
+                        tiles,
+                        offset,
+                        visible,
+                        properties: parse_properties(layer)?,
+                    };
+                    check_tile_count(&layer)?;
+                    layers.push(TiledLayer::Tiles(layer));
+                }
+                "objectgroup" => {
+                    let objects = get_array(layer, "objects")
+                        .iter()
+                        .map(|object| {
+                            Ok(TiledObject {
+                                id: get_u32(object, "id").unwrap_or(0),
+                                name: get_str(object, "name").to_string(),
+                                class: get_class(object),
+                                x: get_f32_or_zero(object, "x"),
+                                y: get_f32_or_zero(object, "y"),
+                                width: get_f32_or_zero(object, "width"),
+                                height: get_f32_or_zero(object, "height"),
+                                rotation: get_f32_or_zero(object, "rotation"),
+                                properties: parse_properties(object)?,
+                            })
+                        })
+                        .collect::<Result<Vec<_>, TiledError>>()?;
+
+                    layers.push(TiledLayer::Objects(TiledObjectLayer {
+                        name,
+                        class: get_class(layer),
+                        objects,
+                        offset,
+                        visible,
+                        properties: parse_properties(layer)?,
+                    }));
+                }
+                "group" => parse_layers(get_array(layer, "layers"), offset, visible, layers)?,
+                // image layers aren't supported
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TMX: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="2" height="2" tilewidth="16" tileheight="16" infinite="0">
+ <properties>
+  <property name="music" value="forest.ogg"/>
+ </properties>
+ <tileset firstgid="1" name="ground" tilewidth="16" tileheight="16" spacing="1" margin="2" tilecount="4" columns="2">
+  <image source="ground.png" width="36" height="36"/>
+  <tile id="3">
+   <animation>
+    <frame tileid="3" duration="100"/>
+    <frame tileid="2" duration="250"/>
+   </animation>
+  </tile>
+ </tileset>
+ <group name="background" offsetx="4">
+  <layer id="1" name="floor" width="2" height="2" offsety="8">
+   <data encoding="csv">
+1,2,
+0,2147483652
+</data>
+  </layer>
+ </group>
+ <objectgroup id="2" name="lights" visible="0">
+  <object id="1" name="torch" type="point_light" x="8" y="24">
+   <properties>
+    <property name="color" type="color" value="#ffff8000"/>
+    <property name="intensity" type="float" value="2.5"/>
+   </properties>
+  </object>
+ </objectgroup>
+</map>"##;
+
+    #[test]
+    fn test_parse_tmx() {
+        let map = TiledMap::from_tmx(TMX, Path::new("maps")).unwrap();
+
+        assert_eq!([map.width, map.height], [2, 2]);
+        assert_eq!(
+            map.properties.get("music"),
+            Some(&PropertyValue::String("forest.ogg".to_string()))
+        );
+
+        let tileset = &map.tilesets[0];
+        assert_eq!(tileset.image, Path::new("maps").join("ground.png"));
+        assert_eq!([tileset.margin, tileset.spacing], [2, 1]);
+        assert_eq!(tileset.animations[&3].get_frames(), &[(3, 0.1), (2, 0.25)]);
+
+        let TiledLayer::Tiles(floor) = &map.layers[0] else {
+            panic!("expected a tile layer");
+        };
+        assert_eq!(floor.offset, [4.0, 8.0]);
+        assert_eq!(floor.tiles, vec![1, 2, 0, FLIPPED_HORIZONTALLY | 4]);
+
+        let TiledLayer::Objects(lights) = &map.layers[1] else {
+            panic!("expected an object layer");
+        };
+        assert!(!lights.visible);
+        let torch = &lights.objects[0];
+        assert_eq!(torch.class, "point_light");
+        assert_eq!(
+            torch.properties.get("color"),
+            Some(&PropertyValue::Color([1.0, 128.0 / 255.0, 0.0, 1.0]))
+        );
+        assert_eq!(
+            torch
+                .properties
+                .get("intensity")
+                .and_then(|value| value.as_float()),
+            Some(2.5)
+        );
+    }
+
+    #[test]
+    fn test_parse_tmj() {
+        // the tile data is 1, 2, 0, 3 in base64
+        let tmj = r#"{
+            "orientation": "orthogonal", "infinite": false,
+            "width": 2, "height": 2, "tilewidth": 8, "tileheight": 8,
+            "tilesets": [{"firstgid": 1, "name": "walls", "image": "walls.png", "tilewidth": 8, "tileheight": 8}],
+            "layers": [
+                {"type": "tilelayer", "name": "walls", "width": 2, "height": 2,
+                 "encoding": "base64", "data": "AQAAAAIAAAAAAAAAAwAAAA=="},
+                {"type": "objectgroup", "name": "props", "objects": [
+                    {"id": 7, "name": "crate", "class": "rectangle", "x": 0, "y": 0, "width": 8, "height": 4, "rotation": 0,
+                     "properties": [{"name": "breakable", "type": "bool", "value": true}]}
+                ]}
+            ]
+        }"#;
+        let map = TiledMap::from_tmj(tmj, Path::new("")).unwrap();
+
+        let TiledLayer::Tiles(walls) = &map.layers[0] else {
+            panic!("expected a tile layer");
+        };
+        assert_eq!(walls.tiles, vec![1, 2, 0, 3]);
+
+        let TiledLayer::Objects(props) = &map.layers[1] else {
+            panic!("expected an object layer");
+        };
+        let crate_object = &props.objects[0];
+        assert_eq!(get_object_center(crate_object), [4.0, 2.0]);
+        assert_eq!(
+            crate_object.properties.get("breakable"),
+            Some(&PropertyValue::Bool(true))
+        );
+
+        assert!(matches!(
+            TiledMap::from_tmj(r#"{"orientation": "isometric"}"#, Path::new("")),
+            Err(TiledError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_gid_to_tile() {
+        assert_eq!(
+            gid_to_tile(FLIPPED_HORIZONTALLY | 5, 4),
+            Tile::new(4).with_flip(true, false)
+        );
+        // diagonal and horizontal is a quarter turn clockwise in tiled
+        assert_eq!(
+            gid_to_tile(FLIPPED_DIAGONALLY | FLIPPED_HORIZONTALLY | 1, 0),
+            Tile::new(0).with_rotation(TileRotation::Clockwise90)
+        );
+    }
+}
@@ -0,0 +1,234 @@
+//! Frame timing that knows about the window, so the game can pause when it isn't being looked at
+//! `GameTime` is what animations are drawn with, and it has the exact times that are recorded and replayed
+//!
+//! the engine's `DeltaTime` follows `GameTime` too, its time scale is set every frame so it stops while paused,
+//! is limited by `max_delta_time` and is close to the recorded time in replays
+//! that means setting the time scale on `DeltaTime` by hand doesn't last, pause `GameTime` instead
+
+use std::time::{Duration, Instant};
+
+use ABC_Game_Engine::Resource;
+
+use crate::window::WindowEvents;
+
+/// How `GameTime` behaves when the window isn't being looked at
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeSettings {
+    /// stop time while the window doesn't have focus
+    pub pause_on_focus_loss: bool,
+    /// stop time while the window is minimized
+    pub pause_when_minimized: bool,
+    /// the longest a single frame can be in seconds, None for no limit
+    /// this stops everything from jumping after the window is dragged or the program is resumed
+    pub max_delta_time: Option<f64>,
+    /// the time between frames in seconds while the window is minimized or hidden, None to not throttle
+    pub background_frame_time: Option<f64>,
+}
+
+impl Default for TimeSettings {
+    fn default() -> Self {
+        Self {
+            pause_on_focus_loss: false,
+            pause_when_minimized: true,
+            max_delta_time: Some(0.1),
+            background_frame_time: Some(0.1),
+        }
+    }
+}
+
+/// A resource with the time of the current frame, updated by the event loop before the update function
+pub struct GameTime {
+    settings: TimeSettings,
+    delta_time: f64,
+    total_time: f64,
+    /// paused by game code
+    paused: bool,
+    /// paused because of the window, see `TimeSettings`
+    auto_paused: bool,
+    last_frame: Option<Instant>,
+    /// the real time of the last frame, from `measure_frame`
+    real_delta_time: f64,
+}
+
+impl GameTime {
+    pub fn new() -> Self {
+        Self {
+            settings: TimeSettings::default(),
+            delta_time: 0.0,
+            total_time: 0.0,
+            paused: false,
+            auto_paused: false,
+            last_frame: None,
+            real_delta_time: 0.0,
+        }
+    }
+
+    pub fn set_settings(&mut self, settings: TimeSettings) {
+        self.settings = settings;
+    }
+
+    pub fn get_settings(&self) -> TimeSettings {
+        self.settings
+    }
+
+    /// pause or unpause time, this is separate from the pausing done because of the window
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// returns true if time is stopped, either by game code or because of the window
+    pub fn is_paused(&self) -> bool {
+        self.paused || self.auto_paused
+    }
+
+    /// the time this frame took in seconds, 0 while paused
+    pub fn get_delta_time(&self) -> f64 {
+        self.delta_time
+    }
+
+    /// the total time in seconds that wasn't paused
+    pub fn get_total_time(&self) -> f64 {
+        self.total_time
+    }
+
+    /// moves time forward by the real time of a frame, following the settings
+    pub fn advance(&mut self, real_delta_time: f64, window_events: &WindowEvents) {
+        let delta_time = self.next_delta_time(
+            real_delta_time,
+            window_events.is_focused(),
+            window_events.is_minimized(),
+        );
+        self.advance_exact(delta_time);
+    }
+
+    /// the delta time a frame with this real time gets after pausing and the max delta time
+    /// this updates whether time is paused because of the window, but doesn't move time forward
+    pub(crate) fn next_delta_time(
+        &mut self,
+        real_delta_time: f64,
+        window_focused: bool,
+        window_minimized: bool,
+    ) -> f64 {
+        self.auto_paused = (self.settings.pause_on_focus_loss && !window_focused)
+            || (self.settings.pause_when_minimized && window_minimized);
+
+        if self.is_paused() {
+            0.0
+        } else {
+            match self.settings.max_delta_time {
+                Some(max_delta_time) => real_delta_time.min(max_delta_time),
+                None => real_delta_time,
+            }
+        }
+    }
+
+    /// moves time forward by exactly this much, ignoring pausing and the max delta time
+    /// used for replays so they match the recording
+    pub fn advance_exact(&mut self, delta_time: f64) {
+        self.delta_time = delta_time;
+        self.total_time += delta_time;
+    }
+
+    /// the real time in seconds since this was last called, 0 the first time
+    pub(crate) fn measure_frame(&mut self) -> f64 {
+        let now = Instant::now();
+        let real_delta_time = self
+            .last_frame
+            .map(|last_frame| now.duration_since(last_frame).as_secs_f64())
+            .unwrap_or(0.0);
+
+        self.last_frame = Some(now);
+        self.real_delta_time = real_delta_time;
+        real_delta_time
+    }
+
+    /// the time scale that makes `DeltaTime`, which measures the real time, match this frame's delta time
+    pub(crate) fn get_world_time_scale(&self) -> f64 {
+        if self.delta_time == 0.0 {
+            0.0
+        } else if self.real_delta_time <= 0.0 {
+            1.0
+        } else {
+            self.delta_time / self.real_delta_time
+        }
+    }
+
+    /// when the next frame should be drawn if the window is hidden and rendering is throttled
+    /// None if the next frame should be drawn right away
+    pub(crate) fn get_throttled_frame_time(&self, window_events: &WindowEvents) -> Option<Instant> {
+        if !(window_events.is_minimized() || window_events.is_occluded()) {
+            return None;
+        }
+
+        let next_frame =
+            self.last_frame? + Duration::from_secs_f64(self.settings.background_frame_time?);
+
+        (next_frame > Instant::now()).then_some(next_frame)
+    }
+}
+
+impl Default for GameTime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Resource for GameTime {
+    fn update(&mut self) {}
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::WindowEvent;
+
+    #[test]
+    fn test_game_time_pauses_and_clamps() {
+        let mut window_events = WindowEvents::new();
+        let mut time = GameTime::new();
+
+        // a long frame, like after the window was dragged, is clamped
+        time.advance(2.0, &window_events);
+        assert_eq!(time.get_delta_time(), 0.1);
+
+        window_events.push_event(WindowEvent::Minimized(true));
+        time.advance(0.05, &window_events);
+        assert!(time.is_paused());
+        assert_eq!(time.get_delta_time(), 0.0);
+
+        // focus loss doesn't pause by default
+        window_events.push_event(WindowEvent::Minimized(false));
+        window_events.push_event(WindowEvent::Focused(false));
+        time.advance(0.05, &window_events);
+        assert_eq!(time.get_delta_time(), 0.05);
+
+        time.set_paused(true);
+        time.advance(0.05, &window_events);
+        assert_eq!(time.get_delta_time(), 0.0);
+        assert!((time.get_total_time() - 0.15).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_world_time_scale() {
+        let mut time = GameTime::new();
+
+        // a frame that took 0.5 seconds, clamped to 0.1
+        time.real_delta_time = 0.5;
+        time.advance_exact(0.1);
+        assert!((time.get_world_time_scale() - 0.2).abs() < 1e-9);
+
+        time.advance_exact(0.0);
+        assert_eq!(time.get_world_time_scale(), 0.0);
+
+        // without a measured frame, like when running without a window, the real time is used
+        time.real_delta_time = 0.0;
+        time.advance_exact(0.1);
+        assert_eq!(time.get_world_time_scale(), 1.0);
+    }
+}
@@ -0,0 +1,470 @@
+//! Window management, everything here is on `LumenpyxProgram` so it can be used from systems (like an options menu)
+//! sizes are in logical pixels so they stay the same on high DPI screens
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use winit::dpi::LogicalSize;
+pub use winit::window::{CursorGrabMode, CursorIcon, WindowId};
+use winit::window::{Fullscreen, Icon};
+use ABC_Game_Engine::{EntitiesAndComponents, Entity, Resource};
+
+use crate::LumenpyxProgram;
+
+pub mod secondary;
+
+/// How the window is shown
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindowMode {
+    Windowed,
+    /// a window without decorations that covers the current monitor
+    BorderlessFullscreen,
+    /// takes over the current monitor using its largest video mode
+    ExclusiveFullscreen,
+}
+
+#[derive(Debug)]
+pub enum WindowError {
+    /// the window isn't on a monitor, or the monitor has no video modes
+    NoMonitor,
+    Image(image::ImageError),
+    BadIcon(winit::window::BadIcon),
+    /// the platform doesn't support the request, like confining the cursor on macOS
+    Unsupported(winit::error::ExternalError),
+}
+
+impl fmt::Display for WindowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WindowError::NoMonitor => write!(f, "the window is not on a monitor with video modes"),
+            WindowError::Image(error) => write!(f, "failed to load image: {}", error),
+            WindowError::BadIcon(error) => write!(f, "failed to create window icon: {}", error),
+            WindowError::Unsupported(error) => {
+                write!(f, "the window does not support this: {}", error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for WindowError {}
+
+impl LumenpyxProgram {
+    /// switch between windowed, borderless fullscreen and exclusive fullscreen
+    pub fn set_window_mode(&mut self, mode: WindowMode) -> Result<(), WindowError> {
+        let fullscreen = match mode {
+            WindowMode::Windowed => None,
+            WindowMode::BorderlessFullscreen => Some(Fullscreen::Borderless(None)),
+            WindowMode::ExclusiveFullscreen => {
+                let monitor = self
+                    .window
+                    .current_monitor()
+                    .ok_or(WindowError::NoMonitor)?;
+
+                // the largest video mode, ties are broken by the refresh rate
+                let video_mode = monitor
+                    .video_modes()
+                    .max_by_key(|video_mode| {
+                        let size = video_mode.size();
+                        (
+                            size.width as u64 * size.height as u64,
+                            video_mode.refresh_rate_millihertz(),
+                        )
+                    })
+                    .ok_or(WindowError::NoMonitor)?;
+
+                Some(Fullscreen::Exclusive(video_mode))
+            }
+        };
+
+        self.window.set_fullscreen(fullscreen);
+        Ok(())
+    }
+
+    pub fn get_window_mode(&self) -> WindowMode {
+        match self.window.fullscreen() {
+            None => WindowMode::Windowed,
+            Some(Fullscreen::Borderless(_)) => WindowMode::BorderlessFullscreen,
+            Some(Fullscreen::Exclusive(_)) => WindowMode::ExclusiveFullscreen,
+        }
+    }
+
+    /// switch between windowed and borderless fullscreen
+    pub fn toggle_fullscreen(&mut self) {
+        let mode = match self.get_window_mode() {
+            WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+            _ => WindowMode::Windowed,
+        };
+
+        // only exclusive fullscreen can fail
+        let _ = self.set_window_mode(mode);
+    }
+
+    /// set the smallest size the window can be resized to, None removes the limit
+    pub fn set_min_window_size(&mut self, size: Option<[u32; 2]>) {
+        self.window
+            .set_min_inner_size(size.map(|size| LogicalSize::new(size[0], size[1])));
+    }
+
+    /// set the largest size the window can be resized to, None removes the limit
+    pub fn set_max_window_size(&mut self, size: Option<[u32; 2]>) {
+        self.window
+            .set_max_inner_size(size.map(|size| LogicalSize::new(size[0], size[1])));
+    }
+
+    /// how many physical pixels one logical pixel is, like 2.0 on a 4k laptop at 200%
+    /// this can change when the window is moved to another monitor, see `WindowEvent::ScaleFactorChanged`
+    pub fn get_scale_factor(&self) -> f64 {
+        self.window.scale_factor()
+    }
+
+    /// the size of the inside of the window in logical pixels
+    pub fn get_window_size(&self) -> [u32; 2] {
+        let size: LogicalSize<u32> = self.window.inner_size().to_logical(self.get_scale_factor());
+        [size.width, size.height]
+    }
+
+    /// the size of the inside of the window in physical pixels, this is what is drawn to
+    pub fn get_window_physical_size(&self) -> [u32; 2] {
+        self.window.inner_size().into()
+    }
+
+    pub fn set_resizable(&mut self, resizable: bool) {
+        self.window.set_resizable(resizable);
+    }
+
+    pub fn is_resizable(&self) -> bool {
+        self.window.is_resizable()
+    }
+
+    /// set the window icon from an image file, the platform decides what sizes look good
+    pub fn set_window_icon(&mut self, path: impl AsRef<Path>) -> Result<(), WindowError> {
+        let image = image::open(path).map_err(WindowError::Image)?.into_rgba8();
+        let (width, height) = image.dimensions();
+
+        self.set_window_icon_rgba(image.into_raw(), width, height)
+    }
+
+    /// set the window icon from raw rgba8 pixels
+    pub fn set_window_icon_rgba(
+        &mut self,
+        rgba: Vec<u8>,
+        width: u32,
+        height: u32,
+    ) -> Result<(), WindowError> {
+        let icon = Icon::from_rgba(rgba, width, height).map_err(WindowError::BadIcon)?;
+        self.window.set_window_icon(Some(icon));
+        Ok(())
+    }
+
+    pub fn remove_window_icon(&mut self) {
+        self.window.set_window_icon(None);
+    }
+
+    /// show or hide the system cursor while it is over the window
+    pub fn set_cursor_visible(&mut self, visible: bool) {
+        self.window.set_cursor_visible(visible);
+    }
+
+    /// keep the cursor inside the window (Confined) or in place (Locked)
+    /// not every platform supports both, so if the requested mode fails the other one is tried
+    pub fn set_cursor_grab(&mut self, mode: CursorGrabMode) -> Result<(), WindowError> {
+        let fallback = match mode {
+            CursorGrabMode::Confined => Some(CursorGrabMode::Locked),
+            CursorGrabMode::Locked => Some(CursorGrabMode::Confined),
+            CursorGrabMode::None => None,
+        };
+
+        match (self.window.set_cursor_grab(mode), fallback) {
+            (Ok(()), _) => Ok(()),
+            (Err(_), Some(fallback)) => self
+                .window
+                .set_cursor_grab(fallback)
+                .map_err(WindowError::Unsupported),
+            (Err(error), None) => Err(WindowError::Unsupported(error)),
+        }
+    }
+
+    /// use one of the system cursors, see `CustomCursor` for using an image
+    pub fn set_cursor_icon(&mut self, icon: CursorIcon) {
+        self.window.set_cursor_icon(icon);
+    }
+}
+
+/// A component that moves its entity to the mouse every frame
+/// put it on an entity with a `Sprite` and hide the system cursor to use an image as the cursor,
+/// it is drawn at the render resolution like everything else so it matches pixel art
+/// the entity should not have a parent, because its transform is set in world space
+#[derive(Clone, Copy)]
+pub struct CustomCursor;
+
+/// moves every entity with a `CustomCursor` to the mouse position, called by the event loop each frame
+pub(crate) fn update_custom_cursors(
+    entities_and_components: &mut EntitiesAndComponents,
+    mouse_position: [f32; 2],
+) {
+    let cursor_entities = entities_and_components
+        .get_entities_with_component::<CustomCursor>()
+        .cloned()
+        .collect::<Vec<Entity>>();
+
+    for entity in cursor_entities {
+        let (_, transform) = entities_and_components
+            .try_get_components_mut::<(CustomCursor, ABC_Game_Engine::Transform)>(entity);
+
+        if let Some(transform) = transform {
+            transform.x = mouse_position[0] as f64;
+            transform.y = mouse_position[1] as f64;
+        }
+    }
+}
+
+/// Something that happened to the window, collected from winit and given to the game once per frame
+#[derive(Clone, Debug, PartialEq)]
+pub enum WindowEvent {
+    /// the window gained (true) or lost (false) focus
+    Focused(bool),
+    /// the new inner size of the window in physical pixels
+    Resized([u32; 2]),
+    /// the new position of the window in physical pixels
+    Moved([i32; 2]),
+    /// the user tried to close the window, see `WindowEvents::veto_close` to keep it open
+    CloseRequested,
+    /// a file was dropped on the window, one event is sent per file
+    FileDropped(PathBuf),
+    /// a file is being dragged over the window
+    FileHovered(PathBuf),
+    /// the file being dragged over the window left without being dropped
+    FileHoverCancelled,
+    /// the window moved to a monitor with a different scale factor, or the scale factor changed
+    ScaleFactorChanged(f64),
+    /// the window is fully hidden by other windows (true) or visible again (false)
+    /// not every platform reports this
+    Occluded(bool),
+    /// the window was minimized (true) or restored (false)
+    Minimized(bool),
+    /// the app was sent to the background, this happens on mobile and web
+    Suspended,
+    /// the app came back from the background
+    Resumed,
+}
+
+/// A resource with the window events of the current frame
+/// events that happen between frames are queued up and given to the game all at once when the next frame starts
+pub struct WindowEvents {
+    /// events received since the last frame started
+    pending: Vec<WindowEvent>,
+    /// events of the current frame
+    events: Vec<WindowEvent>,
+    close_vetoed: bool,
+    focused: bool,
+    minimized: bool,
+    occluded: bool,
+}
+
+impl WindowEvents {
+    pub fn new() -> Self {
+        Self {
+            pending: Vec::new(),
+            events: Vec::new(),
+            close_vetoed: false,
+            focused: true,
+            minimized: false,
+            occluded: false,
+        }
+    }
+
+    /// adds an event to the queue for the next frame, this is done by the event loop
+    /// it can be used to fake events, like in tests
+    pub fn push_event(&mut self, event: WindowEvent) {
+        match event {
+            WindowEvent::Focused(focused) => self.focused = focused,
+            WindowEvent::Minimized(minimized) => self.minimized = minimized,
+            WindowEvent::Occluded(occluded) => self.occluded = occluded,
+            _ => (),
+        }
+
+        self.pending.push(event);
+    }
+
+    /// moves the queued events into the current frame, called by the event loop before the update function
+    pub fn start_frame(&mut self) {
+        self.events.clear();
+        self.events.append(&mut self.pending);
+        self.close_vetoed = false;
+    }
+
+    /// the events of the current frame in the order they happened
+    pub fn get_events(&self) -> &[WindowEvent] {
+        &self.events
+    }
+
+    /// get all the files dropped on the window this frame
+    pub fn get_dropped_files(&self) -> impl Iterator<Item = &PathBuf> {
+        self.events.iter().filter_map(|event| match event {
+            WindowEvent::FileDropped(path) => Some(path),
+            _ => None,
+        })
+    }
+
+    /// returns true if the user tried to close the window this frame
+    pub fn is_close_requested(&self) -> bool {
+        self.events.contains(&WindowEvent::CloseRequested)
+    }
+
+    /// keep the window open even though the user tried to close it this frame
+    /// this has to be called every frame the close is requested, for example to show a "save before quitting?" menu
+    pub fn veto_close(&mut self) {
+        self.close_vetoed = true;
+    }
+
+    /// returns true if the window should close at the end of this frame
+    pub fn should_close(&self) -> bool {
+        self.is_close_requested() && !self.close_vetoed
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    pub fn is_minimized(&self) -> bool {
+        self.minimized
+    }
+
+    /// returns true if the window is fully hidden by other windows, this is always false on some platforms
+    pub fn is_occluded(&self) -> bool {
+        self.occluded
+    }
+}
+
+impl Default for WindowEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Resource for WindowEvents {
+    fn update(&mut self) {}
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+/// A resource game code can use to close the program from inside the update function
+/// the event loop exits at the end of the frame the quit was requested in
+pub struct Quit {
+    requested: bool,
+}
+
+impl Quit {
+    pub fn new() -> Self {
+        Self { requested: false }
+    }
+
+    /// exit the event loop at the end of this frame
+    pub fn request(&mut self) {
+        self.requested = true;
+    }
+
+    /// take back a quit requested earlier in the same frame
+    pub fn cancel(&mut self) {
+        self.requested = false;
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.requested
+    }
+}
+
+impl Default for Quit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Resource for Quit {
+    fn update(&mut self) {}
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+/// turns a winit window event into a `WindowEvent` if the game should know about it
+pub(crate) fn window_event_from_winit(
+    event: &winit::event::WindowEvent,
+    window: &winit::window::Window,
+    was_minimized: bool,
+) -> Option<WindowEvent> {
+    match event {
+        winit::event::WindowEvent::Focused(focused) => Some(WindowEvent::Focused(*focused)),
+        winit::event::WindowEvent::Resized(size) => {
+            // there is no minimize event, but the window is resized when it is minimized or restored
+            // some platforms can't tell us if the window is minimized, so a size of 0 is used instead
+            let minimized = window
+                .is_minimized()
+                .unwrap_or(size.width == 0 && size.height == 0);
+
+            if minimized != was_minimized {
+                Some(WindowEvent::Minimized(minimized))
+            } else if minimized {
+                None
+            } else {
+                Some(WindowEvent::Resized([size.width, size.height]))
+            }
+        }
+        winit::event::WindowEvent::Moved(position) => {
+            Some(WindowEvent::Moved([position.x, position.y]))
+        }
+        winit::event::WindowEvent::CloseRequested => Some(WindowEvent::CloseRequested),
+        winit::event::WindowEvent::DroppedFile(path) => {
+            Some(WindowEvent::FileDropped(path.clone()))
+        }
+        winit::event::WindowEvent::HoveredFile(path) => {
+            Some(WindowEvent::FileHovered(path.clone()))
+        }
+        winit::event::WindowEvent::HoveredFileCancelled => Some(WindowEvent::FileHoverCancelled),
+        winit::event::WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+            Some(WindowEvent::ScaleFactorChanged(*scale_factor))
+        }
+        winit::event::WindowEvent::Occluded(occluded) => Some(WindowEvent::Occluded(*occluded)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_events_close_veto() {
+        let mut events = WindowEvents::new();
+        events.push_event(WindowEvent::Focused(false));
+        events.push_event(WindowEvent::CloseRequested);
+
+        // nothing is visible until the frame starts
+        assert!(events.get_events().is_empty());
+        assert!(!events.is_focused());
+
+        events.start_frame();
+        assert_eq!(events.get_events().len(), 2);
+        assert!(events.should_close());
+
+        events.veto_close();
+        assert!(events.is_close_requested());
+        assert!(!events.should_close());
+
+        // the veto only lasts for the frame it was made in
+        events.push_event(WindowEvent::CloseRequested);
+        events.start_frame();
+        assert!(events.should_close());
+
+        events.start_frame();
+        assert!(events.get_events().is_empty());
+        assert!(!events.should_close());
+    }
+}
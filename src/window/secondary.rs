@@ -0,0 +1,368 @@
+//! More windows for tools and debug views, like a level editor's palette or a G-buffer viewer
+//! every window shows images drawn by the main window's renderer, so they share the world and its textures
+//! lumenpyx owns the main window's OpenGL context so it can't be shared with the other windows,
+//! instead the image is copied out through a pixel buffer without waiting on the gpu, and shown a frame later
+
+use glium::glutin::surface::WindowSurface;
+use glium::texture::pixel_buffer::PixelBuffer;
+use glium::texture::RawImage2d;
+use glium::{Rect, Texture2d};
+use winit::window::{Window, WindowId};
+use ABC_Game_Engine::{EntitiesAndComponents, Resource};
+
+use crate::pipeline::{present_texture, CameraPass, RenderBuffer, MAIN_TARGET};
+use crate::resolution::window_to_render_position;
+use crate::window::{window_event_from_winit, WindowEvents};
+use crate::{get_target_camera_pos, CameraTarget, LumenpyxProgram};
+
+/// What a secondary window shows
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindowView {
+    /// what the camera with `CameraTarget::Window` of this window sees
+    Camera,
+    /// the same image as the main window
+    Mirror,
+    /// one of the buffers of the main window's render, like the normals or the height
+    Buffer(RenderBuffer),
+}
+
+/// An image on its way from the main window's context to a secondary window
+struct PendingImage {
+    pixel_buffer: PixelBuffer<(u8, u8, u8, u8)>,
+    /// the size of the middle of the image to show
+    visible_size: [u32; 2],
+}
+
+/// A window made with `LumenpyxEventLoop::add_window`
+pub struct SecondaryWindow {
+    window: Window,
+    display: glium::Display<WindowSurface>,
+    view: WindowView,
+    /// the resolution the camera of this window renders at
+    resolution: [u32; 2],
+    /// the name of the textures the camera of this window renders to
+    target: String,
+    events: WindowEvents,
+    /// the last cursor position winit gave us, in physical pixels from the top left of the window
+    cursor_position: Option<[f64; 2]>,
+    /// the mouse position of the current frame in world space
+    mouse_position: Option<[f32; 2]>,
+    /// the image being copied out of the main window's context
+    pending_image: Option<PendingImage>,
+    /// the last image shown, kept so a texture isn't made every frame
+    texture: Option<Texture2d>,
+}
+
+impl SecondaryWindow {
+    pub fn get_id(&self) -> WindowId {
+        self.window.id()
+    }
+
+    /// the winit window, for things like the title, position and visibility
+    pub fn get_window(&self) -> &Window {
+        &self.window
+    }
+
+    pub fn set_view(&mut self, view: WindowView) {
+        self.view = view;
+    }
+
+    pub fn get_view(&self) -> WindowView {
+        self.view
+    }
+
+    /// set the resolution the camera of this window renders at
+    pub fn set_resolution(&mut self, resolution: [u32; 2]) {
+        self.resolution = resolution;
+    }
+
+    pub fn get_resolution(&self) -> [u32; 2] {
+        self.resolution
+    }
+
+    /// the events of this window, the main window's are in the `WindowEvents` resource
+    pub fn get_events(&self) -> &WindowEvents {
+        &self.events
+    }
+
+    /// the events of this window, mutable so closing the window can be vetoed
+    pub fn get_events_mut(&mut self) -> &mut WindowEvents {
+        &mut self.events
+    }
+
+    /// the mouse position in the world of this window's camera
+    /// None if the cursor hasn't been over the window or the window doesn't show a camera
+    pub fn get_mouse_position(&self) -> Option<[f32; 2]> {
+        self.mouse_position
+    }
+
+    /// copies an image read back from the main window's context into this window's and shows it
+    fn show_image(&mut self, image: PendingImage) {
+        let PendingImage {
+            pixel_buffer,
+            visible_size,
+        } = image;
+        let Ok(image) = pixel_buffer.read_as_texture_2d::<RawImage2d<u8>>() else {
+            return;
+        };
+        let (width, height) = (image.width, image.height);
+
+        match &self.texture {
+            Some(texture) if texture.dimensions() == (width, height) => {
+                texture.write(
+                    Rect {
+                        left: 0,
+                        bottom: 0,
+                        width,
+                        height,
+                    },
+                    image,
+                );
+            }
+            _ => {
+                self.texture = Some(
+                    Texture2d::new(&self.display, image).expect("failed to copy texture to window"),
+                );
+            }
+        }
+
+        if let Some(texture) = &self.texture {
+            present_texture(&self.display, texture, visible_size, false);
+        }
+    }
+}
+
+/// A resource with every window other than the main one
+pub struct SecondaryWindows {
+    windows: Vec<SecondaryWindow>,
+    /// used to give every window's textures a different name
+    next_target_id: u32,
+}
+
+impl SecondaryWindows {
+    pub fn new() -> Self {
+        Self {
+            windows: vec![],
+            next_target_id: 0,
+        }
+    }
+
+    pub(crate) fn add_window(
+        &mut self,
+        window: Window,
+        display: glium::Display<WindowSurface>,
+        view: WindowView,
+        resolution: [u32; 2],
+    ) -> WindowId {
+        let id = window.id();
+
+        self.windows.push(SecondaryWindow {
+            window,
+            display,
+            view,
+            resolution,
+            target: format!("window_{}", self.next_target_id),
+            events: WindowEvents::new(),
+            cursor_position: None,
+            mouse_position: None,
+            pending_image: None,
+            texture: None,
+        });
+        self.next_target_id += 1;
+
+        id
+    }
+
+    pub fn get_window(&self, id: WindowId) -> Option<&SecondaryWindow> {
+        self.windows.iter().find(|window| window.get_id() == id)
+    }
+
+    pub fn get_window_mut(&mut self, id: WindowId) -> Option<&mut SecondaryWindow> {
+        self.windows.iter_mut().find(|window| window.get_id() == id)
+    }
+
+    pub fn get_window_ids(&self) -> Vec<WindowId> {
+        self.windows.iter().map(|window| window.get_id()).collect()
+    }
+
+    /// closes a window, returns false if there is no window with this id
+    pub fn close_window(&mut self, id: WindowId) -> bool {
+        let window_count = self.windows.len();
+        self.windows.retain(|window| window.get_id() != id);
+        self.windows.len() != window_count
+    }
+}
+
+impl Default for SecondaryWindows {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Resource for SecondaryWindows {
+    fn update(&mut self) {}
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+/// the cameras that need to be drawn for the secondary windows
+pub(crate) fn get_camera_passes(scene: &EntitiesAndComponents) -> Vec<CameraPass> {
+    let Some(windows) = scene.get_resource::<SecondaryWindows>() else {
+        return vec![];
+    };
+
+    windows
+        .windows
+        .iter()
+        .filter(|window| window.view == WindowView::Camera)
+        .filter_map(|window| {
+            let camera_pos = get_target_camera_pos(scene, CameraTarget::Window(window.get_id()))?;
+
+            Some(CameraPass {
+                target: window.target.clone(),
+                resolution: window.resolution,
+                camera: lumenpyx::Camera::new(camera_pos),
+                output: None,
+            })
+        })
+        .collect()
+}
+
+/// handles an event for a secondary window, returns false if the window doesn't exist
+pub(crate) fn handle_secondary_window_event(
+    windows: &mut SecondaryWindows,
+    id: WindowId,
+    event: &winit::event::WindowEvent,
+) -> bool {
+    let Some(window) = windows.get_window_mut(id) else {
+        return false;
+    };
+
+    let was_minimized = window.events.is_minimized();
+    if let Some(event) = window_event_from_winit(event, &window.window, was_minimized) {
+        window.events.push_event(event);
+    }
+
+    match event {
+        winit::event::WindowEvent::Resized(size) => {
+            window.display.resize((*size).into());
+        }
+        winit::event::WindowEvent::CursorMoved { position, .. } => {
+            window.cursor_position = Some([position.x, position.y]);
+        }
+        _ => (),
+    }
+
+    true
+}
+
+/// gives the secondary windows their events and mouse positions for this frame
+pub(crate) fn start_secondary_windows_frame(scene: &mut EntitiesAndComponents) {
+    let camera_positions = match scene.get_resource::<SecondaryWindows>() {
+        Some(windows) => windows
+            .windows
+            .iter()
+            .map(|window| get_target_camera_pos(scene, CameraTarget::Window(window.get_id())))
+            .collect::<Vec<_>>(),
+        None => return,
+    };
+
+    let windows = scene
+        .get_resource_mut::<SecondaryWindows>()
+        .expect("failed to get secondary windows");
+
+    for (window, camera_pos) in windows.windows.iter_mut().zip(camera_positions) {
+        window.events.start_frame();
+
+        window.mouse_position = None;
+        if window.view != WindowView::Camera {
+            continue;
+        }
+
+        let (Some([cursor_x, cursor_y]), Some(camera_pos)) = (window.cursor_position, camera_pos)
+        else {
+            continue;
+        };
+
+        let inner_size = window.window.inner_size();
+        if inner_size.width == 0 || inner_size.height == 0 {
+            continue;
+        }
+
+        // the y axis is flipped in lumen compared to winit
+        let local_x = (cursor_x / inner_size.width as f64).clamp(0.0, 1.0) - 0.5;
+        let local_y = ((inner_size.height as f64 - cursor_y) / inner_size.height as f64)
+            .clamp(0.0, 1.0)
+            - 0.5;
+
+        let [render_x, render_y] = window_to_render_position(
+            [local_x, local_y],
+            inner_size.into(),
+            window.resolution,
+            false,
+        );
+
+        window.mouse_position = Some([
+            (render_x + camera_pos[0] as f64) as f32,
+            (render_y + camera_pos[1] as f64) as f32,
+        ]);
+    }
+}
+
+/// closes the windows the game didn't keep open, and shows the latest render in the rest
+pub(crate) fn finish_secondary_windows_frame(scene: &mut EntitiesAndComponents) {
+    let Some(windows) = scene.get_resource_mut::<SecondaryWindows>() else {
+        return;
+    };
+
+    windows
+        .windows
+        .retain(|window| !window.events.should_close());
+
+    // taken out so the windows can change while the program is borrowed
+    let mut window_list = std::mem::take(&mut windows.windows);
+
+    let program = scene
+        .get_resource::<LumenpyxProgram>()
+        .expect("failed to get lumen program");
+
+    for window in &mut window_list {
+        if window.events.is_minimized() {
+            continue;
+        }
+
+        // the copy started last frame has had a whole frame to finish, so reading it doesn't wait
+        if let Some(image) = window.pending_image.take() {
+            window.show_image(image);
+        }
+
+        let (target, buffer, visible_size) = match window.view {
+            WindowView::Camera => (
+                window.target.as_str(),
+                RenderBuffer::Final,
+                window.resolution,
+            ),
+            WindowView::Mirror => (MAIN_TARGET, RenderBuffer::Final, program.get_resolution()),
+            WindowView::Buffer(buffer) => (MAIN_TARGET, buffer, program.get_resolution()),
+        };
+
+        // nothing has been rendered for this window yet
+        let Some(texture) = program.get_target_texture(target, buffer) else {
+            continue;
+        };
+
+        window.pending_image = Some(PendingImage {
+            pixel_buffer: texture.read_to_pixel_buffer(),
+            visible_size,
+        });
+    }
+
+    scene
+        .get_resource_mut::<SecondaryWindows>()
+        .expect("failed to get secondary windows")
+        .windows = window_list;
+}